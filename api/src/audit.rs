@@ -0,0 +1,199 @@
+use std::pin::Pin;
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::{
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::PayloadError,
+    http::Method,
+    middleware::Next,
+    web,
+    Error,
+    HttpMessage
+};
+use async_graphql::parser::{parse_query, types::OperationType};
+use futures_util::{stream, Stream, StreamExt};
+use serde_json::{json, Value};
+use crate::AppState;
+use crate::auth::{
+    path_setting,
+    CurrentUser,
+    READ_ONLY_POSTS
+};
+use grpg_core::db::audit::AuditEntry;
+
+/// Request bodies are recorded up to this many bytes.
+const MAX_RECORDED_BODY: usize = 4096;
+/// GraphQL bodies are read up to this many bytes to find their operation.
+const MAX_GRAPHQL_BODY: usize = 1 << 20;
+/// Fields whose name contains any of these are recorded as `***`.
+const SECRETS: [&str; 3] = ["password", "secret", "token"];
+
+/// Reads the start of the request body, at most `limit` bytes unless a chunk
+/// goes past it, and puts it back in front of the rest of the payload so the
+/// endpoint still reads the whole body. Answers whether the body was read
+/// completely.
+async fn peek_body(req: &mut ServiceRequest, limit: usize) -> Result<(Vec<u8>, bool), Error> {
+    let mut payload = req.take_payload();
+    let mut chunks = Vec::new();
+    let mut read = 0;
+    let mut complete = true;
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        read += chunk.len();
+        chunks.push(chunk);
+        if read > limit {
+            complete = false;
+            break;
+        }
+    }
+    let body = chunks.concat();
+    let forwarded: Pin<Box<dyn Stream<Item = Result<web::Bytes, PayloadError>>>> =
+        Box::pin(stream::iter(chunks.into_iter().map(Ok)).chain(payload));
+    req.set_payload(Payload::from(forwarded));
+    Ok((body, complete))
+}
+
+/// Whether the GraphQL operation selected by `request` mutates. Documents
+/// with several operations and no `operationName` count as mutations when
+/// any of them does.
+fn is_graphql_mutation(request: &Value) -> bool {
+    let Some(Ok(document)) = request.get("query").and_then(Value::as_str).map(parse_query) else {
+        return false;
+    };
+    let selected = request.get("operationName").and_then(Value::as_str);
+    document.operations.iter()
+        .filter(|(name, _)| selected.is_none_or(|s| name.is_some_and(|n| n.as_str() == s)))
+        .any(|(_, operation)| operation.node.ty == OperationType::Mutation)
+}
+
+/// Whether the request is recorded. Bodies of GraphQL requests too large to
+/// be read are recorded, as they may hold a mutation.
+fn is_mutation(path: &str, body: Option<&Value>, complete: bool) -> bool {
+    if path == "/graphql" {
+        return !complete || match body {
+            Some(Value::Array(batch)) => batch.iter().any(is_graphql_mutation),
+            Some(request) => is_graphql_mutation(request),
+            None => false,
+        };
+    }
+    !(path == "/auth/login" || path == "/auth/logout" || READ_ONLY_POSTS.iter().any(|p| path.ends_with(p)))
+}
+
+/// Replaces the secrets found at any depth of the value.
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                let key = key.to_lowercase();
+                if SECRETS.iter().any(|s| key.contains(s)) {
+                    *value = json!("***");
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Operation name and variables of a GraphQL request. The document is left
+/// out, as its inline arguments may hold secrets.
+fn graphql_parameters(request: &Value) -> Value {
+    json!({
+        "operationName": request.get("operationName"),
+        "variables": request.get("variables"),
+    })
+}
+
+/// Body of the request as JSON when it parses, secrets left out.
+fn parameters(path: &str, query: &str, body: &[u8], parsed: Option<Value>, complete: bool) -> Value {
+    let mut body = match parsed {
+        _ if !complete => json!("<body too large to record>"),
+        Some(Value::Array(batch)) if path == "/graphql" => batch.iter().map(graphql_parameters).collect(),
+        Some(request) if path == "/graphql" => graphql_parameters(&request),
+        Some(value) => value,
+        None if body.is_empty() => Value::Null,
+        None => Value::String(String::from_utf8_lossy(body).into_owned()),
+    };
+    redact(&mut body);
+    if serde_json::to_vec(&body).map_or(0, |b| b.len()) > MAX_RECORDED_BODY {
+        body = json!("<body too large to record>");
+    }
+    json!({ "query": query, "body": body })
+}
+
+/// Records every mutating request, with the user making it and the status
+/// it was answered with, into the audit log.
+pub async fn record(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.call(req).await;
+    }
+    let Some(app_state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return next.call(req).await;
+    };
+    let is_graphql = req.path() == "/graphql";
+    if !is_graphql && !is_mutation(req.path(), None, true) {
+        return next.call(req).await;
+    }
+    let limit = if is_graphql { MAX_GRAPHQL_BODY } else { MAX_RECORDED_BODY };
+    let (body, complete) = peek_body(&mut req, limit).await?;
+    let parsed = if complete { serde_json::from_slice::<Value>(&body).ok() } else { None };
+    if !is_mutation(req.path(), parsed.as_ref(), complete) {
+        return next.call(req).await;
+    }
+    let actor = req.extensions().get::<CurrentUser>().map(|u| u.id());
+    let operation = format!("{} {}", req.method(), req.match_pattern().unwrap_or_else(|| req.path().to_string()));
+    let path = req.path().to_string();
+    let parameters = parameters(&path, req.query_string(), &body, parsed, complete);
+    let response = next.call(req).await?;
+    let entry = AuditEntry {
+        id: 0,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        actor,
+        setting: path_setting(&path),
+        operation,
+        path,
+        parameters,
+        status: response.status().as_u16(),
+    };
+    if let Err(e) = app_state.audit.append(entry) {
+        log::error!("Failed to write audit entry: {}", e);
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_the_graphql_operation() {
+        let document = "# mutation in a comment\nquery Read { me { id } }\nmutation Write { deleteNode(id: 1) }";
+        assert!(!is_graphql_mutation(&json!({ "query": document, "operationName": "Read" })));
+        assert!(is_graphql_mutation(&json!({ "query": document, "operationName": "Write" })));
+        assert!(is_graphql_mutation(&json!({ "query": document })));
+        assert!(!is_graphql_mutation(&json!({ "query": "fragment F on User { id } { me { ...F } }" })));
+        assert!(is_graphql_mutation(&json!({ "query": "  # note\n mutation { logout }" })));
+    }
+
+    #[test]
+    fn leaves_secrets_and_graphql_documents_out() {
+        let body = json!({
+            "query": "mutation { createUser(name: \"a\", password: \"hunter2\") { id } }",
+            "operationName": null,
+            "variables": { "input": { "name": "a", "newPassword": "hunter2" } }
+        });
+        let recorded = parameters("/graphql", "", &[], Some(body), true).to_string();
+        assert!(!recorded.contains("hunter2"));
+        assert!(!recorded.contains("createUser"));
+
+        let body = json!({ "webhook": { "url": "http://x", "secret": "s3cr3t" } });
+        let recorded = parameters("/settings/1/webhooks", "", &[], Some(body), true).to_string();
+        assert!(!recorded.contains("s3cr3t"));
+        assert!(recorded.contains("http://x"));
+    }
+}
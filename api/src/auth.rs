@@ -0,0 +1,231 @@
+use std::future::{ready, Ready};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{header, Method},
+    middleware::Next,
+    web,
+    Error,
+    FromRequest,
+    HttpMessage,
+    HttpRequest,
+    ResponseError
+};
+use crate::AppState;
+use crate::error::ApiError;
+use grpg_core::db::error::DbError;
+use grpg_core::db::base::API_TOKEN_PREFIX;
+use grpg_core::db::models::{
+    Role,
+    TokenScope,
+    UserData
+};
+
+pub const SESSION_COOKIE: &str = "grpg_session";
+
+/// Routes answered with POST that only read.
+pub const READ_ONLY_POSTS: [&str; 3] = ["/sparql", "/cypher", "/graphql"];
+
+/// The user the request was authenticated as. Extracting it from a request
+/// without a valid session or API token answers 401, use
+/// `Option<CurrentUser>` where the endpoint is also open to anonymous callers.
+#[derive(Clone)]
+pub struct CurrentUser {
+    pub user: UserData,
+    /// Sessions have every scope, API tokens the one they were created with
+    pub scope: TokenScope,
+    /// Settings an API token is limited to
+    pub settings: Option<Vec<i32>>,
+}
+
+impl CurrentUser {
+    pub fn id(&self) -> i32 {
+        self.user.id
+    }
+    pub fn can_access(&self, setting_id: i32) -> bool {
+        self.settings.as_ref().is_none_or(|s| s.contains(&setting_id))
+    }
+    /// Checks the request against the scope and settings of the credentials.
+    fn authorize(&self, required: TokenScope, path: &str) -> Result<(), &'static str> {
+        if self.scope < required {
+            return Err("Token scope does not allow this request");
+        }
+        if self.settings.is_some() && path.trim_start_matches('/').starts_with("settings") {
+            match path_setting(path) {
+                Some(setting_id) if self.can_access(setting_id) => {}
+                Some(_) => return Err("Token is not valid for this setting"),
+                None if required > TokenScope::Read => return Err("Token is limited to specific settings"),
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scope a request needs, by its method and route.
+fn required_scope(method: &Method, path: &str) -> TokenScope {
+    if path.starts_with("/tokens")
+        || path.starts_with("/admin/")
+        || path.contains("/webhooks")
+        || path.contains("/members")
+        || path.contains("/shares")
+        || path.contains("/audit")
+        // Inviting to an organization manages its members, while answering
+        // one's own invitations under `/invitations` is scoped by method
+        || (path.starts_with("/organizations/") && path.ends_with("/invitations")) {
+        TokenScope::Admin
+    } else if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        || READ_ONLY_POSTS.iter().any(|p| path.ends_with(p)) {
+        TokenScope::Read
+    } else {
+        TokenScope::ReadWrite
+    }
+}
+
+/// Setting of a `/settings/{setting_id}/...` route.
+pub fn path_setting(path: &str) -> Option<i32> {
+    let mut segments = path.trim_start_matches('/').split('/');
+    match segments.next() {
+        Some("settings") => segments.next().and_then(|s| s.parse().ok()),
+        _ => None,
+    }
+}
+
+/// Session token or API token sent as a bearer token, or the session cookie.
+pub fn request_token(req: &HttpRequest) -> Option<String> {
+    let bearer = req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(|t| t.trim().to_string());
+    bearer.or_else(|| req.cookie(SESSION_COOKIE).map(|c| c.value().to_string()))
+}
+
+fn resolve(app_state: &AppState, token: &str) -> Result<Option<CurrentUser>, DbError> {
+    Ok(if token.starts_with(API_TOKEN_PREFIX) {
+        app_state.store.get_api_token_user(token)?.map(|(user, api_token)| CurrentUser {
+            user,
+            scope: api_token.scope,
+            settings: api_token.settings,
+        })
+    } else {
+        app_state.store.get_session_user(token)?.map(|user| CurrentUser {
+            user,
+            scope: TokenScope::Admin,
+            settings: None,
+        })
+    })
+}
+
+/// Why the user may not make a request needing `required` on the setting,
+/// unless they may.
+fn setting_denial(app_state: &AppState, user: &CurrentUser, setting_id: i32, required: TokenScope) -> Result<Option<ApiError>, DbError> {
+    Ok(match app_state.store.conn(setting_id).get_role(user.id())? {
+        None if app_state.store.get_setting(setting_id)?.is_none() => {
+            Some(ApiError::not_found("Setting not found"))
+        }
+        role if role < Some(Role::for_scope(required)) => {
+            Some(ApiError::forbidden("Your role on this setting does not allow this request"))
+        }
+        _ => None,
+    })
+}
+
+/// Resolves the session or API token of every request into a `CurrentUser`,
+/// rejects requests outside the token's scope and requires a role on the
+/// setting of every `/settings/{setting_id}/...` route and to be one of the
+/// configured administrators on `/admin/...` routes. Other requests without
+/// valid credentials go through anonymously.
+pub async fn resolve_user(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let Some(app_state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    let user = match request_token(req.request()).map(|token| resolve(&app_state, &token)).transpose() {
+        Ok(user) => user.flatten(),
+        Err(e) => return Ok(req.into_response(ApiError::from(e).error_response()).map_into_right_body()),
+    };
+    let required = required_scope(req.method(), req.path());
+    let token_check = user.as_ref().map(|u| u.authorize(required, req.path()));
+    let admin_route = req.path().starts_with("/admin/");
+    let denied = if let Some(Err(message)) = token_check {
+        Some(ApiError::forbidden(message))
+    } else {
+        match (&user, path_setting(req.path())) {
+            (None, _) if admin_route => Some(ApiError::unauthorized("Not logged in")),
+            (Some(user), _) if admin_route && !app_state.admins.contains(&user.user.name) => {
+                Some(ApiError::forbidden("Only administrators of the instance can do this"))
+            }
+            (None, Some(_)) => Some(ApiError::unauthorized("Not logged in")),
+            (Some(user), Some(setting_id)) => {
+                setting_denial(&app_state, user, setting_id, required).unwrap_or_else(|e| Some(e.into()))
+            }
+            _ => None,
+        }
+    };
+    if let Some(error) = denied {
+        return Ok(req.into_response(error.error_response()).map_into_right_body());
+    }
+    if let Some(user) = user {
+        req.extensions_mut().insert(user);
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+impl FromRequest for CurrentUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.extensions()
+            .get::<CurrentUser>()
+            .cloned()
+            .ok_or_else(|| ApiError::unauthorized("Not logged in").into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(scope: TokenScope, settings: Option<Vec<i32>>) -> CurrentUser {
+        CurrentUser { user: UserData { id: 1, name: "ada".to_string() }, scope, settings }
+    }
+
+    #[test]
+    fn scopes_follow_method_and_route() {
+        assert!(required_scope(&Method::GET, "/settings/1/node") == TokenScope::Read);
+        assert!(required_scope(&Method::POST, "/settings/1/sparql") == TokenScope::Read);
+        assert!(required_scope(&Method::POST, "/graphql") == TokenScope::Read);
+        assert!(required_scope(&Method::POST, "/settings/1/node") == TokenScope::ReadWrite);
+        assert!(required_scope(&Method::DELETE, "/settings/1/node/2") == TokenScope::ReadWrite);
+        for path in ["/tokens", "/settings/1/webhooks", "/settings/1/members", "/settings/1/shares", "/settings/1/audit", "/organizations/1/invitations", "/admin/backup"] {
+            assert!(required_scope(&Method::GET, path) == TokenScope::Admin, "{}", path);
+        }
+        assert!(required_scope(&Method::GET, "/invitations") == TokenScope::Read);
+        assert!(required_scope(&Method::POST, "/invitations/1/accept") == TokenScope::ReadWrite);
+    }
+
+    #[test]
+    fn tokens_are_held_to_their_scope_and_settings() {
+        assert!(token(TokenScope::Read, None).authorize(TokenScope::Read, "/settings/1/node").is_ok());
+        assert!(token(TokenScope::Read, None).authorize(TokenScope::ReadWrite, "/settings/1/node").is_err());
+        assert!(token(TokenScope::ReadWrite, None).authorize(TokenScope::Admin, "/tokens").is_err());
+
+        let limited = token(TokenScope::ReadWrite, Some(vec![1]));
+        assert!(limited.authorize(TokenScope::ReadWrite, "/settings/1/node").is_ok());
+        assert_eq!(limited.authorize(TokenScope::Read, "/settings/2/node"), Err("Token is not valid for this setting"));
+        assert!(limited.authorize(TokenScope::Read, "/settings").is_ok());
+        assert_eq!(limited.authorize(TokenScope::ReadWrite, "/settings"), Err("Token is limited to specific settings"));
+    }
+
+    #[test]
+    fn finds_the_setting_of_a_route() {
+        assert_eq!(path_setting("/settings/12/table/3"), Some(12));
+        assert_eq!(path_setting("/settings"), None);
+        assert_eq!(path_setting("/settings/import"), None);
+        assert_eq!(path_setting("/shared/abc"), None);
+    }
+}
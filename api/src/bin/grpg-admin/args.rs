@@ -0,0 +1,93 @@
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+
+/// Command line split into positional arguments, taken in order, and
+/// `--name value` options.
+pub struct Args {
+    positional: VecDeque<String>,
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut positional = VecDeque::new();
+        let mut options = HashMap::new();
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = args.next_if(|next| !next.starts_with("--"));
+                    if options.insert(name.to_string(), value).is_some() {
+                        return Err(format!("--{} given twice", name));
+                    }
+                }
+                None => positional.push_back(arg),
+            }
+        }
+        Ok(Args { positional, options })
+    }
+
+    /// Next positional argument, described as `what` when it is missing.
+    pub fn next(&mut self, what: &str) -> Result<String, String> {
+        self.positional.pop_front().ok_or_else(|| format!("Missing {}, see --help", what))
+    }
+    pub fn next_parsed<T: FromStr>(&mut self, what: &str) -> Result<T, String> {
+        let arg = self.next(what)?;
+        arg.parse().map_err(|_| format!("Invalid {} {}", what, arg))
+    }
+
+    pub fn flag(&mut self, name: &str) -> bool {
+        self.options.remove(name).is_some()
+    }
+    pub fn option(&mut self, name: &str) -> Option<String> {
+        self.options.remove(name).flatten()
+    }
+    pub fn option_parsed<T: FromStr>(&mut self, name: &str) -> Result<Option<T>, String> where T::Err: ToString {
+        self.option(name)
+            .map(|value| value.parse().map_err(|e: T::Err| format!("--{}: {}", name, e.to_string())))
+            .transpose()
+    }
+
+    /// Fails on the arguments no command took.
+    pub fn finish(self) -> Result<(), String> {
+        if let Some(arg) = self.positional.front() {
+            return Err(format!("Unexpected argument {}", arg));
+        }
+        if let Some(name) = self.options.keys().next() {
+            return Err(format!("Unknown option --{}", name));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn takes_arguments_in_order_and_options_by_name() {
+        let mut args = parse(&["settings", "--format", "gexf", "export", "3", "--check"]).unwrap();
+        assert_eq!(args.next("command").unwrap(), "settings");
+        assert_eq!(args.next("settings command").unwrap(), "export");
+        assert_eq!(args.next_parsed::<i32>("setting id").unwrap(), 3);
+        assert_eq!(args.option("format").as_deref(), Some("gexf"));
+        assert!(args.flag("check"));
+        assert!(!args.flag("check"));
+        assert_eq!(args.next("file").err().unwrap(), "Missing file, see --help");
+        assert!(args.finish().is_ok());
+    }
+
+    #[test]
+    fn rejects_what_no_command_takes() {
+        assert_eq!(parse(&["--config", "a", "--config", "b"]).err().unwrap(), "--config given twice");
+        assert_eq!(parse(&["restore", "x"]).unwrap().next_parsed::<i32>("user id").err().unwrap(), "Invalid user id restore");
+        let mut args = parse(&["--into", "x"]).unwrap();
+        assert_eq!(args.option_parsed::<i32>("into").err().unwrap(), "--into: invalid digit found in string");
+        assert_eq!(parse(&["check", "now"]).unwrap().finish().err().unwrap(), "Unexpected argument check");
+        assert_eq!(parse(&["--verbose"]).unwrap().finish().err().unwrap(), "Unknown option --verbose");
+    }
+}
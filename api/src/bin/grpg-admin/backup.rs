@@ -0,0 +1,33 @@
+use std::fs::File;
+use std::io;
+use grpg_core::db::backup::Backup;
+use crate::Instance;
+use crate::args::Args;
+
+/// Writes the backup to the file, or to the standard output.
+pub fn backup(instance: &mut Instance, mut args: Args) -> Result<(), String> {
+    let output = args.option("output");
+    args.finish()?;
+    let backend = instance.backend()?;
+    let backup = Backup::take(&instance.store, backend.as_ref()).map_err(|e| e.to_string())?;
+    match output {
+        Some(path) => {
+            let file = File::create(&path).map_err(|e| format!("{}: {}", path, e))?;
+            backup.write(file).map_err(|e| e.to_string())?;
+            eprintln!("Backed up {} to {}", serde_json::to_string(&backup.counts).unwrap_or_default(), path);
+        }
+        None => backup.write(io::stdout().lock()).map_err(|e| e.to_string())?,
+    }
+    Ok(())
+}
+
+pub fn restore(instance: &mut Instance, mut args: Args) -> Result<(), String> {
+    let path = args.next("backup file")?;
+    args.finish()?;
+    let file = File::open(&path).map_err(|e| format!("{}: {}", path, e))?;
+    let backup = Backup::read(file).map_err(|e| e.to_string())?;
+    let backend = instance.backend()?;
+    let counts = backup.restore(&instance.store, backend.as_ref()).map_err(|e| e.to_string())?;
+    println!("Restored {}", serde_json::to_string(&counts).unwrap_or_default());
+    Ok(())
+}
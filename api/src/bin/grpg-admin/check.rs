@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+use grpg_core::db::error::DbError;
+use grpg_core::db::graph::GraphBackend;
+use crate::Instance;
+use crate::args::Args;
+
+/// Problems of one setting: references from its metadata or triples to
+/// users, organizations, nodes, predicates and tables that do not exist.
+fn check_setting(instance: &Instance, backend: &dyn GraphBackend, setting_id: i32) -> Result<Vec<String>, DbError> {
+    let mut problems = Vec::new();
+    let store = &instance.store;
+    let Some(setting) = store.get_setting(setting_id)? else {
+        return Ok(problems);
+    };
+    let users: HashSet<i32> = store.get_users()?.into_iter().map(|u| u.id).collect();
+    if !users.contains(&setting.user_id) {
+        problems.push(format!("owner {} does not exist", setting.user_id));
+    }
+    for member in setting.members.keys().filter(|id| !users.contains(id)) {
+        problems.push(format!("member {} does not exist", member));
+    }
+    if let Some(organization) = setting.organization_id
+        && store.organization(organization).get()?.is_none() {
+        problems.push(format!("organization {} does not exist", organization));
+    }
+
+    let nodes: HashSet<i32> = backend.node_all(setting_id)?.into_iter().map(|n| n.node_id).collect();
+    let predicates: HashSet<i32> = backend.predicate_all(setting_id)?.into_iter().map(|p| p.id).collect();
+    for triple in backend.triple_all(setting_id)? {
+        let (s, p, o) = (triple.subject_id, triple.predicate_id, triple.object_id);
+        for (role, id, known) in [("subject", s, &nodes), ("predicate", p, &predicates), ("object", o, &nodes)] {
+            if !known.contains(&id) {
+                problems.push(format!("triple ({}, {}, {}) has a missing {} {}", s, p, o, role, id));
+            }
+        }
+    }
+
+    let conn = store.conn(setting_id);
+    let tables = conn.get_tables()?.unwrap_or_default();
+    for (table_id, table) in &tables {
+        if let Some(node_id) = table.filter.node_id.filter(|id| !nodes.contains(id)) {
+            problems.push(format!("table {} filters on the missing node {}", table_id, node_id));
+        }
+        let column_predicates = table.columns.iter().filter_map(|c| c.filter.predicate_id);
+        for predicate in table.filter.predicate.into_iter().chain(column_predicates) {
+            if !predicates.contains(&predicate) {
+                problems.push(format!("table {} uses the missing predicate {}", table_id, predicate));
+            }
+        }
+    }
+    for (share_id, share) in conn.get_shares()?.unwrap_or_default() {
+        if let Some(table_id) = share.table_id.filter(|id| !tables.contains_key(id)) {
+            problems.push(format!("share link {} shares the missing table {}", share_id, table_id));
+        }
+    }
+    Ok(problems)
+}
+
+/// Prints every problem found, failing when there is any.
+pub fn run(instance: &mut Instance, args: Args) -> Result<(), String> {
+    args.finish()?;
+    let backend = instance.backend()?;
+    let mut settings: Vec<i32> = instance.store.get_all_settings().map_err(|e| e.to_string())?.into_iter().map(|(id, _)| id).collect();
+    settings.sort();
+    let mut problems = 0;
+    for setting_id in &settings {
+        for problem in check_setting(instance, backend.as_ref(), *setting_id).map_err(|e| e.to_string())? {
+            println!("setting {}: {}", setting_id, problem);
+            problems += 1;
+        }
+    }
+    for orphan in backend.settings().map_err(|e| e.to_string())?.into_iter().filter(|id| !settings.contains(id)) {
+        println!("graph of setting {} has no setting in the store", orphan);
+        problems += 1;
+    }
+    if problems > 0 {
+        return Err(format!("{} problems found in {} settings", problems, settings.len()));
+    }
+    println!("No problems found in {} settings", settings.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use api::config::Config;
+    use grpg_core::db::events::Events;
+    use grpg_core::db::graph::MemoryBackend;
+    use grpg_core::db::models::{Quota, Triple};
+    use grpg_core::db::base::Store;
+    use super::*;
+
+    #[test]
+    fn reports_triples_with_missing_predicates() {
+        let events = Arc::new(Events::default());
+        let store = Store::in_memory(events.clone(), Quota::default());
+        let instance = Instance { config: Config::default(), events, store, graph: None };
+        let backend = MemoryBackend::new();
+        let ada = instance.store.add_user("ada".to_string(), "secret").unwrap();
+        let setting = instance.store.user_settings(ada).add_setting("family".to_string(), None).unwrap();
+        let parent = backend.node_create(setting, "ada".to_string()).unwrap();
+        let child = backend.node_create(setting, "byron".to_string()).unwrap();
+        let predicate = backend.predicate_create(setting, "parent of").unwrap().id;
+        backend.triple_create(setting, Triple { subject_id: parent, predicate_id: predicate, object_id: child }).unwrap();
+        assert!(check_setting(&instance, &backend, setting).unwrap().is_empty());
+
+        backend.triple_create(setting, Triple { subject_id: parent, predicate_id: 9, object_id: child }).unwrap();
+        assert_eq!(
+            check_setting(&instance, &backend, setting).unwrap(),
+            [format!("triple ({}, 9, {}) has a missing predicate 9", parent, child)]
+        );
+        assert!(check_setting(&instance, &backend, setting + 1).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+//! Administration of a grpg instance, working directly on the database and
+//! store of its configuration rather than through the HTTP API. Commands
+//! touching the graph need the server stopped, as Kuzu allows a single
+//! process to write to its database.
+mod args;
+mod backup;
+mod check;
+mod migrate;
+mod schema;
+mod settings;
+mod users;
+use std::env;
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use api::config::{Config, GraphBackendKind};
+use grpg_core::db::base::Store;
+use grpg_core::db::events::Events;
+use grpg_core::db::graph::{
+    GraphBackend,
+    GraphManager
+};
+use crate::args::Args;
+
+const USAGE: &str = "Usage: grpg-admin [--config <file>] <command>
+
+Commands:
+  users list
+  users create <name> [--password <password>]
+  users password <user> [--password <password>]
+  settings list
+  settings rename <setting> <name>
+  settings delete <setting>
+  settings export <setting> [--format graphml|gexf|cytoscape|jsonld] [--output <file>]
+  settings import <file> (--into <setting> | --owner <user> [--name <name>])
+  check
+  schema
+  migrate [--check]
+  backup [--output <file>]
+  restore <file>
+
+Users created before logins were added have no password and cannot log in
+until one is set with `users password`, which also closes the user's sessions.
+
+Backups are gzipped JSON; a running server is backed up through its
+/admin/backup route instead. Restoring needs an empty store and graph.
+
+The configuration is read like the server's, from --config, GRPG_CONFIG or
+grpg.toml, with the GRPG_* environment overrides. Passwords not given are
+read from the standard input.";
+
+/// The store and graph of the configured instance, the graph opened on first use.
+pub struct Instance {
+    pub config: Config,
+    pub events: Arc<Events>,
+    pub store: Store,
+    graph: Option<Arc<dyn GraphBackend>>,
+}
+
+impl Instance {
+    fn open(config: Config) -> Result<Self, String> {
+        let events = Arc::new(Events::new());
+        let store = config.store(events.clone())?;
+        Ok(Instance { config, events, store, graph: None })
+    }
+    /// The graph backend, which must be Kuzu as the graphs of the memory
+    /// backend live in the server's process.
+    pub fn backend(&mut self) -> Result<Arc<dyn GraphBackend>, String> {
+        if self.config.storage.graph_backend == GraphBackendKind::Memory {
+            return Err("The memory graph backend cannot be reached outside of the server".to_string());
+        }
+        if self.graph.is_none() {
+            self.graph = Some(self.config.graph_backend()?);
+        }
+        Ok(self.graph.clone().unwrap())
+    }
+    pub fn graph<'a>(&'a self, backend: &'a dyn GraphBackend, setting: i32) -> GraphManager<'a> {
+        GraphManager {
+            backend,
+            setting,
+            events: &self.events,
+            store: &self.store
+        }
+    }
+}
+
+fn run(mut args: Args) -> Result<(), String> {
+    if args.flag("help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let config = match args.option("config") {
+        Some(path) => Config::load_file(Path::new(&path)),
+        None => Config::load(),
+    }.map_err(|e| format!("Invalid configuration:\n  {}", e))?;
+    let mut instance = Instance::open(config)?;
+    match args.next("command")?.as_str() {
+        "users" => users::run(&instance, args),
+        "settings" => settings::run(&mut instance, args),
+        "check" => check::run(&mut instance, args),
+        "schema" => schema::run(&mut instance, args),
+        "migrate" => migrate::run(&instance, args),
+        "backup" => backup::backup(&mut instance, args),
+        "restore" => backup::restore(&mut instance, args),
+        other => Err(format!("Unknown command {}\n\n{}", other, USAGE)),
+    }
+}
+
+fn main() {
+    let result = Args::parse(env::args().skip(1)).and_then(run);
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
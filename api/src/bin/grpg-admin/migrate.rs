@@ -0,0 +1,42 @@
+use crate::Instance;
+use crate::args::Args;
+
+/// Applies the pending migrations of the Kuzu schema, or with `--check`
+/// reports its version, failing when it is not current.
+#[cfg(feature = "kuzu")]
+pub fn run(instance: &Instance, mut args: Args) -> Result<(), String> {
+    use api::config::GraphBackendKind;
+    use grpg_core::db::graph::{KuzuBackend, SCHEMA_VERSION};
+
+    let check = args.flag("check");
+    args.finish()?;
+    if instance.config.storage.graph_backend != GraphBackendKind::Kuzu {
+        return Err("Only the kuzu graph backend has a schema to migrate".to_string());
+    }
+    let path = instance.config.storage.graph.to_string_lossy();
+    let backend = KuzuBackend::open_unchecked(&path, &instance.config.kuzu)
+        .map_err(|e| format!("{}: {}", path, e))?;
+    let version = backend.schema_version()?;
+    if check {
+        println!("Schema at version {}, this build knows version {}", version, SCHEMA_VERSION);
+        if version != SCHEMA_VERSION {
+            return Err("The schema is not current".to_string());
+        }
+        return Ok(());
+    }
+    if instance.config.kuzu.read_only {
+        return Err("The database is configured read-only".to_string());
+    }
+    let previous = backend.migrate()?;
+    if previous == SCHEMA_VERSION {
+        println!("Schema already at version {}", SCHEMA_VERSION);
+    } else {
+        println!("Migrated the schema from version {} to {}", previous, SCHEMA_VERSION);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "kuzu"))]
+pub fn run(_instance: &Instance, _args: Args) -> Result<(), String> {
+    Err("grpg-admin is built without the kuzu feature".to_string())
+}
@@ -0,0 +1,36 @@
+use crate::Instance;
+use crate::args::Args;
+
+/// Rows are printed up to this many per statement.
+const MAX_ROWS: usize = 1000;
+const TIMEOUT_MS: u64 = 30_000;
+
+/// Prints the node and rel tables of the Kuzu database with their properties.
+pub fn run(instance: &mut Instance, args: Args) -> Result<(), String> {
+    args.finish()?;
+    let backend = instance.backend()?;
+    let tables = backend.query("CALL show_tables() RETURN name, type ORDER BY name;", Vec::new(), MAX_ROWS, TIMEOUT_MS)
+        .map_err(|e| e.to_string())?;
+    for table in tables.rows {
+        let name = table[0].as_str().unwrap_or_default().to_string();
+        println!("{} ({})", name, table[1].as_str().unwrap_or_default());
+        let info = backend.query(
+            &format!("CALL table_info('{}') RETURN *;", name.replace('\'', "")),
+            Vec::new(),
+            MAX_ROWS,
+            TIMEOUT_MS
+        ).map_err(|e| e.to_string())?;
+        let name_column = info.columns.iter().position(|c| c == "name");
+        let type_column = info.columns.iter().position(|c| c == "type");
+        let primary_column = info.columns.iter().position(|c| c == "primary key");
+        for property in info.rows {
+            let text = |column: Option<usize>| column
+                .and_then(|c| property.get(c))
+                .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                .unwrap_or_default();
+            let primary = primary_column.and_then(|c| property[c].as_bool()).unwrap_or(false);
+            println!("  {}\t{}{}", text(name_column), text(type_column), if primary { "\tprimary key" } else { "" });
+        }
+    }
+    Ok(())
+}
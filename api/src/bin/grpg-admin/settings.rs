@@ -0,0 +1,93 @@
+use std::fs;
+use api::formats::graph::{
+    GraphFormat,
+    Subgraph
+};
+use api::formats::jsonld;
+use crate::Instance;
+use crate::args::Args;
+
+pub fn run(instance: &mut Instance, mut args: Args) -> Result<(), String> {
+    match args.next("settings command")?.as_str() {
+        "list" => {
+            args.finish()?;
+            let mut settings = instance.store.get_all_settings().map_err(|e| e.to_string())?;
+            settings.sort_by_key(|(id, _)| *id);
+            println!("id\towner\torganization\tname");
+            for (id, setting) in settings {
+                let organization = setting.organization_id.map_or("-".to_string(), |o| o.to_string());
+                println!("{}\t{}\t{}\t{}", id, setting.user_id, organization, setting.name);
+            }
+            Ok(())
+        }
+        "rename" => {
+            let setting_id = args.next_parsed("setting id")?;
+            let name = args.next("setting name")?;
+            args.finish()?;
+            instance.store.rename_setting(setting_id, name).map_err(|e| e.to_string())?.ok_or("Setting not found")?;
+            Ok(())
+        }
+        "delete" => {
+            let setting_id = args.next_parsed("setting id")?;
+            args.finish()?;
+            let backend = instance.backend()?;
+            instance.store.remove_setting(setting_id).map_err(|e| e.to_string())?.ok_or("Setting not found")?;
+            backend.setting_delete(setting_id).map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        "export" => {
+            let setting_id = args.next_parsed("setting id")?;
+            let format = args.option_parsed::<GraphFormat>("format")?.unwrap_or(GraphFormat::Jsonld);
+            let output = args.option("output");
+            args.finish()?;
+            instance.store.get_setting(setting_id).map_err(|e| e.to_string())?.ok_or("Setting not found")?;
+            let backend = instance.backend()?;
+            let graph = instance.graph(backend.as_ref(), setting_id);
+            let subgraph = Subgraph {
+                setting: setting_id,
+                nodes: graph.node_all().map_err(|e| e.to_string())?,
+                predicates: graph.predicate_all().map_err(|e| e.to_string())?,
+                triples: graph.triple_all().map_err(|e| e.to_string())?,
+            };
+            let document = subgraph.render(format);
+            match output {
+                Some(path) => fs::write(&path, document).map_err(|e| format!("{}: {}", path, e)),
+                None => {
+                    println!("{}", document);
+                    Ok(())
+                }
+            }
+        }
+        "import" => {
+            let path = args.next("file")?;
+            let into = args.option_parsed::<i32>("into")?;
+            let owner = args.option_parsed::<i32>("owner")?;
+            let name = args.option("name");
+            args.finish()?;
+            let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+            let document: serde_json::Value = serde_json::from_str(&text)
+                .map_err(|e| format!("{}: Invalid JSON: {}", path, e))?;
+            let backend = instance.backend()?;
+            let setting_id = match (into, owner) {
+                (Some(setting_id), None) => {
+                    instance.store.get_setting(setting_id).map_err(|e| e.to_string())?.ok_or("Setting not found")?;
+                    setting_id
+                }
+                (None, Some(owner)) => {
+                    instance.store.get_user(owner).map_err(|e| e.to_string())?.ok_or("User not found")?;
+                    let name = name.unwrap_or_else(|| path.clone());
+                    instance.store.user_settings(owner).add_setting(name, None).map_err(|e| e.to_string())?
+                }
+                _ => return Err("Give either --into or --owner".to_string()),
+            };
+            let summary = jsonld::import(&instance.graph(backend.as_ref(), setting_id), &document)
+                .map_err(|e| e.to_string())?;
+            println!(
+                "Imported {} nodes, {} predicates and {} triples into setting {}",
+                summary.nodes, summary.predicates, summary.triples, setting_id
+            );
+            Ok(())
+        }
+        other => Err(format!("Unknown settings command {}", other)),
+    }
+}
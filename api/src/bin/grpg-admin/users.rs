@@ -0,0 +1,52 @@
+use std::io::{self, BufRead, Write};
+use crate::Instance;
+use crate::args::Args;
+
+fn read_password() -> Result<String, String> {
+    eprint!("Password: ");
+    io::stderr().flush().ok();
+    let mut password = String::new();
+    io::stdin().lock().read_line(&mut password).map_err(|e| e.to_string())?;
+    let password = password.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        return Err("The password cannot be empty".to_string());
+    }
+    Ok(password)
+}
+
+pub fn run(instance: &Instance, mut args: Args) -> Result<(), String> {
+    match args.next("users command")?.as_str() {
+        "list" => {
+            args.finish()?;
+            let mut users = instance.store.get_users().map_err(|e| e.to_string())?;
+            users.sort_by_key(|u| u.id);
+            for user in users {
+                println!("{}\t{}", user.id, user.name);
+            }
+            Ok(())
+        }
+        "create" => {
+            let name = args.next("user name")?;
+            let password = args.option("password");
+            args.finish()?;
+            let password = match password {
+                Some(password) => password,
+                None => read_password()?,
+            };
+            let id = instance.store.add_user(name, &password).map_err(|e| e.to_string())?;
+            println!("{}", id);
+            Ok(())
+        }
+        "password" => {
+            let id = args.next_parsed("user id")?;
+            let password = args.option("password");
+            args.finish()?;
+            let password = match password {
+                Some(password) => password,
+                None => read_password()?,
+            };
+            instance.store.set_password(id, &password).map_err(|e| e.to_string())
+        }
+        other => Err(format!("Unknown users command {}", other)),
+    }
+}
@@ -0,0 +1,416 @@
+use std::env;
+use std::fs;
+use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use serde::Deserialize;
+use grpg_core::db::base::Store;
+use grpg_core::db::events::Events;
+use grpg_core::db::graph::{
+    GraphBackend,
+    KuzuOptions,
+    MemoryBackend
+};
+use grpg_core::db::models::Quota;
+
+/// File read when `GRPG_CONFIG` is not set, skipped when it does not exist.
+const DEFAULT_FILE: &str = "grpg.toml";
+
+/// Configuration of the server, read from a TOML file and overridden by the
+/// `GRPG_*` environment variables.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub storage: StorageConfig,
+    pub kuzu: KuzuOptions,
+    pub limits: LimitsConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Origins allowed by CORS, `*` for any
+    pub cors_origins: Vec<String>,
+    /// Log filter, in the `RUST_LOG` syntax, `RUST_LOG` itself taking precedence
+    pub log: String,
+    /// Serves HTTPS instead of HTTP when present
+    pub tls: Option<TlsConfig>,
+    /// Names of the users allowed to the `/admin` routes
+    pub admins: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: "0.0.0.0".to_string(),
+            port: 8000,
+            cors_origins: vec!["*".to_string()],
+            log: "actix_web=debug,actix_server=info".to_string(),
+            tls: None,
+            admins: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM file of the certificate chain
+    pub cert: PathBuf,
+    /// PEM file of the private key
+    pub key: PathBuf,
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphBackendKind {
+    Kuzu,
+    Memory,
+}
+
+impl FromStr for GraphBackendKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kuzu" => Ok(GraphBackendKind::Kuzu),
+            "memory" => Ok(GraphBackendKind::Memory),
+            other => Err(format!("unknown graph backend {}, expected kuzu or memory", other)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// SQLite database of the metadata store
+    pub store: PathBuf,
+    /// Where the graphs are kept, `kuzu` by default when the server is built
+    /// with Kuzu and `memory` otherwise
+    pub graph_backend: GraphBackendKind,
+    /// Kuzu database of the graphs
+    pub graph: PathBuf,
+    /// File the audit log is appended to, `audit.jsonl` next to the store
+    /// by default
+    pub audit_log: Option<PathBuf>,
+    /// YAML file the store was kept in before SQLite, imported into a new
+    /// store, `graph.yaml` next to the store by default
+    pub legacy_store: Option<PathBuf>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            store: PathBuf::from("./grpg.db"),
+            graph_backend: if cfg!(feature = "kuzu") { GraphBackendKind::Kuzu } else { GraphBackendKind::Memory },
+            graph: PathBuf::from("./demo_db"),
+            audit_log: None,
+            legacy_store: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Quota of every setting
+    pub max_nodes: Option<u64>,
+    pub max_triples: Option<u64>,
+    pub max_tables: Option<u64>,
+    /// Requests per minute of each user, 0 for no limit
+    pub rate_limit_user: u32,
+    /// Requests per minute of each client address, 0 for no limit
+    pub rate_limit_ip: u32,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            max_nodes: None,
+            max_triples: None,
+            max_tables: None,
+            rate_limit_user: 600,
+            rate_limit_ip: 300,
+        }
+    }
+}
+
+impl LimitsConfig {
+    pub fn quota(&self) -> Quota {
+        Quota {
+            max_nodes: self.max_nodes,
+            max_triples: self.max_triples,
+            max_tables: self.max_tables,
+        }
+    }
+}
+
+/// Replaces `value` with the variable `name` when it is set.
+fn env_override<T: FromStr>(name: &str, value: &mut T, errors: &mut Vec<String>) where T::Err: ToString {
+    if let Ok(raw) = env::var(name) {
+        match raw.parse() {
+            Ok(parsed) => *value = parsed,
+            Err(e) => errors.push(format!("{}: {}", name, e.to_string())),
+        }
+    }
+}
+
+fn env_override_option<T: FromStr>(name: &str, value: &mut Option<T>, errors: &mut Vec<String>) where T::Err: ToString {
+    if let Ok(raw) = env::var(name) {
+        match raw.parse() {
+            Ok(parsed) => *value = Some(parsed),
+            Err(e) => errors.push(format!("{}: {}", name, e.to_string())),
+        }
+    }
+}
+
+/// Items of a comma separated list.
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()
+}
+
+/// Whether the directory a file would be created in exists.
+fn parent_exists(path: &Path) -> bool {
+    path.parent().is_none_or(|p| p.as_os_str().is_empty() || p.is_dir())
+}
+
+impl Config {
+    /// Reads the file named by `GRPG_CONFIG`, or `grpg.toml` when it exists,
+    /// applies the environment overrides and validates the result, returning
+    /// every problem found.
+    pub fn load() -> Result<Config, String> {
+        match env::var("GRPG_CONFIG") {
+            Ok(path) => Config::load_file(Path::new(&path)),
+            Err(_) if Path::new(DEFAULT_FILE).exists() => Config::load_file(Path::new(DEFAULT_FILE)),
+            Err(_) => Config::default().finish(),
+        }
+    }
+
+    /// Like [`Config::load`], reading the file at `path`.
+    pub fn load_file(path: &Path) -> Result<Config, String> {
+        Config::from_file(path)?.finish()
+    }
+
+    fn finish(mut self) -> Result<Config, String> {
+        let mut errors = self.apply_env();
+        errors.extend(self.validate());
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(errors.join("\n  "))
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn apply_env(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let e = &mut errors;
+        env_override("GRPG_HOST", &mut self.server.host, e);
+        env_override("GRPG_PORT", &mut self.server.port, e);
+        env_override("GRPG_LOG", &mut self.server.log, e);
+        if let Ok(origins) = env::var("GRPG_CORS_ORIGINS") {
+            self.server.cors_origins = split_list(&origins);
+        }
+        if let Ok(admins) = env::var("GRPG_ADMINS") {
+            self.server.admins = split_list(&admins);
+        }
+        match (env::var("GRPG_TLS_CERT"), env::var("GRPG_TLS_KEY")) {
+            (Ok(cert), Ok(key)) => self.server.tls = Some(TlsConfig { cert: cert.into(), key: key.into() }),
+            (Err(_), Err(_)) => {}
+            _ => e.push("GRPG_TLS_CERT and GRPG_TLS_KEY must be set together".to_string()),
+        }
+        env_override("GRPG_STORE", &mut self.storage.store, e);
+        env_override("GRPG_GRAPH_BACKEND", &mut self.storage.graph_backend, e);
+        env_override("GRPG_GRAPH", &mut self.storage.graph, e);
+        env_override_option("GRPG_AUDIT_LOG", &mut self.storage.audit_log, e);
+        env_override_option("GRPG_LEGACY_STORE", &mut self.storage.legacy_store, e);
+        env_override_option("GRPG_KUZU_BUFFER_POOL_SIZE", &mut self.kuzu.buffer_pool_size, e);
+        env_override_option("GRPG_KUZU_MAX_NUM_THREADS", &mut self.kuzu.max_num_threads, e);
+        env_override("GRPG_KUZU_READ_ONLY", &mut self.kuzu.read_only, e);
+        env_override("GRPG_KUZU_MIGRATE", &mut self.kuzu.migrate, e);
+        env_override_option("GRPG_MAX_NODES", &mut self.limits.max_nodes, e);
+        env_override_option("GRPG_MAX_TRIPLES", &mut self.limits.max_triples, e);
+        env_override_option("GRPG_MAX_TABLES", &mut self.limits.max_tables, e);
+        env_override("GRPG_RATE_LIMIT_USER", &mut self.limits.rate_limit_user, e);
+        env_override("GRPG_RATE_LIMIT_IP", &mut self.limits.rate_limit_ip, e);
+        errors
+    }
+
+    /// Opens the graph backend chosen by the configuration.
+    pub fn graph_backend(&self) -> Result<Arc<dyn GraphBackend>, String> {
+        match self.storage.graph_backend {
+            #[cfg(feature = "kuzu")]
+            GraphBackendKind::Kuzu => {
+                let path = self.storage.graph.to_string_lossy();
+                let backend = grpg_core::db::graph::KuzuBackend::open(&path, &self.kuzu)
+                    .map_err(|e| format!("storage.graph: {}: {}", path, e))?;
+                Ok(Arc::new(backend))
+            }
+            #[cfg(not(feature = "kuzu"))]
+            GraphBackendKind::Kuzu => Err("storage.graph_backend: the server is built without the kuzu feature".to_string()),
+            GraphBackendKind::Memory => Ok(Arc::new(MemoryBackend::new())),
+        }
+    }
+
+    /// Opens the metadata store, importing the legacy store when it is new.
+    pub fn store(&self, events: Arc<Events>) -> Result<Store, String> {
+        let path = self.storage.store.to_string_lossy();
+        let legacy_path = self.legacy_store_path();
+        Store::open(&path, &legacy_path.to_string_lossy(), events, self.limits.quota())
+            .map_err(|e| format!("storage.store: {}: {}", path, e))
+    }
+
+    /// Path of the audit log, defaulting to `audit.jsonl` next to the store.
+    pub fn audit_log_path(&self) -> PathBuf {
+        self.storage.audit_log.clone().unwrap_or_else(|| self.storage.store.with_file_name("audit.jsonl"))
+    }
+    /// Path of the legacy YAML store, defaulting to `graph.yaml` next to the store.
+    pub fn legacy_store_path(&self) -> PathBuf {
+        self.storage.legacy_store.clone().unwrap_or_else(|| self.storage.store.with_file_name("graph.yaml"))
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let server = &self.server;
+        if let Err(e) = (server.host.as_str(), server.port).to_socket_addrs() {
+            errors.push(format!("server.host: cannot resolve {}: {}", server.host, e));
+        }
+        if server.cors_origins.len() > 1 && server.cors_origins.iter().any(|o| o == "*") {
+            errors.push("server.cors_origins: * cannot be combined with other origins".to_string());
+        }
+        for origin in server.cors_origins.iter().filter(|o| *o != "*") {
+            let host = origin.strip_prefix("http://").or_else(|| origin.strip_prefix("https://"));
+            if host.is_none_or(|h| h.is_empty() || h.contains('/')) {
+                errors.push(format!("server.cors_origins: {} is not an origin like https://example.com", origin));
+            }
+        }
+        if let Some(tls) = &server.tls {
+            if !cfg!(feature = "tls") {
+                errors.push("server.tls: the server is built without the tls feature".to_string());
+            }
+            for (name, path) in [("cert", &tls.cert), ("key", &tls.key)] {
+                if !path.is_file() {
+                    errors.push(format!("server.tls.{}: {} is not a file", name, path.display()));
+                }
+            }
+        }
+        if !parent_exists(&self.storage.store) {
+            errors.push(format!("storage.store: the directory of {} does not exist", self.storage.store.display()));
+        }
+        if !parent_exists(&self.audit_log_path()) {
+            errors.push(format!("storage.audit_log: the directory of {} does not exist", self.audit_log_path().display()));
+        }
+        if self.storage.graph_backend == GraphBackendKind::Kuzu {
+            if !cfg!(feature = "kuzu") {
+                errors.push("storage.graph_backend: the server is built without the kuzu feature".to_string());
+            }
+            if !parent_exists(&self.storage.graph) {
+                errors.push(format!("storage.graph: the directory of {} does not exist", self.storage.graph.display()));
+            }
+            if self.kuzu.read_only && !self.storage.graph.exists() {
+                errors.push(format!("kuzu.read_only: {} does not exist to be opened read-only", self.storage.graph.display()));
+            }
+        }
+        if self.kuzu.buffer_pool_size == Some(0) {
+            errors.push("kuzu.buffer_pool_size: must be greater than 0".to_string());
+        }
+        if self.kuzu.max_num_threads == Some(0) {
+            errors.push("kuzu.max_num_threads: must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+impl TlsConfig {
+    /// Rustls configuration serving the certificate chain with the key.
+    #[cfg(feature = "tls")]
+    pub fn server_config(&self) -> Result<rustls::ServerConfig, String> {
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+        let certs = CertificateDer::pem_file_iter(&self.cert)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("server.tls.cert: {}: {}", self.cert.display(), e))?;
+        let key = PrivateKeyDer::from_pem_file(&self.key)
+            .map_err(|e| format!("server.tls.key: {}: {}", self.key.display(), e))?;
+        rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(|e| format!("server.tls: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(name: &str, text: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("grpg-config-test-{}-{}.toml", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn files_fill_in_the_defaults() {
+        let path = write("defaults", "[server]\nport = 9000\n\n[limits]\nmax_nodes = 10\n");
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.server.port, 9000);
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.limits.quota().max_nodes, Some(10));
+        assert_eq!(config.limits.rate_limit_user, 600);
+        assert_eq!(config.audit_log_path(), PathBuf::from("./audit.jsonl"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let path = write("unknown", "[server]\nprot = 9000\n");
+        assert!(Config::from_file(&path).err().unwrap().contains("prot"));
+        fs::remove_file(path).unwrap();
+    }
+
+    // The only test setting variables, as they are shared by the whole process
+    #[test]
+    fn variables_override_the_file() {
+        let path = write("env", "[server]\nport = 9000\nadmins = [\"ada\"]\n\n[limits]\nmax_nodes = 10\nrate_limit_ip = 5\n");
+        unsafe {
+            env::set_var("GRPG_PORT", "9001");
+            env::set_var("GRPG_ADMINS", "byron, ada,");
+            env::set_var("GRPG_MAX_NODES", "20");
+        }
+        let config = Config::load_file(&path).unwrap();
+        assert_eq!(config.server.port, 9001);
+        assert_eq!(config.server.admins, ["byron", "ada"]);
+        assert_eq!(config.limits.max_nodes, Some(20));
+        assert_eq!(config.limits.rate_limit_ip, 5);
+
+        unsafe {
+            env::set_var("GRPG_PORT", "port");
+            env::set_var("GRPG_TLS_CERT", "cert.pem");
+        }
+        let errors = Config::load_file(&path).err().unwrap();
+        assert!(errors.contains("GRPG_PORT"));
+        assert!(errors.contains("GRPG_TLS_CERT and GRPG_TLS_KEY must be set together"));
+        unsafe {
+            for name in ["GRPG_PORT", "GRPG_ADMINS", "GRPG_MAX_NODES", "GRPG_TLS_CERT"] {
+                env::remove_var(name);
+            }
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn validates_cors_origins() {
+        let mut config = Config::default();
+        assert!(config.validate().is_empty());
+        config.server.cors_origins = vec!["*".to_string(), "https://example.com".to_string()];
+        assert_eq!(config.validate().len(), 1);
+        config.server.cors_origins = vec!["https://example.com".to_string(), "example.com".to_string(), "https://example.com/".to_string()];
+        assert_eq!(config.validate().len(), 2);
+    }
+}
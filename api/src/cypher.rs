@@ -0,0 +1,402 @@
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Word(String),
+    Quoted(String),
+    Param(String),
+    Other(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: Kind,
+    /// Character range of the token in the query
+    start: usize,
+    end: usize,
+}
+impl Token {
+    fn is_word(&self, word: &str) -> bool {
+        matches!(&self.kind, Kind::Word(w) if w.eq_ignore_ascii_case(word))
+    }
+    fn is(&self, text: &str) -> bool {
+        matches!(&self.kind, Kind::Other(o) if o == text)
+    }
+    fn word(&self) -> Option<&String> {
+        match &self.kind {
+            Kind::Word(w) => Some(w),
+            _ => None,
+        }
+    }
+}
+
+/// Splits the query in tokens, keeping their position so parts of the query
+/// can be rewritten in place. Comments are dropped.
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+            continue;
+        } else if c == '\'' || c == '"' || c == '`' {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err("Unterminated string".to_string());
+            }
+            i += 1;
+            let text: String = chars[start + 1..i - 1].iter().collect();
+            let kind = if c == '`' { Kind::Word(text) } else { Kind::Quoted(text) };
+            tokens.push(Token { kind, start, end: i });
+        } else if c == '$' {
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token { kind: Kind::Param(chars[start + 1..i].iter().collect()), start, end: i });
+        } else if c.is_alphanumeric() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token { kind: Kind::Word(chars[start..i].iter().collect()), start, end: i });
+        } else {
+            i += 1;
+            tokens.push(Token { kind: Kind::Other(c.to_string()), start, end: i });
+        }
+    }
+    Ok(tokens)
+}
+
+const WRITE_CLAUSES: [&str; 20] = [
+    "CREATE", "MERGE", "SET", "DELETE", "DETACH", "REMOVE", "DROP", "ALTER",
+    "COPY", "LOAD", "INSTALL", "ATTACH", "USE", "CALL", "EXPORT", "IMPORT",
+    "BEGIN", "COMMIT", "ROLLBACK", "CHECKPOINT",
+];
+const READ_CLAUSES: [&str; 5] = ["MATCH", "OPTIONAL", "UNWIND", "WITH", "RETURN"];
+const KEYWORDS: [&str; 24] = [
+    "MATCH", "OPTIONAL", "WHERE", "AND", "OR", "XOR", "NOT", "RETURN", "WITH",
+    "UNWIND", "AS", "IN", "DISTINCT", "ORDER", "BY", "SKIP", "LIMIT", "UNION",
+    "ALL", "CASE", "WHEN", "THEN", "ELSE", "IS",
+];
+const SCOPED_LABELS: [&str; 2] = ["Node", "Predicate"];
+
+fn is_keyword(token: &Token) -> bool {
+    KEYWORDS.iter().any(|k| token.is_word(k))
+}
+
+/// Parsed `(variable:Label {properties})` node pattern spanning
+/// `tokens[start..end]`.
+struct NodePattern {
+    end: usize,
+    variable: Option<String>,
+    labels: Vec<String>,
+    /// Index of the `{` opening the property map
+    properties: Option<usize>,
+}
+
+fn node_pattern(tokens: &[Token], start: usize) -> Option<NodePattern> {
+    let mut i = start + 1;
+    let mut variable = None;
+    let mut labels = Vec::new();
+    let mut properties = None;
+    if let Some(w) = tokens.get(i).and_then(Token::word) {
+        if is_keyword(&tokens[i]) {
+            return None;
+        }
+        variable = Some(w.clone());
+        i += 1;
+    }
+    if tokens.get(i).is_some_and(|t| t.is(":")) {
+        i += 1;
+        loop {
+            match tokens.get(i).and_then(Token::word) {
+                Some(label) => labels.push(label.clone()),
+                None => return None,
+            }
+            i += 1;
+            if tokens.get(i).is_some_and(|t| t.is("|") || t.is(":")) {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+    }
+    if tokens.get(i).is_some_and(|t| t.is("{")) {
+        properties = Some(i);
+        let mut depth = 0;
+        while let Some(token) = tokens.get(i) {
+            if token.is("{") {
+                depth += 1;
+            } else if token.is("}") {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    if tokens.get(i).is_some_and(|t| t.is(")")) {
+        Some(NodePattern { end: i, variable, labels, properties })
+    } else {
+        None
+    }
+}
+
+const PROJECTION_END: [&str; 10] = [
+    "MATCH", "OPTIONAL", "UNWIND", "WITH", "RETURN", "WHERE", "ORDER", "SKIP", "LIMIT", "UNION",
+];
+
+/// Reads the items of the WITH projection starting at `tokens[start]` and
+/// returns the index ending it with the node variables of `bound` it keeps,
+/// either as-is or renamed with AS.
+fn projection(tokens: &[Token], start: usize, bound: &HashSet<String>) -> (usize, HashSet<String>) {
+    let mut projected = HashSet::new();
+    let mut item: Vec<&Token> = Vec::new();
+    let mut depth = 0;
+    let mut i = start;
+    loop {
+        let token = tokens.get(i);
+        let at_end = match token {
+            None => true,
+            Some(t) => depth == 0 && (t.is(",") || t.is(")") || t.is("]") || t.is("}") || PROJECTION_END.iter().any(|c| t.is_word(c))),
+        };
+        if at_end {
+            match item.as_slice() {
+                [t] if t.is("*") => projected.extend(bound.iter().cloned()),
+                [t] => {
+                    if let Some(v) = t.word().filter(|v| bound.contains(*v)) {
+                        projected.insert(v.clone());
+                    }
+                }
+                [source, r#as, alias] if r#as.is_word("AS") && source.word().is_some_and(|v| bound.contains(v)) => {
+                    if let Some(alias) = alias.word() {
+                        projected.insert(alias.clone());
+                    }
+                }
+                _ => {}
+            }
+            item.clear();
+            if !token.is_some_and(|t| t.is(",")) {
+                return (i, projected);
+            }
+        } else if let Some(t) = token {
+            if t.is("(") || t.is("[") || t.is("{") {
+                depth += 1;
+            } else if t.is(")") || t.is("]") || t.is("}") {
+                depth -= 1;
+            }
+            if !(item.is_empty() && t.is_word("DISTINCT")) {
+                item.push(t);
+            }
+        }
+        i += 1;
+    }
+}
+
+/// Checks that `query` is a single read-only statement and rewrites every
+/// node pattern so it can only match `Node`s and `Predicate`s of the setting
+/// bound to the `$setting` parameter. Patterns on other node or rel tables
+/// are rejected.
+pub fn scope_query(query: &str) -> Result<String, String> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = tokenize(query)?;
+    if tokens.last().is_some_and(|t| t.is(";")) {
+        tokens.pop();
+    }
+    match tokens.first() {
+        Some(first) if READ_CLAUSES.iter().any(|c| first.is_word(c)) => {}
+        Some(_) => return Err("Query must start with MATCH, OPTIONAL MATCH, UNWIND, WITH or RETURN".to_string()),
+        None => return Err("Query is empty".to_string()),
+    }
+
+    for (i, token) in tokens.iter().enumerate() {
+        let is_property = i > 0 && tokens[i - 1].is(".");
+        if token.is(";") {
+            return Err("Only a single statement is allowed".to_string());
+        }
+        if let Kind::Param(name) = &token.kind {
+            return Err(format!("Parameter ${} is not allowed", name));
+        }
+        if !is_property && let Some(clause) = WRITE_CLAUSES.iter().find(|c| token.is_word(c)) {
+            return Err(format!("{} is not allowed in a read-only query", clause));
+        }
+    }
+
+    let text = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+    let mut bound: HashSet<String> = HashSet::new();
+    // Variables bound outside each subquery brace, restored when it closes
+    let mut outer: Vec<HashSet<String>> = Vec::new();
+    // End of the current WITH projection and the variables it keeps bound
+    let mut scope: Option<(usize, HashSet<String>)> = None;
+    let mut out = String::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        if scope.as_ref().is_some_and(|(end, _)| i >= *end) {
+            bound = scope.take().map(|(_, projected)| projected).unwrap_or_default();
+        }
+        let token = &tokens[i];
+        let previous = if i > 0 { Some(&tokens[i - 1]) } else { None };
+
+        // Relationship patterns may only use the Triple table
+        if token.is("[") && previous.is_some_and(|p| p.is("-")) {
+            let mut j = i + 1;
+            if tokens.get(j).and_then(Token::word).is_some() {
+                j += 1;
+            }
+            if tokens.get(j).is_some_and(|t| t.is(":")) {
+                j += 1;
+                while let Some(label) = tokens.get(j).and_then(Token::word) {
+                    if label != "Triple" {
+                        return Err(format!("Relationship table {} is not accessible", label));
+                    }
+                    j += 1;
+                    if !tokens.get(j).is_some_and(|t| t.is("|") || t.is(":")) {
+                        break;
+                    }
+                    j += 1;
+                }
+            }
+        }
+
+        // A parenthesis right after a function name opens its arguments
+        let is_call = previous.is_some_and(|p| p.word().is_some() && !is_keyword(p));
+        if token.is("(") && !is_call && let Some(pattern) = node_pattern(&tokens, i) {
+            for label in &pattern.labels {
+                if !SCOPED_LABELS.contains(&label.as_str()) {
+                    return Err(format!("Node table {} is not accessible", label));
+                }
+            }
+            if let Some(open) = pattern.properties {
+                for j in open + 1..pattern.end {
+                    if tokens[j].is_word("setting") && tokens.get(j + 1).is_some_and(|t| t.is(":")) {
+                        return Err("The setting property is set by the server".to_string());
+                    }
+                }
+            }
+            let is_bound = pattern.variable.as_ref().is_some_and(|v| bound.contains(v));
+            if !(is_bound && pattern.labels.is_empty() && pattern.properties.is_none()) {
+                let mut rewritten = String::from("(");
+                if let Some(variable) = &pattern.variable {
+                    rewritten.push_str(&text(tokens[i + 1].start, tokens[i + 1].end));
+                    bound.insert(variable.clone());
+                }
+                if pattern.labels.is_empty() {
+                    // Unlabelled patterns could match any node table
+                    if !is_bound {
+                        rewritten.push_str(":Node");
+                    }
+                } else {
+                    rewritten.push(':');
+                    rewritten.push_str(&pattern.labels.join(":"));
+                }
+                rewritten.push_str(" {setting: $setting");
+                if let Some(open) = pattern.properties {
+                    let close = pattern.end - 1;
+                    if open + 1 < close {
+                        rewritten.push_str(", ");
+                        rewritten.push_str(&text(tokens[open + 1].start, tokens[close - 1].end));
+                    }
+                }
+                rewritten.push_str("})");
+                out.push_str(&text(copied, token.start));
+                out.push_str(&rewritten);
+                copied = tokens[pattern.end].end;
+                i = pattern.end + 1;
+                continue;
+            }
+        }
+
+        // UNION starts a new query, WITH only carries its projection over and
+        // names bound in a subquery are gone once it closes, so later patterns
+        // reusing a name must be scoped again
+        if token.is("{") {
+            outer.push(bound.clone());
+        } else if token.is("}") {
+            bound = outer.pop().unwrap_or_default();
+        } else if token.is_word("UNION") {
+            bound.clear();
+        } else if token.is_word("WITH") && !previous.is_some_and(|p| p.is_word("STARTS") || p.is_word("ENDS")) {
+            let (end, projected) = projection(&tokens, i + 1, &bound);
+            scope = Some((end, projected));
+        }
+        i += 1;
+    }
+    let end = tokens.last().map_or(0, |t| t.end);
+    out.push_str(&text(copied, end));
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_every_pattern() {
+        assert_eq!(
+            scope_query("MATCH (n)-[t]->(m:Predicate) RETURN n").unwrap(),
+            "MATCH (n:Node {setting: $setting})-[t]->(m:Predicate {setting: $setting}) RETURN n",
+        );
+        assert!(scope_query("MATCH (u:User) RETURN u").is_err());
+        assert!(scope_query("MATCH (n) DETACH DELETE n").is_err());
+    }
+
+    #[test]
+    fn union_starts_a_new_scope() {
+        assert_eq!(
+            scope_query("MATCH (n:Node) RETURN n.label AS l UNION ALL MATCH (n) RETURN n.label AS l").unwrap(),
+            "MATCH (n:Node {setting: $setting}) RETURN n.label AS l UNION ALL MATCH (n:Node {setting: $setting}) RETURN n.label AS l",
+        );
+    }
+
+    #[test]
+    fn with_only_keeps_projected_variables() {
+        assert_eq!(
+            scope_query("MATCH (n:Node) WITH count(n) AS c MATCH (n) RETURN n.label").unwrap(),
+            "MATCH (n:Node {setting: $setting}) WITH count(n) AS c MATCH (n:Node {setting: $setting}) RETURN n.label",
+        );
+        assert_eq!(
+            scope_query("MATCH (n:Node) WITH n AS m, n.label AS n MATCH (m)-->(n) RETURN m").unwrap(),
+            "MATCH (n:Node {setting: $setting}) WITH n AS m, n.label AS n MATCH (m)-->(n:Node {setting: $setting}) RETURN m",
+        );
+        assert_eq!(
+            scope_query("MATCH (n:Node) WHERE n.label STARTS WITH 'a' MATCH (n) RETURN n").unwrap(),
+            "MATCH (n:Node {setting: $setting}) WHERE n.label STARTS WITH 'a' MATCH (n) RETURN n",
+        );
+    }
+
+    #[test]
+    fn subqueries_keep_their_variables() {
+        assert_eq!(
+            scope_query("MATCH (n:Node) WHERE EXISTS { MATCH (x) RETURN x } MATCH (x) RETURN x.label").unwrap(),
+            "MATCH (n:Node {setting: $setting}) WHERE EXISTS { MATCH (x:Node {setting: $setting}) RETURN x } MATCH (x:Node {setting: $setting}) RETURN x.label",
+        );
+        assert_eq!(
+            scope_query("MATCH (n:Node) WHERE COUNT { MATCH (n)-->(m) } > 1 RETURN n").unwrap(),
+            "MATCH (n:Node {setting: $setting}) WHERE COUNT { MATCH (n)-->(m:Node {setting: $setting}) } > 1 RETURN n",
+        );
+    }
+}
@@ -6,3 +6,17 @@ pub mod table;
 pub mod hooks;
 pub mod users;
 pub mod settings;
+pub mod export;
+pub mod import;
+pub mod sparql;
+pub mod cypher;
+pub mod graphql;
+pub mod events;
+pub mod webhooks;
+pub mod auth;
+pub mod tokens;
+pub mod members;
+pub mod organizations;
+pub mod shares;
+pub mod audit;
+pub mod admin;
@@ -0,0 +1,29 @@
+use super::prelude::*;
+use grpg_core::db::backup::Backup;
+
+#[utoipa::path(
+    tags=["admin"],
+    responses(
+        (status = 200, description = "Gzipped JSON backup of the store and of every graph", content_type = "application/gzip", body = Vec<u8>),
+        (status = 403, description = "Only administrators of the instance can back it up", body = ErrorBody),
+        (status = 500, description = "The backup failed", body = ErrorBody)
+    )
+)]
+#[get("/admin/backup")]
+pub async fn get_backup(
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let state = app_state.clone();
+    let (created_at, archive) = web::block(move || {
+        let backup = Backup::take(&state.store, state.graph.as_ref())?;
+        let mut archive = Vec::new();
+        backup.write(&mut archive).map(|_| (backup.created_at, archive))
+    }).await.map_err(ApiError::internal)??;
+    Ok(HttpResponse::Ok()
+        .content_type("application/gzip")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"grpg-backup-{}.json.gz\"", created_at)
+        ))
+        .body(archive))
+}
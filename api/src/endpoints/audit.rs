@@ -0,0 +1,44 @@
+use super::prelude::*;
+use grpg_core::db::audit::{
+    AuditEntry,
+    AuditFilter
+};
+
+/// Entries returned when no limit is given.
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditPage {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditResponse {
+    /// Number of entries matching the filter
+    pub total: usize,
+    /// Matching entries, newest first
+    pub entries: Vec<AuditEntry>,
+}
+
+#[utoipa::path(
+    tags=["audit"],
+    params(AuditFilter, AuditPage),
+    responses(
+        (status = 200, description = "Mutating requests made on the setting", body = AuditResponse),
+        (status = 403, description = "Only owners can read the audit log")
+    )
+)]
+#[get("/settings/{setting_id}/audit")]
+pub async fn get_audit(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+    filter: web::Query<AuditFilter>,
+    page: web::Query<AuditPage>,
+) -> impl Responder {
+    let limit = page.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let (entries, total) = app_state.audit.query(path.into_inner(), &filter, page.offset.unwrap_or(0), limit);
+    HttpResponse::Ok().json(AuditResponse { total, entries })
+}
@@ -0,0 +1,87 @@
+use super::prelude::*;
+use actix_web::{
+    cookie::{time::Duration, Cookie, SameSite},
+    HttpRequest
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::auth::{
+    request_token,
+    CurrentUser,
+    SESSION_COOKIE
+};
+use grpg_core::db::models::UserData;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LoginResponse {
+    /// Session token, also set as the `grpg_session` cookie. Send it as
+    /// `Authorization: Bearer <token>` when cookies are not available.
+    pub token: String,
+    /// Seconds since the Unix epoch
+    pub expires_at: u64,
+    pub user: UserData
+}
+
+fn session_cookie(value: String, max_age: Duration) -> Cookie<'static> {
+    Cookie::build(SESSION_COOKIE, value)
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .max_age(max_age)
+        .finish()
+}
+
+#[utoipa::path(
+    tags=["auth"],
+    request_body = LoginRequest,
+    responses(
+        (status = 200, body = LoginResponse),
+        (status = 401, description = "Invalid username or password", body = ErrorBody)
+    )
+)]
+#[post("/auth/login")]
+pub async fn login(
+    app_state: web::Data<AppState>,
+    body: web::Json<LoginRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let user = app_state.store.authenticate(&body.username, &body.password)?
+        .ok_or_else(|| ApiError::unauthorized("Invalid username or password"))?;
+    let (token, expires_at) = app_state.store.add_session(user.id)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let max_age = Duration::seconds(expires_at.saturating_sub(now) as i64);
+    Ok(HttpResponse::Ok()
+        .cookie(session_cookie(token.clone(), max_age))
+        .json(LoginResponse { token, expires_at, user }))
+}
+
+#[utoipa::path(
+    tags=["auth"],
+    responses((status = 200, description = "Session closed"))
+)]
+#[post("/auth/logout")]
+pub async fn logout(app_state: web::Data<AppState>, req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    if let Some(token) = request_token(&req) {
+        app_state.store.remove_session(&token)?;
+    }
+    Ok(HttpResponse::Ok()
+        .cookie(session_cookie(String::new(), Duration::ZERO))
+        .body("Logged out"))
+}
+
+#[utoipa::path(
+    tags=["auth"],
+    responses(
+        (status = 200, body = UserData),
+        (status = 401, description = "Not logged in", body = ErrorBody)
+    )
+)]
+#[get("/auth/me")]
+pub async fn get_me(user: CurrentUser) -> impl Responder {
+    HttpResponse::Ok().json(user.user)
+}
@@ -0,0 +1,55 @@
+use super::prelude::*;
+use crate::cypher::scope_query;
+
+const DEFAULT_ROW_LIMIT: usize = 1000;
+const MAX_ROW_LIMIT: usize = 10000;
+const QUERY_TIMEOUT_MS: u64 = 10000;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct CypherRequest {
+    /// Read-only Cypher query over the `Node`, `Predicate` and `Triple` tables
+    pub query: String,
+    /// Maximum number of rows returned, at most 10000
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CypherResponse {
+    pub columns: Vec<String>,
+    #[schema(value_type = Vec<Vec<Object>>)]
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Whether rows past the limit were dropped
+    pub truncated: bool,
+}
+
+#[utoipa::path(
+    tags=["query"],
+    request_body = CypherRequest,
+    responses(
+        (status = 200, body = CypherResponse),
+        (status = 400, description = "Query rejected or failed", body = ErrorBody),
+        (status = 501, description = "The graph backend cannot run queries", body = ErrorBody)
+    )
+)]
+#[post("/settings/{setting_id}/cypher")]
+pub async fn post_cypher(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+    body: web::Json<CypherRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let setting_id = path.into_inner();
+    let request = body.into_inner();
+    let query = scope_query(&request.query).map_err(ApiError::invalid)?;
+    let limit = request.limit.unwrap_or(DEFAULT_ROW_LIMIT).min(MAX_ROW_LIMIT);
+    let result = app_state.graph(setting_id).query_limited(
+        &query,
+        vec![("setting", serde_json::Value::from(setting_id))],
+        limit,
+        QUERY_TIMEOUT_MS
+    )?;
+    Ok(HttpResponse::Ok().json(CypherResponse {
+        columns: result.columns,
+        rows: result.rows,
+        truncated: result.truncated,
+    }))
+}
@@ -0,0 +1,54 @@
+use super::prelude::*;
+use std::time::Duration;
+use futures_util::stream;
+use tokio::sync::broadcast::{
+    error::RecvError,
+    Receiver
+};
+use grpg_core::db::events::ChangeEvent;
+
+/// Interval after which an idle stream sends a comment, so proxies do not
+/// close the connection.
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+fn sse(event: &str, id: Option<u64>, data: &str) -> web::Bytes {
+    let id = id.map(|id| format!("id: {}\n", id)).unwrap_or_default();
+    web::Bytes::from(format!("event: {}\n{}data: {}\n\n", event, id, data))
+}
+
+#[utoipa::path(
+    tags=["events"],
+    responses((
+        status = 200,
+        description = "Server-Sent Events stream of the setting's changes. \
+            Each event is named after its type and carries a ChangeEvent. \
+            A `lagged` event means changes were missed and the client should reload.",
+        body = ChangeEvent,
+        content_type = "text/event-stream"
+    ))
+)]
+#[get("/settings/{setting_id}/events")]
+pub async fn get_events(app_state: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
+    let setting_id = path.into_inner();
+    let receiver: Receiver<ChangeEvent> = app_state.events.subscribe();
+    let body = stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            let chunk = match tokio::time::timeout(KEEP_ALIVE, receiver.recv()).await {
+                Err(_) => web::Bytes::from_static(b": keep-alive\n\n"),
+                Ok(Ok(event)) if event.setting == setting_id => match serde_json::to_string(&event) {
+                    Ok(data) => sse(event.change.name(), Some(event.sequence), &data),
+                    // Events that cannot be serialized are skipped rather than ending the stream
+                    Err(_) => continue,
+                },
+                Ok(Ok(_)) => continue,
+                Ok(Err(RecvError::Lagged(missed))) => sse("lagged", None, &missed.to_string()),
+                Ok(Err(RecvError::Closed)) => return None,
+            };
+            return Some((Ok::<_, actix_web::Error>(chunk), receiver));
+        }
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body)
+}
@@ -0,0 +1,110 @@
+use super::prelude::*;
+use std::collections::HashSet;
+use grpg_core::db::models::{
+    Filter,
+    GraphDirection
+};
+use crate::formats::archive::{
+    self,
+    SettingArchive
+};
+use crate::formats::graph::{
+    GraphFormat,
+    Subgraph
+};
+
+#[derive(Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in=Query)]
+pub struct GraphExportQuery {
+    /// Output format of the export
+    pub format: GraphFormat,
+    /// Only export this node, the nodes linked to it and the triples linking them
+    pub node_id: Option<i32>,
+    /// Only export nodes linked through this predicate
+    pub predicate: Option<i32>,
+    /// Direction of the link used by `node_id` and `predicate`
+    pub direction: Option<GraphDirection>,
+    /// Comma separated list of predicate ids, only triples using them are exported
+    pub predicate_ids: Option<String>,
+}
+
+#[utoipa::path(
+    tags=["export"],
+    params(GraphExportQuery),
+    responses(
+        (status = 200, description = "Graph as GraphML, GEXF, Cytoscape.js JSON or JSON-LD", body = String),
+        (status = 400, body = ErrorBody),
+        (status = 404, description = "Node not found", body = ErrorBody)
+    )
+)]
+#[get("/settings/{setting_id}/export")]
+pub async fn export_graph(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<GraphExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let setting_id = path.into_inner();
+    let query = query.into_inner();
+    let predicate_ids = match &query.predicate_ids {
+        Some(ids) => Some(
+            ids.split(',').map(|id| id.trim().parse::<i32>()).collect::<Result<HashSet<_>, _>>()
+                .map_err(|_| ApiError::invalid("Invalid predicate_ids parameter"))?
+        ),
+        None => None,
+    };
+
+    let graph = app_state.graph(setting_id);
+    let mut subgraph = Subgraph {
+        setting: setting_id,
+        nodes: graph.node_all()?,
+        predicates: graph.predicate_all()?,
+        triples: graph.triple_all()?,
+    };
+    if let Some(node_id) = query.node_id {
+        if !subgraph.nodes.iter().any(|n| n.node_id == node_id) {
+            return Err(ApiError::not_found("Node not found"));
+        }
+        subgraph.around(node_id, query.predicate, query.direction.as_ref());
+    } else if query.predicate.is_some() || query.direction.is_some() {
+        let selected: HashSet<i32> = graph.node_filter(Filter {
+            node_id: None,
+            predicate: query.predicate,
+            direction: query.direction.clone(),
+        }).await?.into_iter().collect();
+        subgraph.nodes.retain(|n| selected.contains(&n.node_id));
+        subgraph.triples.retain(|t| selected.contains(&t.subject_id) && selected.contains(&t.object_id));
+    }
+    if let Some(ids) = &predicate_ids {
+        subgraph.triples.retain(|t| ids.contains(&t.predicate_id));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(query.format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"setting-{}.{}\"", setting_id, query.format.extension())
+        ))
+        .body(subgraph.render(query.format)))
+}
+
+#[utoipa::path(
+    tags=["export"],
+    responses(
+        (status = 200, description = "The setting with its graph and tables, to import on another instance", body = SettingArchive),
+        (status = 404, description = "Setting not found", body = ErrorBody)
+    )
+)]
+#[get("/settings/{setting_id}/archive")]
+pub async fn export_archive(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let setting_id = path.into_inner();
+    let archive = archive::export(&app_state.graph(setting_id))?;
+    Ok(HttpResponse::Ok()
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"setting-{}.archive.json\"", setting_id)
+        ))
+        .json(archive))
+}
@@ -0,0 +1,37 @@
+use super::prelude::*;
+use async_graphql::http::GraphiQLSource;
+use crate::auth::CurrentUser;
+use crate::graphql::{
+    loader,
+    GrpgSchema
+};
+
+#[utoipa::path(
+    tags=["graphql"],
+    request_body(content = Object, description = "GraphQL request with `query`, `variables` and `operationName`"),
+    responses((status = 200, description = "GraphQL response", body = Object))
+)]
+#[post("/graphql")]
+pub async fn post_graphql(
+    app_state: web::Data<AppState>,
+    schema: web::Data<GrpgSchema>,
+    user: Option<CurrentUser>,
+    request: web::Json<async_graphql::Request>,
+) -> impl Responder {
+    let request = request.into_inner()
+        .data(app_state.clone())
+        .data(loader(app_state))
+        .data(user);
+    HttpResponse::Ok().json(schema.execute(request).await)
+}
+
+#[utoipa::path(
+    tags=["graphql"],
+    responses((status = 200, description = "GraphiQL playground", content_type = "text/html"))
+)]
+#[get("/graphql")]
+pub async fn get_graphiql() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(GraphiQLSource::build().endpoint("/graphql").finish())
+}
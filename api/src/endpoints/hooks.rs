@@ -13,42 +13,42 @@ use std::os::unix::process::CommandExt;
     request_body = (),
     responses(
         (status = 200, description = "Webhook processed successfully"),
-        (status = 400, description = "Bad Request"),
-        (status = 401, description = "Unauthorized"),
-        (status = 500, description = "Internal Server Error")
+        (status = 400, description = "Bad Request", body = ErrorBody),
+        (status = 401, description = "Unauthorized", body = ErrorBody),
+        (status = 500, description = "Internal Server Error", body = ErrorBody)
     )
 )]
 #[post("/hook")]
-pub async fn github_webhook(req: HttpRequest, body: web::Bytes) -> impl Responder {
+pub async fn github_webhook(req: HttpRequest, body: web::Bytes) -> Result<HttpResponse, ApiError> {
     let signature_string = match req.headers().get("X-Hub-Signature-256") {
         Some(sig) => sig.to_str().unwrap_or(""),
-        None => return HttpResponse::BadRequest().body("Bad Request: Missing signature"),
+        None => return Err(ApiError::invalid("Bad Request: Missing signature")),
     };
 
     let signature = match hex::decode(signature_string.strip_prefix("sha256=").unwrap_or("")) {
         Ok(sig) => sig,
-        Err(_) => return HttpResponse::BadRequest().body("Bad Request: Invalid signature format"),
+        Err(_) => return Err(ApiError::invalid("Bad Request: Invalid signature format")),
     };
 
     let secret = match env::var("GITHUB_WEBHOOK_SECRET") {
         Ok(s) => s.as_bytes().to_vec(),
-        Err(_) => return HttpResponse::InternalServerError().body("Secret not configured"),
+        Err(_) => return Err(ApiError::internal("Secret not configured")),
     };
     
     if !verify_signature(&body, &signature, &secret) {
-        return HttpResponse::Unauthorized().body("Invalid signature");
+        return Err(ApiError::unauthorized("Invalid signature"));
     }
 
     let current_dir = match std::env::current_dir() {
         Ok(dir) => dir,
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get current directory: {}", e)),
+        Err(e) => return Err(ApiError::internal(format!("Failed to get current directory: {}", e))),
     };
 
     // Construct the path to the update script making it detached from current process with &
     let update_script = current_dir.parent().unwrap_or(&current_dir).parent().unwrap_or(&current_dir).join("update.sh");
 
     if !update_script.exists() {
-        return HttpResponse::InternalServerError().body("Update script not found");
+        return Err(ApiError::internal("Update script not found"));
     }
     
     let _c = Command::new(&update_script)
@@ -57,10 +57,10 @@ pub async fn github_webhook(req: HttpRequest, body: web::Bytes) -> impl Responde
         .spawn()
         .ok();
     
-    return HttpResponse::Ok().body("Webhook processed successfully");
+    Ok(HttpResponse::Ok().body("Webhook processed successfully"))
 }
 
-fn verify_signature(payload: &[u8], signature: &[u8], secret: &[u8]) -> bool {
+pub fn verify_signature(payload: &[u8], signature: &[u8], secret: &[u8]) -> bool {
     // Create HMAC instance
     let mut mac = match Hmac::<Sha256>::new_from_slice(secret) {
         Ok(m) => m,
@@ -71,5 +71,14 @@ fn verify_signature(payload: &[u8], signature: &[u8], secret: &[u8]) -> bool {
     mac.update(payload);
 
     // Verify signature
-    return mac.verify_slice(&signature).is_ok();
+    mac.verify_slice(signature).is_ok()
+}
+
+/// Signs a payload the way `verify_signature` checks it, formatted as the
+/// `sha256=<hex>` header value.
+pub fn sign_payload(payload: &[u8], secret: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+        .expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
 }
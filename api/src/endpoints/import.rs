@@ -0,0 +1,75 @@
+use super::prelude::*;
+use crate::auth::CurrentUser;
+use crate::formats::archive::{
+    ImportedSetting,
+    SettingArchive
+};
+use crate::formats::jsonld::{
+    self,
+    ImportSummary
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ArchiveImportQuery {
+    /// Name of the new setting, the archived one when absent
+    pub name: Option<String>,
+    /// Organization to create the setting in, the caller must be one of its editors
+    pub organization_id: Option<i32>,
+}
+
+#[utoipa::path(
+    tags=["export"],
+    request_body(content = String, description = "Compacted or expanded JSON-LD document", content_type = "application/ld+json"),
+    responses(
+        (status = 200, body = ImportSummary),
+        (status = 400, body = ErrorBody),
+        (status = 403, description = "Node or triple quota of the setting reached", body = ErrorBody)
+    )
+)]
+#[post("/settings/{setting_id}/import/jsonld")]
+pub async fn import_jsonld(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let setting_id = path.into_inner();
+    let document: serde_json::Value = serde_json::from_slice(&body)
+        .map_err(|e| ApiError::invalid(format!("Invalid JSON: {}", e)))?;
+    let summary = jsonld::import(&app_state.graph(setting_id), &document)?;
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+#[utoipa::path(
+    tags=["export"],
+    params(ArchiveImportQuery),
+    request_body = SettingArchive,
+    responses(
+        (status = 200, description = "Setting created from the archive", body = ImportedSetting),
+        (status = 400, description = "Invalid archive", body = ErrorBody),
+        (status = 401, description = "Not logged in", body = ErrorBody),
+        (status = 403, description = "Organization not editable or quota exceeded", body = ErrorBody),
+        (status = 404, description = "Organization not found", body = ErrorBody)
+    )
+)]
+#[post("/settings/import")]
+pub async fn import_archive(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    query: web::Query<ArchiveImportQuery>,
+    body: web::Json<SettingArchive>,
+) -> Result<HttpResponse, ApiError> {
+    let archive = body.into_inner();
+    let query = query.into_inner();
+    archive.validate()?;
+    let name = query.name.unwrap_or_else(|| archive.setting.name.clone());
+    let setting_id = app_state.store.user_settings(user.id()).add_setting(name, query.organization_id)?;
+    match archive.import(&app_state.graph(setting_id)) {
+        Ok(imported) => Ok(HttpResponse::Ok().json(imported)),
+        Err(e) => {
+            let _ = app_state.store.remove_setting(setting_id);
+            let _ = app_state.graph.setting_delete(setting_id);
+            Err(e.into())
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use super::prelude::*;
+use grpg_core::db::models::Role;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Member {
+    pub user_id: i32,
+    pub name: Option<String>,
+    pub role: Role
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PutMember {
+    pub role: Role
+}
+
+#[utoipa::path(
+    tags=["members"],
+    responses(
+        (status = 200, description = "Users the setting is shared with, its owner included", body = [Member]),
+        (status = 404, description = "Setting not found", body = ErrorBody)
+    )
+)]
+#[get("/settings/{setting_id}/members")]
+pub async fn get_members(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let setting_id = path.into_inner();
+    let members = app_state.store.conn(setting_id).get_members()?
+        .ok_or_else(|| ApiError::not_found("Setting not found"))?;
+    let users = app_state.store.get_users()?;
+    let mut members = members.into_iter().map(|(user_id, role)| Member {
+        user_id,
+        name: users.iter().find(|u| u.id == user_id).map(|u| u.name.clone()),
+        role
+    }).collect::<Vec<_>>();
+    members.sort_by_key(|m| m.user_id);
+    Ok(HttpResponse::Ok().json(members))
+}
+
+#[utoipa::path(
+    tags=["members"],
+    params(
+        ("user_id" = i32, Path, description = "User ID")
+    ),
+    request_body = PutMember,
+    responses(
+        (status = 200, description = "Setting shared with the user"),
+        (status = 400, description = "The user cannot be given a role", body = ErrorBody),
+        (status = 404, description = "Setting or user not found", body = ErrorBody)
+    )
+)]
+#[put("/settings/{setting_id}/members/{user_id}")]
+pub async fn put_member(
+    app_state: web::Data<AppState>,
+    path: web::Path<(i32, i32)>,
+    body: web::Json<PutMember>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, user_id) = path.into_inner();
+    app_state.store.conn(setting_id).set_member(user_id, body.role)?;
+    Ok(HttpResponse::Ok().body("Member updated"))
+}
+
+#[utoipa::path(
+    tags=["members"],
+    params(
+        ("user_id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Setting no longer shared with the user"),
+        (status = 404, description = "Member not found", body = ErrorBody)
+    )
+)]
+#[delete("/settings/{setting_id}/members/{user_id}")]
+pub async fn delete_member(
+    app_state: web::Data<AppState>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, user_id) = path.into_inner();
+    app_state.store.conn(setting_id).remove_member(user_id)?
+        .ok_or_else(|| ApiError::not_found("Member not found"))?;
+    Ok(HttpResponse::Ok().body("Member removed"))
+}
@@ -1,5 +1,5 @@
 use super::prelude::*;
-use crate::db::models::{
+use grpg_core::db::models::{
     Node
 };
 #[derive(Deserialize, Serialize, ToSchema)]
@@ -14,15 +14,17 @@ pub struct NewNode {
 
 #[utoipa::path(
     tags=["nodes"],
-    responses((status = 200, body = NodeResponse))
+    responses(
+        (status = 200, body = NodeResponse),
+        (status = 403, description = "Node quota of the setting reached", body = ErrorBody)
+    )
 )]
 #[post("/settings/{setting_id}/node")]
-pub async fn post_node(app_state: web::Data<AppState>, new_label: web::Json<NewNode>, path: web::Path<i32>) -> impl Responder {
+pub async fn post_node(app_state: web::Data<AppState>, new_label: web::Json<NewNode>, path: web::Path<i32>) -> Result<HttpResponse, ApiError> {
     let setting_id = path.into_inner();
     let label = new_label.into_inner().label;
-    HttpResponse::Ok().json(NodeResponse {
-        node_id: app_state.graph(setting_id).node_create(label.clone())
-     })
+    let node_id = app_state.graph(setting_id).node_create(label.clone())?;
+    Ok(HttpResponse::Ok().json(NodeResponse { node_id }))
 }
 
 #[utoipa::path(
@@ -30,9 +32,9 @@ pub async fn post_node(app_state: web::Data<AppState>, new_label: web::Json<NewN
     responses((status = 200, body = [Node]))
 )]
 #[get("/settings/{setting_id}/node")]
-pub async fn get_node(app_state: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
+pub async fn get_node(app_state: web::Data<AppState>, path: web::Path<i32>) -> Result<HttpResponse, ApiError> {
     let setting_id = path.into_inner();
-    HttpResponse::Ok().json(app_state.graph(setting_id).node_all())
+    Ok(HttpResponse::Ok().json(app_state.graph(setting_id).node_all()?))
 }
 
 #[utoipa::path(
@@ -41,21 +43,22 @@ pub async fn get_node(app_state: web::Data<AppState>, path: web::Path<i32>) -> i
         ("node_id" = i32, Path, description = "ID do nó a ser atualizado"),
         ("label" = String, Query, description = "Novo label do nó")
     ),
-    responses((status = 200, body = Node))
+    responses(
+        (status = 200, body = Node),
+        (status = 400, description = "Missing label parameter", body = ErrorBody),
+        (status = 404, description = "Node not found", body = ErrorBody)
+    )
 )]
 #[put("/settings/{setting_id}/node/{node_id}")]
 pub async fn put_node(
     app_state: web::Data<AppState>,
     path: web::Path<(i32, i32)>,
     mut label: web::Query<std::collections::HashMap<String, String>>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let (setting_id, node_id) = path.into_inner();
-    let label = match label.remove("label") {
-        Some(l) => l,
-        None => return HttpResponse::BadRequest().body("Missing label parameter"),
-    };
+    let label = label.remove("label").ok_or_else(|| ApiError::invalid("Missing label parameter"))?;
 
-    HttpResponse::Ok().json(app_state.graph(setting_id).node_update(node_id, label))
+    Ok(HttpResponse::Ok().json(app_state.graph(setting_id).node_update(node_id, label)?))
 }
 
 
@@ -68,10 +71,10 @@ pub async fn put_node(
     responses((status = 200, body = NodeResponse))
 )]
 #[delete("/settings/{setting_id}/node/{node_id}")]
-pub async fn delete_node(app_state: web::Data<AppState>, path: web::Path<(i32, i32)>) -> impl Responder {
+pub async fn delete_node(app_state: web::Data<AppState>, path: web::Path<(i32, i32)>) -> Result<HttpResponse, ApiError> {
     let (setting_id, node_id) = path.into_inner();
 
-    app_state.graph(setting_id).node_delete(node_id);
+    app_state.graph(setting_id).node_delete(node_id)?;
 
-    HttpResponse::Ok().json(NodeResponse { node_id })
+    Ok(HttpResponse::Ok().json(NodeResponse { node_id }))
 }
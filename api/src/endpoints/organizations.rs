@@ -0,0 +1,281 @@
+use super::prelude::*;
+use crate::auth::CurrentUser;
+use grpg_core::db::models::{
+    OrganizationData,
+    Role
+};
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PostOrganization {
+    pub name: String
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Organization {
+    pub id: i32,
+    pub name: String,
+    /// Role of the logged in user in the organization
+    pub role: Option<Role>
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct OrganizationMember {
+    pub user_id: i32,
+    pub name: Option<String>,
+    pub role: Role
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct OrganizationDetails {
+    pub id: i32,
+    pub name: String,
+    pub members: Vec<OrganizationMember>,
+    pub invitations: Vec<OrganizationMember>
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PostInvitation {
+    pub username: String,
+    pub role: Role
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PutOrganizationMember {
+    pub role: Role
+}
+
+fn summary(id: i32, organization: OrganizationData, user_id: i32, pending: bool) -> Organization {
+    let roles = if pending { &organization.invitations } else { &organization.members };
+    Organization {
+        id,
+        role: roles.get(&user_id).copied(),
+        name: organization.name
+    }
+}
+
+/// Checks the user's role in the organization, failing when it is not
+/// enough.
+fn require_role(
+    app_state: &AppState,
+    organization_id: i32,
+    user_id: i32,
+    required: Role
+) -> Result<OrganizationData, ApiError> {
+    let organization = app_state.store.organization(organization_id).get()?
+        .ok_or_else(|| ApiError::not_found("Organization not found"))?;
+    match organization.members.get(&user_id) {
+        None => Err(ApiError::not_found("Organization not found")),
+        Some(role) if *role < required => {
+            Err(ApiError::forbidden("Your role in this organization does not allow this request"))
+        }
+        Some(_) => Ok(organization),
+    }
+}
+
+#[utoipa::path(
+    tags=["organizations"],
+    request_body = PostOrganization,
+    responses(
+        (status = 200, description = "Organization created, owned by the logged in user", body = Organization),
+        (status = 401, description = "Not logged in")
+    )
+)]
+#[post("/organizations")]
+pub async fn post_organization(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    body: web::Json<PostOrganization>,
+) -> Result<HttpResponse, ApiError> {
+    let name = body.into_inner().name;
+    let id = app_state.store.add_organization(user.id(), name.clone())?;
+    Ok(HttpResponse::Ok().json(Organization { id, name, role: Some(Role::Owner) }))
+}
+
+#[utoipa::path(
+    tags=["organizations"],
+    responses(
+        (status = 200, description = "Organizations the logged in user belongs to", body = [Organization]),
+        (status = 401, description = "Not logged in")
+    )
+)]
+#[get("/organizations")]
+pub async fn get_organizations(app_state: web::Data<AppState>, user: CurrentUser) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(app_state.store.get_organizations(user.id())?
+        .into_iter()
+        .map(|(id, o)| summary(id, o, user.id(), false))
+        .collect::<Vec<_>>()))
+}
+
+#[utoipa::path(
+    tags=["organizations"],
+    params(
+        ("organization_id" = i32, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, body = OrganizationDetails),
+        (status = 404, description = "Organization not found", body = ErrorBody)
+    )
+)]
+#[get("/organizations/{organization_id}")]
+pub async fn get_organization(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let organization_id = path.into_inner();
+    let organization = require_role(&app_state, organization_id, user.id(), Role::Viewer)?;
+    let users = app_state.store.get_users()?;
+    let list = |roles: std::collections::HashMap<i32, Role>| {
+        let mut members = roles.into_iter().map(|(user_id, role)| OrganizationMember {
+            user_id,
+            name: users.iter().find(|u| u.id == user_id).map(|u| u.name.clone()),
+            role
+        }).collect::<Vec<_>>();
+        members.sort_by_key(|m| m.user_id);
+        members
+    };
+    Ok(HttpResponse::Ok().json(OrganizationDetails {
+        id: organization_id,
+        name: organization.name,
+        members: list(organization.members),
+        invitations: list(organization.invitations)
+    }))
+}
+
+#[utoipa::path(
+    tags=["organizations"],
+    params(
+        ("organization_id" = i32, Path, description = "Organization ID")
+    ),
+    request_body = PostInvitation,
+    responses(
+        (status = 200, description = "User invited"),
+        (status = 403, description = "Only owners can invite", body = ErrorBody),
+        (status = 404, description = "User not found", body = ErrorBody),
+        (status = 409, description = "User already a member", body = ErrorBody)
+    )
+)]
+#[post("/organizations/{organization_id}/invitations")]
+pub async fn post_invitation(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    path: web::Path<i32>,
+    body: web::Json<PostInvitation>,
+) -> Result<HttpResponse, ApiError> {
+    let organization_id = path.into_inner();
+    require_role(&app_state, organization_id, user.id(), Role::Owner)?;
+    app_state.store.organization(organization_id).invite(&body.username, body.role)?;
+    Ok(HttpResponse::Ok().body("User invited"))
+}
+
+#[utoipa::path(
+    tags=["organizations"],
+    params(
+        ("organization_id" = i32, Path, description = "Organization ID"),
+        ("user_id" = i32, Path, description = "User ID")
+    ),
+    request_body = PutOrganizationMember,
+    responses(
+        (status = 200, description = "Member role changed"),
+        (status = 403, description = "Only owners can change roles", body = ErrorBody),
+        (status = 404, description = "Member not found", body = ErrorBody),
+        (status = 409, description = "The last owner cannot be demoted", body = ErrorBody)
+    )
+)]
+#[put("/organizations/{organization_id}/members/{user_id}")]
+pub async fn put_organization_member(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    path: web::Path<(i32, i32)>,
+    body: web::Json<PutOrganizationMember>,
+) -> Result<HttpResponse, ApiError> {
+    let (organization_id, member_id) = path.into_inner();
+    require_role(&app_state, organization_id, user.id(), Role::Owner)?;
+    app_state.store.organization(organization_id).set_member(member_id, body.role)?;
+    Ok(HttpResponse::Ok().body("Member updated"))
+}
+
+#[utoipa::path(
+    tags=["organizations"],
+    params(
+        ("organization_id" = i32, Path, description = "Organization ID"),
+        ("user_id" = i32, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Member removed, members may also remove themselves"),
+        (status = 403, description = "Only owners can remove other members", body = ErrorBody),
+        (status = 404, description = "Member not found", body = ErrorBody),
+        (status = 409, description = "The last owner cannot be removed", body = ErrorBody)
+    )
+)]
+#[delete("/organizations/{organization_id}/members/{user_id}")]
+pub async fn delete_organization_member(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (organization_id, member_id) = path.into_inner();
+    let required = if member_id == user.id() { Role::Viewer } else { Role::Owner };
+    require_role(&app_state, organization_id, user.id(), required)?;
+    app_state.store.organization(organization_id).remove_member(member_id)?;
+    Ok(HttpResponse::Ok().body("Member removed"))
+}
+
+#[utoipa::path(
+    tags=["organizations"],
+    responses(
+        (status = 200, description = "Organizations inviting the logged in user, with the offered role", body = [Organization]),
+        (status = 401, description = "Not logged in")
+    )
+)]
+#[get("/invitations")]
+pub async fn get_invitations(app_state: web::Data<AppState>, user: CurrentUser) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(app_state.store.get_invitations(user.id())?
+        .into_iter()
+        .map(|(id, o)| summary(id, o, user.id(), true))
+        .collect::<Vec<_>>()))
+}
+
+#[utoipa::path(
+    tags=["organizations"],
+    params(
+        ("organization_id" = i32, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Invitation accepted", body = Organization),
+        (status = 404, description = "Invitation not found", body = ErrorBody)
+    )
+)]
+#[post("/invitations/{organization_id}/accept")]
+pub async fn accept_invitation(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let organization_id = path.into_inner();
+    let organization = app_state.store.organization(organization_id);
+    organization.accept_invitation(user.id())?;
+    let o = organization.get()?.ok_or_else(|| ApiError::not_found("Organization not found"))?;
+    Ok(HttpResponse::Ok().json(summary(organization_id, o, user.id(), false)))
+}
+
+#[utoipa::path(
+    tags=["organizations"],
+    params(
+        ("organization_id" = i32, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Invitation declined"),
+        (status = 404, description = "Invitation not found", body = ErrorBody)
+    )
+)]
+#[delete("/invitations/{organization_id}")]
+pub async fn decline_invitation(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    app_state.store.organization(path.into_inner()).decline_invitation(user.id())?
+        .ok_or_else(|| ApiError::not_found("Invitation not found"))?;
+    Ok(HttpResponse::Ok().body("Invitation declined"))
+}
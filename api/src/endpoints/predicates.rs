@@ -1,5 +1,5 @@
 use super::prelude::*;
-use crate::db::models::Predicate;
+use grpg_core::db::models::Predicate;
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PostPredicate {
@@ -11,9 +11,9 @@ pub struct PostPredicate {
     responses((status = 200, body = [Predicate])),
 )]
 #[get("/settings/{setting_id}/predicates")]
-pub async fn get_predicates(app_state: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
+pub async fn get_predicates(app_state: web::Data<AppState>, path: web::Path<i32>) -> Result<HttpResponse, ApiError> {
     let setting_id = path.into_inner();
-    HttpResponse::Ok().json(app_state.graph(setting_id).predicate_all())
+    Ok(HttpResponse::Ok().json(app_state.graph(setting_id).predicate_all()?))
 }
 
 
@@ -27,7 +27,7 @@ pub async fn post_predicate(
     app_state: web::Data<AppState>,
     predicate: web::Json<PostPredicate>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let setting_id = path.into_inner();
-    HttpResponse::Ok().json(app_state.graph(setting_id).predicate_create(&predicate.label))
+    Ok(HttpResponse::Ok().json(app_state.graph(setting_id).predicate_create(&predicate.label)?))
 }
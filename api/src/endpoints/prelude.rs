@@ -2,3 +2,4 @@ pub use actix_web::{delete, get, put, post, web, HttpResponse, Responder};
 pub use serde::{Deserialize, Serialize};
 pub use utoipa::{ToSchema, IntoParams};
 pub use crate::AppState;
+pub use crate::error::{ApiError, ErrorBody};
@@ -1,82 +1,126 @@
     use super::prelude::*;
+use crate::auth::CurrentUser;
+use grpg_core::db::models::{
+    Quota,
+    Role
+};
 
 #[derive(Serialize, Deserialize, ToSchema)]
 struct PostSetting {
     pub name: String,
-    pub user_id: i32
-
+    /// Organization to create the setting in, the caller must be one of its editors
+    pub organization_id: Option<i32>
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
 struct Setting {
     pub id: i32,
     pub name: String,
-    pub user_id: i32
+    pub user_id: i32,
+    pub organization_id: Option<i32>,
+    /// Role of the logged in user on the setting
+    pub role: Option<Role>
 }
 
 #[utoipa::path(
     tags=["settings"],
     request_body = PostSetting,
-    responses((status = 200, body = Setting))
+    responses(
+        (status = 200, body = Setting),
+        (status = 401, description = "Not logged in", body = ErrorBody),
+        (status = 403, description = "Organization not editable by the user", body = ErrorBody),
+        (status = 404, description = "Organization not found", body = ErrorBody)
+    )
 )]
 #[post("/settings")]
 pub async fn post_setting(
     app_state: web::Data<AppState>,
+    user: CurrentUser,
     body: web::Json<PostSetting>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let setting = body.into_inner();
-     app_state.store.user_settings(setting.user_id)
-        .add_setting(setting.name.clone())
-        .map(|id| HttpResponse::Ok().json(Setting {
-            id,
-            name: setting.name,
-            user_id: setting.user_id
-        }))
-        .unwrap_or_else(
-            |_| HttpResponse::InternalServerError().body("Failed to create setting")
-        )
+    let id = app_state.store.user_settings(user.id())
+        .add_setting(setting.name.clone(), setting.organization_id)?;
+    Ok(HttpResponse::Ok().json(Setting {
+        id,
+        name: setting.name,
+        user_id: user.id(),
+        organization_id: setting.organization_id,
+        role: Some(Role::Owner)
+    }))
 }
 
 #[utoipa::path(
     tags=["settings"],
     responses(
         (status = 200, body = [Setting]),
-        (status = 404, description = "User not found")
+        (status = 404, description = "Setting not found", body = ErrorBody)
     )
 )]
 #[get("/settings/{setting_id}")]
-pub async fn get_setting_by_id(app_state: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
+pub async fn get_setting_by_id(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    path: web::Path<i32>
+) -> Result<HttpResponse, ApiError> {
     let setting_id = path.into_inner();
-    if let Some(setting) = app_state.store.get_setting(setting_id) {
-        HttpResponse::Ok().json(Setting {
-            id: setting_id,
-            name: setting.name,
-            user_id: setting.user_id
-        })
-    } else {
-        HttpResponse::NotFound().body("Setting not found")
-    }
-}
-#[derive(Deserialize, Serialize)]
-struct GetSettingsQuery {
-    user_id: i32
+    let setting = app_state.store.get_setting(setting_id)?
+        .ok_or_else(|| ApiError::not_found("Setting not found"))?;
+    Ok(HttpResponse::Ok().json(Setting {
+        id: setting_id,
+        role: app_state.store.conn(setting_id).get_role(user.id())?,
+        organization_id: setting.organization_id,
+        name: setting.name,
+        user_id: setting.user_id
+    }))
 }
 #[utoipa::path(
     tags=["settings"],
-    params(
-        ("user_id" = i32, Query, description = "User ID")
-    ),
-    responses((status = 200, body = [Setting])))]
+    responses(
+        (status = 200, description = "Settings owned by or shared with the logged in user, directly or through an organization", body = [Setting]),
+        (status = 401, description = "Not logged in", body = ErrorBody)
+    ))]
 #[get("/settings")]
-pub async fn get_settings(app_state: web::Data<AppState>, query: web::Query<GetSettingsQuery>) -> impl Responder {
-    let user_id = query.user_id;
-    if let Some(settings) = app_state.store.user_settings(user_id).get_settings() {
-        HttpResponse::Ok().json(settings.into_iter().map(|s| Setting {
-            id: s.0,
-            name: s.1.name,
-            user_id: s.1.user_id
-        }).collect::<Vec<_>>())
-    } else {
-        HttpResponse::NotFound().body("No settings found for this user")
-    }
+pub async fn get_settings(app_state: web::Data<AppState>, user: CurrentUser) -> Result<HttpResponse, ApiError> {
+    let settings = app_state.store.user_settings(user.id()).get_settings()?;
+    Ok(HttpResponse::Ok().json(settings.into_iter().filter(|s| user.can_access(s.0)).map(|s| Setting {
+        id: s.0,
+        role: Some(s.2),
+        organization_id: s.1.organization_id,
+        name: s.1.name,
+        user_id: s.1.user_id
+    }).collect::<Vec<_>>()))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct Usage {
+    pub nodes: u64,
+    pub triples: u64,
+    pub tables: u64
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct QuotaResponse {
+    pub quota: Quota,
+    pub usage: Usage
+}
+
+#[utoipa::path(
+    tags=["settings"],
+    responses(
+        (status = 200, description = "Limits of the setting and how much of them is used", body = QuotaResponse)
+    )
+)]
+#[get("/settings/{setting_id}/quota")]
+pub async fn get_quota(app_state: web::Data<AppState>, path: web::Path<i32>) -> Result<HttpResponse, ApiError> {
+    let setting_id = path.into_inner();
+    let graph = app_state.graph(setting_id);
+    Ok(HttpResponse::Ok().json(QuotaResponse {
+        quota: app_state.store.quota(),
+        usage: Usage {
+            nodes: graph.node_count()?,
+            triples: graph.triple_count()?,
+            tables: app_state.store.conn(setting_id).get_tables()?.map_or(0, |t| t.len() as u64)
+        }
+    }))
 }
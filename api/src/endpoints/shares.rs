@@ -0,0 +1,271 @@
+use super::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+use grpg_core::db::models::{
+    Node,
+    Predicate,
+    ShareLink
+};
+use grpg_core::db::models::Triple;
+use crate::formats::table::{
+    label_table,
+    LabeledRow
+};
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PostShare {
+    /// Table to share, the whole setting when absent
+    pub table_id: Option<i32>,
+    /// Seconds until the link expires, never when absent
+    pub expires_in: Option<u64>
+}
+
+/// A share link. The token is only returned when it is created.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ShareResponse {
+    pub id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(flatten)]
+    pub details: ShareLink
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SharedSetting {
+    pub name: String,
+    /// Table the link is limited to, the whole setting when absent
+    pub table_id: Option<i32>,
+    pub expires_at: Option<u64>
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SharedTable {
+    pub id: i32,
+    pub label: String,
+    pub headers: Vec<String>,
+    pub rows: Vec<LabeledRow>
+}
+
+#[utoipa::path(
+    tags=["shares"],
+    request_body = PostShare,
+    responses(
+        (status = 200, body = ShareResponse),
+        (status = 400, description = "Expiry out of range", body = ErrorBody),
+        (status = 404, description = "Setting or table not found", body = ErrorBody)
+    )
+)]
+#[post("/settings/{setting_id}/shares")]
+pub async fn post_share(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+    body: web::Json<PostShare>,
+) -> Result<HttpResponse, ApiError> {
+    let setting_id = path.into_inner();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let expires_at = match body.expires_in {
+        Some(seconds) => Some(now.checked_add(seconds).ok_or_else(|| ApiError::invalid("expires_in is too large"))?),
+        None => None,
+    };
+    let (id, token, details) = app_state.store.conn(setting_id).add_share(body.table_id, expires_at)?;
+    Ok(HttpResponse::Ok().json(ShareResponse {
+        id,
+        token: Some(token),
+        details
+    }))
+}
+
+#[utoipa::path(
+    tags=["shares"],
+    responses(
+        (status = 200, body = [ShareResponse]),
+        (status = 404, description = "Setting not found", body = ErrorBody)
+    )
+)]
+#[get("/settings/{setting_id}/shares")]
+pub async fn get_shares(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let shares = app_state.store.conn(path.into_inner()).get_shares()?
+        .ok_or_else(|| ApiError::not_found("Setting not found"))?;
+    let mut shares = shares.into_iter()
+        .map(|(id, details)| ShareResponse { id, token: None, details })
+        .collect::<Vec<_>>();
+    shares.sort_by_key(|s| s.id);
+    Ok(HttpResponse::Ok().json(shares))
+}
+
+#[utoipa::path(
+    tags=["shares"],
+    params(
+        ("share_id" = i32, Path, description = "Share link ID")
+    ),
+    responses(
+        (status = 200, description = "Share link revoked"),
+        (status = 404, description = "Share link not found", body = ErrorBody)
+    )
+)]
+#[delete("/settings/{setting_id}/shares/{share_id}")]
+pub async fn delete_share(
+    app_state: web::Data<AppState>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, share_id) = path.into_inner();
+    app_state.store.conn(setting_id).remove_share(share_id)?
+        .ok_or_else(|| ApiError::not_found("Share link not found"))?;
+    Ok(HttpResponse::Ok().body("Share link revoked"))
+}
+
+/// Setting of a share token, failing with 404 for unknown, revoked or expired
+/// links and for links limited to a single table when `whole_setting` is asked.
+fn shared_setting(app_state: &AppState, token: &str, whole_setting: bool) -> Result<(i32, ShareLink), ApiError> {
+    match app_state.store.get_share(token)? {
+        Some((_, share)) if whole_setting && share.table_id.is_some() => {
+            Err(ApiError::not_found("Link only shares a table"))
+        }
+        Some(shared) => Ok(shared),
+        None => Err(ApiError::not_found("Share link not found")),
+    }
+}
+
+#[utoipa::path(
+    tags=["shares"],
+    params(
+        ("token" = String, Path, description = "Share token")
+    ),
+    responses(
+        (status = 200, body = SharedSetting),
+        (status = 404, description = "Share link not found or expired", body = ErrorBody)
+    )
+)]
+#[get("/shared/{token}")]
+pub async fn get_shared(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, share) = shared_setting(&app_state, &path.into_inner(), false)?;
+    let setting = app_state.store.get_setting(setting_id)?
+        .ok_or_else(|| ApiError::not_found("Share link not found"))?;
+    Ok(HttpResponse::Ok().json(SharedSetting {
+        name: setting.name,
+        table_id: share.table_id,
+        expires_at: share.expires_at
+    }))
+}
+
+#[utoipa::path(
+    tags=["shares"],
+    params(
+        ("token" = String, Path, description = "Share token"),
+        ("table_id" = i32, Path, description = "Table ID")
+    ),
+    responses(
+        (status = 200, description = "Table rows with the labels of their nodes", body = SharedTable),
+        (status = 404, description = "Share link or table not found", body = ErrorBody)
+    )
+)]
+#[get("/shared/{token}/tables/{table_id}")]
+pub async fn get_shared_table(
+    app_state: web::Data<AppState>,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (token, table_id) = path.into_inner();
+    let (setting_id, share) = shared_setting(&app_state, &token, false)?;
+    if share.table_id.is_some_and(|id| id != table_id) {
+        return Err(ApiError::not_found("Table not found"));
+    }
+    let table_def = app_state.store.conn(setting_id).get_table(table_id)?
+        .ok_or_else(|| ApiError::not_found("Table not found"))?;
+    let graph = app_state.graph(setting_id);
+    let rows = graph.table_rows(table_def.clone()).await?;
+    let table = label_table(&table_def, rows, graph.node_all()?, graph.predicate_all()?);
+    Ok(HttpResponse::Ok().json(SharedTable {
+        id: table_id,
+        label: table_def.label,
+        headers: table.headers,
+        rows: table.rows
+    }))
+}
+
+#[utoipa::path(
+    tags=["shares"],
+    params(
+        ("token" = String, Path, description = "Share token")
+    ),
+    responses(
+        (status = 200, description = "Ids and labels of the shared setting's tables", body = [SharedTable]),
+        (status = 404, description = "Share link not found or expired", body = ErrorBody)
+    )
+)]
+#[get("/shared/{token}/tables")]
+pub async fn get_shared_tables(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, share) = shared_setting(&app_state, &path.into_inner(), false)?;
+    let mut tables = app_state.store.conn(setting_id).get_tables()?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(id, _)| share.table_id.is_none_or(|t| t == *id))
+        .map(|(id, def)| SharedTable { id, label: def.label, headers: Vec::new(), rows: Vec::new() })
+        .collect::<Vec<_>>();
+    tables.sort_by_key(|t| t.id);
+    Ok(HttpResponse::Ok().json(tables))
+}
+
+#[utoipa::path(
+    tags=["shares"],
+    params(
+        ("token" = String, Path, description = "Share token")
+    ),
+    responses(
+        (status = 200, body = [Node]),
+        (status = 404, description = "Share link not found or limited to a table", body = ErrorBody)
+    )
+)]
+#[get("/shared/{token}/nodes")]
+pub async fn get_shared_nodes(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, _) = shared_setting(&app_state, &path.into_inner(), true)?;
+    Ok(HttpResponse::Ok().json(app_state.graph(setting_id).node_all()?))
+}
+
+#[utoipa::path(
+    tags=["shares"],
+    params(
+        ("token" = String, Path, description = "Share token")
+    ),
+    responses(
+        (status = 200, body = [Predicate]),
+        (status = 404, description = "Share link not found or limited to a table", body = ErrorBody)
+    )
+)]
+#[get("/shared/{token}/predicates")]
+pub async fn get_shared_predicates(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, _) = shared_setting(&app_state, &path.into_inner(), true)?;
+    Ok(HttpResponse::Ok().json(app_state.graph(setting_id).predicate_all()?))
+}
+
+#[utoipa::path(
+    tags=["shares"],
+    params(
+        ("token" = String, Path, description = "Share token")
+    ),
+    responses(
+        (status = 200, body = [Triple]),
+        (status = 404, description = "Share link not found or limited to a table", body = ErrorBody)
+    )
+)]
+#[get("/shared/{token}/triples")]
+pub async fn get_shared_triples(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, _) = shared_setting(&app_state, &path.into_inner(), true)?;
+    Ok(HttpResponse::Ok().json(app_state.graph(setting_id).triple_all()?))
+}
@@ -0,0 +1,65 @@
+use super::prelude::*;
+use actix_web::HttpRequest;
+use crate::sparql;
+
+#[derive(Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in=Query)]
+pub struct SparqlQuery {
+    /// SPARQL SELECT query
+    pub query: String,
+}
+
+fn respond(app_state: &AppState, setting_id: i32, query: &str) -> Result<HttpResponse, ApiError> {
+    let results = sparql::execute(&app_state.graph(setting_id), query)?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/sparql-results+json")
+        .body(results.to_string()))
+}
+
+#[utoipa::path(
+    tags=["query"],
+    params(SparqlQuery),
+    responses(
+        (status = 200, description = "SPARQL JSON results", body = Object, content_type = "application/sparql-results+json"),
+        (status = 400, body = ErrorBody),
+        (status = 501, description = "The graph backend cannot run queries", body = ErrorBody)
+    )
+)]
+#[get("/settings/{setting_id}/sparql")]
+pub async fn get_sparql(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<SparqlQuery>,
+) -> Result<HttpResponse, ApiError> {
+    respond(&app_state, path.into_inner(), &query.query)
+}
+
+#[utoipa::path(
+    tags=["query"],
+    request_body(content = String, description = "SPARQL SELECT query, or a form with a `query` field", content_type = "application/sparql-query"),
+    responses(
+        (status = 200, description = "SPARQL JSON results", body = Object, content_type = "application/sparql-results+json"),
+        (status = 400, body = ErrorBody),
+        (status = 501, description = "The graph backend cannot run queries", body = ErrorBody)
+    )
+)]
+#[post("/settings/{setting_id}/sparql")]
+pub async fn post_sparql(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let setting_id = path.into_inner();
+    let is_form = req.headers().get("Content-Type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"));
+    let query = if is_form {
+        web::Query::<SparqlQuery>::from_query(&String::from_utf8_lossy(&body))
+            .map_err(|_| ApiError::invalid("Missing query field"))?
+            .into_inner().query
+    } else {
+        String::from_utf8(body.to_vec()).map_err(|_| ApiError::invalid("Query is not valid UTF-8"))?
+    };
+    respond(&app_state, setting_id, &query)
+}
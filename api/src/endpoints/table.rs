@@ -1,14 +1,15 @@
 use super::prelude::*;
-use crate::db::models::{
+use grpg_core::db::models::{
     TableDefinition
 };
-use crate::db::models::RowResponse;
-
-#[derive(Deserialize, Serialize, ToSchema)]
-pub struct TableRow {
-    pub node_id: i32,
-    pub label: Option<String>,
-}
+use grpg_core::db::models::RowResponse;
+use crate::formats::table::{
+    TableFormat,
+    label_table,
+    csv_lines,
+    jsonl_lines
+};
+use futures_util::stream;
 
 
 #[utoipa::path(
@@ -19,7 +20,8 @@ pub struct TableRow {
     request_body = TableDefinition,
     responses(
         (status = 200, body = [RowResponse]),
-        (status = 404, body = String)
+        (status = 403, description = "Table quota of the setting reached by a new table", body = ErrorBody),
+        (status = 404, body = ErrorBody)
     )
 )]
 #[put("/settings/{setting_id}/table/{id}")]
@@ -27,14 +29,11 @@ pub async fn put_table(
     app_state: web::Data<AppState>,
     params: web::Json<TableDefinition>,
     path: web::Path<(i32, i32)>
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let (setting_id, id) = path.into_inner();
     let table = params.into_inner();
-    if let Ok(_) = app_state.store.conn(setting_id).set_table(id, table.clone()) {
-        HttpResponse::Ok().json(app_state.graph(setting_id).table_rows(table).await)
-    } else {
-        HttpResponse::NotFound().body("Table not found")
-    }
+    app_state.store.conn(setting_id).set_table(id, table.clone())?;
+    Ok(HttpResponse::Ok().json(app_state.graph(setting_id).table_rows(table).await?))
 }
 
 
@@ -47,22 +46,21 @@ pub struct Table {
 
 #[utoipa::path(
     tags=["tables"],
-    responses((status = 200, body = [RowResponse]))
+    responses(
+        (status = 200, body = [RowResponse]),
+        (status = 403, description = "Table quota of the setting reached", body = ErrorBody)
+    )
 )]
 #[post("/settings/{setting_id}/table")]
 pub async fn post_table(
     app_state: web::Data<AppState>,
     params: web::Json<TableDefinition>,
     path: web::Path<i32>
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let setting_id = path.into_inner();
     let table = params.into_inner();
-    if let None = app_state.store.conn(setting_id).add_table(table.clone()) {
-        HttpResponse::InternalServerError().body("Failed to create table")
-    } else {
-        HttpResponse::Ok().json(app_state.graph(setting_id).table_rows(table).await)
-    }
-
+    app_state.store.conn(setting_id).add_table(table.clone())?;
+    Ok(HttpResponse::Ok().json(app_state.graph(setting_id).table_rows(table).await?))
 }
 
 #[utoipa::path(
@@ -70,47 +68,52 @@ pub async fn post_table(
     params(
         ("id" = i32, Path, description = "Table ID")
     ),
-    responses((status = 200, body = Table))
+    responses(
+        (status = 200, body = Table),
+        (status = 404, description = "Table not found", body = ErrorBody)
+    )
 )]
 #[get("/settings/{setting_id}/table/{id}")]
 pub async fn get_table(
     app_state: web::Data<AppState>,
     path: web::Path<(i32, i32)>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let (setting_id, id) = path.into_inner();
-    if let Some(table_def) = app_state.store.conn(setting_id).get_table(id) {
-        let rows = app_state.graph(setting_id).table_rows(table_def.clone()).await;
-        HttpResponse::Ok().json( Table {
-            id: id,
-            def: table_def,
-            rows: rows
-        })
-    } else {
-        HttpResponse::NotFound().body("Table not found")
-    }
+    let table_def = app_state.store.conn(setting_id).get_table(id)?
+        .ok_or_else(|| ApiError::not_found("Table not found"))?;
+    let rows = app_state.graph(setting_id).table_rows(table_def.clone()).await?;
+    Ok(HttpResponse::Ok().json( Table {
+        id,
+        def: table_def,
+        rows
+    }))
 }
 
 #[utoipa::path(
     tags=["tables"],
-    responses((status = 200, body = [Table]))
+    responses(
+        (status = 200, body = [Table]),
+        (status = 404, description = "Setting not found", body = ErrorBody)
+    )
 )]
 #[get("/settings/{setting_id}/tables")]
 pub async fn get_tables(
     app_state: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let setting_id = path.into_inner();
-    let tables = app_state.store.conn(setting_id).get_tables();
+    let tables = app_state.store.conn(setting_id).get_tables()?
+        .ok_or_else(|| ApiError::not_found("Setting not found"))?;
     let mut result = Vec::new();
-    for (id, def) in tables.unwrap() {
-        let rows = app_state.graph(setting_id).table_rows(def.clone()).await;
+    for (id, def) in tables {
+        let rows = app_state.graph(setting_id).table_rows(def.clone()).await?;
         result.push(Table {
             id,
             def: def.clone(),
             rows,
         });
     }
-    HttpResponse::Ok().json(result)
+    Ok(HttpResponse::Ok().json(result))
 }
 
 #[utoipa::path(
@@ -118,17 +121,63 @@ pub async fn get_tables(
     params(
         ("id" = i32, Path, description = "Table ID")
     ),
-    responses((status = 200, body = String), (status = 404, body = String))
+    responses((status = 200, body = String), (status = 404, body = ErrorBody))
 )]
 #[delete("/settings/{setting_id}/tables/{id}")]
 pub async fn delete_table(
     app_state: web::Data<AppState>,
     path: web::Path<(i32, i32)>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let (setting_id, id) = path.into_inner();
-    if app_state.store.conn(setting_id).remove_table(id).is_some() {
-        HttpResponse::Ok().body(format!("Table {} deleted", id))
-    } else {
-        HttpResponse::NotFound().body("Table not found")
-    }
+    app_state.store.conn(setting_id).remove_table(id)?
+        .ok_or_else(|| ApiError::not_found("Table not found"))?;
+    Ok(HttpResponse::Ok().body(format!("Table {} deleted", id)))
+}
+
+#[derive(Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in=Query)]
+pub struct TableExportQuery {
+    /// Output format of the export
+    pub format: TableFormat,
+    /// Separator used to join cells holding several values (CSV only)
+    pub separator: Option<String>,
+}
+
+#[utoipa::path(
+    tags=["tables"],
+    params(
+        ("id" = i32, Path, description = "Table ID"),
+        TableExportQuery
+    ),
+    responses(
+        (status = 200, description = "Table rows as CSV or JSON Lines", body = String),
+        (status = 404, body = ErrorBody)
+    )
+)]
+#[get("/settings/{setting_id}/table/{id}/export")]
+pub async fn export_table(
+    app_state: web::Data<AppState>,
+    path: web::Path<(i32, i32)>,
+    query: web::Query<TableExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, id) = path.into_inner();
+    let query = query.into_inner();
+    let table_def = app_state.store.conn(setting_id).get_table(id)?
+        .ok_or_else(|| ApiError::not_found("Table not found"))?;
+    let graph = app_state.graph(setting_id);
+    let rows = graph.table_rows(table_def.clone()).await?;
+    let table = label_table(&table_def, rows, graph.node_all()?, graph.predicate_all()?);
+
+    let lines: Box<dyn Iterator<Item = Result<String, actix_web::Error>>> = match query.format {
+        TableFormat::Csv => Box::new(csv_lines(table, query.separator.unwrap_or_else(|| "|".to_string())).map(Ok)),
+        TableFormat::Jsonl => Box::new(jsonl_lines(table).map(|line| line.map_err(actix_web::error::ErrorInternalServerError))),
+    };
+    let body = stream::iter(lines.map(|line| line.map(web::Bytes::from)));
+    Ok(HttpResponse::Ok()
+        .content_type(query.format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"table-{}.{}\"", id, query.format.extension())
+        ))
+        .streaming(body))
 }
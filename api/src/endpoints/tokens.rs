@@ -0,0 +1,88 @@
+use super::prelude::*;
+use crate::auth::CurrentUser;
+use grpg_core::db::models::{
+    ApiToken,
+    TokenScope
+};
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PostToken {
+    pub name: String,
+    pub scope: TokenScope,
+    /// Settings the token is limited to, every setting of the user when absent
+    pub settings: Option<Vec<i32>>
+}
+
+/// An API token. The token itself is only returned when it is created.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TokenResponse {
+    pub id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(flatten)]
+    pub details: ApiToken
+}
+
+#[utoipa::path(
+    tags=["tokens"],
+    request_body = PostToken,
+    responses(
+        (status = 200, body = TokenResponse),
+        (status = 401, description = "Not logged in", body = ErrorBody),
+        (status = 403, description = "Token limited to settings cannot create tokens", body = ErrorBody)
+    )
+)]
+#[post("/tokens")]
+pub async fn post_token(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    body: web::Json<PostToken>,
+) -> Result<HttpResponse, ApiError> {
+    if user.settings.is_some() {
+        return Err(ApiError::forbidden("Token limited to settings cannot create tokens"));
+    }
+    let body = body.into_inner();
+    let (id, token, details) = app_state.store.add_api_token(user.id(), body.name, body.scope, body.settings)?;
+    Ok(HttpResponse::Ok().json(TokenResponse {
+        id,
+        token: Some(token),
+        details
+    }))
+}
+
+#[utoipa::path(
+    tags=["tokens"],
+    responses(
+        (status = 200, body = [TokenResponse]),
+        (status = 401, description = "Not logged in", body = ErrorBody)
+    )
+)]
+#[get("/tokens")]
+pub async fn get_tokens(app_state: web::Data<AppState>, user: CurrentUser) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(app_state.store.get_api_tokens(user.id())?
+        .into_iter()
+        .map(|(id, details)| TokenResponse { id, token: None, details })
+        .collect::<Vec<_>>()))
+}
+
+#[utoipa::path(
+    tags=["tokens"],
+    params(
+        ("token_id" = i32, Path, description = "Token ID")
+    ),
+    responses(
+        (status = 200, description = "Token revoked"),
+        (status = 401, description = "Not logged in", body = ErrorBody),
+        (status = 404, description = "Token not found", body = ErrorBody)
+    )
+)]
+#[delete("/tokens/{token_id}")]
+pub async fn delete_token(
+    app_state: web::Data<AppState>,
+    user: CurrentUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    app_state.store.remove_api_token(user.id(), path.into_inner())?
+        .ok_or_else(|| ApiError::not_found("Token not found"))?;
+    Ok(HttpResponse::Ok().body("Token revoked"))
+}
@@ -1,26 +1,23 @@
 use super::prelude::*;
-
-#[derive(Deserialize, Serialize, ToSchema)]
-pub struct Triple {
-    pub subject_id: i32,
-    pub predicate_id: i32,
-    pub object_id: i32,
-}
+use grpg_core::db::models::Triple;
 
 #[utoipa::path(
     tags=["triples"],
     request_body = Triple,
-    responses((status = 200))
+    responses(
+        (status = 200),
+        (status = 403, description = "Triple quota of the setting reached", body = ErrorBody)
+    )
 )]
 #[post("/settings/{setting_id}/triple")]
 pub async fn post_triple(
     app_state: web::Data<AppState>,
     triple: web::Json<Triple>,
     path : web::Path<i32>
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let setting_id = path.into_inner();
-    app_state.graph(setting_id).triple_create(triple.into_inner());
-    HttpResponse::Ok()
+    app_state.graph(setting_id).triple_create(triple.into_inner())?;
+    Ok(HttpResponse::Ok().finish())
 }
 #[utoipa::path(
     tags=["triples"],
@@ -32,10 +29,10 @@ pub async fn delete_triple(
     app_state: web::Data<AppState>,
     triple: web::Json<Triple>,
     path: web::Path<i32>
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let setting_id = path.into_inner();
-    app_state.graph(setting_id).triple_delete(triple.into_inner());
-    HttpResponse::Ok()
+    app_state.graph(setting_id).triple_delete(triple.into_inner())?;
+    Ok(HttpResponse::Ok().finish())
 }
 
 #[utoipa::path(
@@ -43,7 +40,7 @@ pub async fn delete_triple(
     responses((status = 200, body = [Triple]))
 )]
 #[get("/settings/{setting_id}/triples")]
-pub async fn get_triples(app_state: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
+pub async fn get_triples(app_state: web::Data<AppState>, path: web::Path<i32>) -> Result<HttpResponse, ApiError> {
     let setting_id = path.into_inner();
-    HttpResponse::Ok().json(app_state.graph(setting_id).triple_all())
+    Ok(HttpResponse::Ok().json(app_state.graph(setting_id).triple_all()?))
 }
@@ -1,48 +1,59 @@
 use super::prelude::*;
-use crate::db::models::UserData;
+use grpg_core::db::models::UserData;
+use crate::auth::CurrentUser;
 
 #[derive(Serialize, Deserialize, ToSchema)]
 struct PostUser {
-    pub username: String
+    pub username: String,
+    pub password: String
 }
 #[utoipa::path(
     tags=["users"],
     request_body = PostUser,
-    responses((status = 200, body = UserData))
+    responses(
+        (status = 200, body = UserData),
+        (status = 409, description = "Username already taken", body = ErrorBody)
+    )
 )]
 #[post("/users")]
 pub async fn post_user(
     app_state: web::Data<AppState>,
     body: web::Json<PostUser>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let user = body.into_inner();
-    let id = app_state.store.add_user(user.username.clone());
-    HttpResponse::Ok().json(UserData { id, name: user.username })
+    let id = app_state.store.add_user(user.username.clone(), &user.password)?;
+    Ok(HttpResponse::Ok().json(UserData { id, name: user.username }))
 }
 
 #[utoipa::path(
     tags=["users"],
     responses(
         (status = 200, body = [UserData]),
-        (status = 404, description = "User not found")
+        (status = 401, description = "Not logged in", body = ErrorBody),
+        (status = 404, description = "User not found", body = ErrorBody)
     )
 )]
 #[get("/users/{user_id}")]
-pub async fn get_user_by_id(app_state: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
+pub async fn get_user_by_id(
+    app_state: web::Data<AppState>,
+    _user: CurrentUser,
+    path: web::Path<i32>
+) -> Result<HttpResponse, ApiError> {
     let user_id = path.into_inner();
-    if let Some(user) = app_state.store.get_user(user_id) {
-        HttpResponse::Ok().json(user)
-    } else {
-        HttpResponse::NotFound().body("User not found")
-    }
+    let user = app_state.store.get_user(user_id)?
+        .ok_or_else(|| ApiError::not_found("User not found"))?;
+    Ok(HttpResponse::Ok().json(user))
 }
 
 #[utoipa::path(
     tags=["users"],
-    responses((status = 200, body = [UserData]))
+    responses(
+        (status = 200, body = [UserData]),
+        (status = 401, description = "Not logged in", body = ErrorBody)
+    )
 )]
 #[get("/users")]
-pub async fn get_users(app_state: web::Data<AppState>) -> impl Responder {
-    let users = app_state.store.get_users();
-    HttpResponse::Ok().json(users)
+pub async fn get_users(app_state: web::Data<AppState>, _user: CurrentUser) -> Result<HttpResponse, ApiError> {
+    let users = app_state.store.get_users()?;
+    Ok(HttpResponse::Ok().json(users))
 }
@@ -0,0 +1,137 @@
+use super::prelude::*;
+use grpg_core::db::models::{
+    Delivery,
+    Webhook
+};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct PostWebhook {
+    pub url: String,
+    /// Key used to sign the payloads, generated when omitted
+    pub secret: Option<String>,
+    /// Event names to deliver, such as `node_created` or `table_*`. Every event when empty
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+/// A registered webhook. The secret is only returned when it is created.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: i32,
+    pub url: String,
+    pub events: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+fn generate_secret() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes)?;
+    Ok(hex::encode(bytes))
+}
+
+#[utoipa::path(
+    tags=["webhooks"],
+    request_body = PostWebhook,
+    responses(
+        (status = 200, body = WebhookResponse),
+        (status = 400, description = "Invalid URL", body = ErrorBody),
+        (status = 404, description = "Setting not found", body = ErrorBody)
+    )
+)]
+#[post("/settings/{setting_id}/webhooks")]
+pub async fn post_webhook(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+    body: web::Json<PostWebhook>,
+) -> Result<HttpResponse, ApiError> {
+    let setting_id = path.into_inner();
+    let body = body.into_inner();
+    if !(body.url.starts_with("http://") || body.url.starts_with("https://")) {
+        return Err(ApiError::invalid("Webhook URL must use http or https"));
+    }
+    let secret = match body.secret {
+        Some(secret) => secret,
+        None => generate_secret().map_err(|_| ApiError::internal("Failed to generate secret"))?,
+    };
+    let webhook = Webhook {
+        url: body.url,
+        secret,
+        events: body.events,
+    };
+    let id = app_state.store.conn(setting_id).add_webhook(webhook.clone())?
+        .ok_or_else(|| ApiError::not_found("Setting not found"))?;
+    Ok(HttpResponse::Ok().json(WebhookResponse {
+        id,
+        url: webhook.url,
+        events: webhook.events,
+        secret: Some(webhook.secret),
+    }))
+}
+
+#[utoipa::path(
+    tags=["webhooks"],
+    responses(
+        (status = 200, body = [WebhookResponse]),
+        (status = 404, description = "Setting not found", body = ErrorBody)
+    )
+)]
+#[get("/settings/{setting_id}/webhooks")]
+pub async fn get_webhooks(
+    app_state: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let setting_id = path.into_inner();
+    let webhooks = app_state.store.conn(setting_id).get_webhooks()?
+        .ok_or_else(|| ApiError::not_found("Setting not found"))?;
+    let mut webhooks = webhooks.into_iter().map(|(id, w)| WebhookResponse {
+        id,
+        url: w.url,
+        events: w.events,
+        secret: None,
+    }).collect::<Vec<_>>();
+    webhooks.sort_by_key(|w| w.id);
+    Ok(HttpResponse::Ok().json(webhooks))
+}
+
+#[utoipa::path(
+    tags=["webhooks"],
+    params(
+        ("webhook_id" = i32, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook deleted"),
+        (status = 404, description = "Webhook not found", body = ErrorBody)
+    )
+)]
+#[delete("/settings/{setting_id}/webhooks/{webhook_id}")]
+pub async fn delete_webhook(
+    app_state: web::Data<AppState>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, webhook_id) = path.into_inner();
+    app_state.store.conn(setting_id).remove_webhook(webhook_id)?
+        .ok_or_else(|| ApiError::not_found("Webhook not found"))?;
+    Ok(HttpResponse::Ok().body("Webhook deleted"))
+}
+
+#[utoipa::path(
+    tags=["webhooks"],
+    params(
+        ("webhook_id" = i32, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Delivery attempts, oldest first", body = [Delivery]),
+        (status = 404, description = "Setting not found", body = ErrorBody)
+    )
+)]
+#[get("/settings/{setting_id}/webhooks/{webhook_id}/deliveries")]
+pub async fn get_deliveries(
+    app_state: web::Data<AppState>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (setting_id, webhook_id) = path.into_inner();
+    let deliveries = app_state.store.conn(setting_id).get_deliveries(webhook_id)?
+        .ok_or_else(|| ApiError::not_found("Setting not found"))?;
+    Ok(HttpResponse::Ok().json(deliveries))
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+use actix_web::{
+    http::StatusCode,
+    HttpResponse,
+    ResponseError
+};
+use serde::Serialize;
+use serde_json::json;
+use utoipa::ToSchema;
+use grpg_core::db::error::DbError;
+
+/// Kind of an error, each answered with its own status.
+#[derive(Clone, Copy, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// 400, the request is malformed
+    Invalid,
+    /// 401, the request needs a session or API token
+    Unauthorized,
+    /// 403, the credentials or the role do not allow the request
+    Forbidden,
+    /// 403, the setting's quota is reached
+    QuotaExceeded,
+    /// 404
+    NotFound,
+    /// 409, the request clashes with what is stored
+    Conflict,
+    /// 429, too many requests, see the `Retry-After` header
+    RateLimited,
+    /// 500
+    Internal,
+    /// 501, the graph backend cannot run the request
+    Unsupported,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::Invalid => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden | ErrorCode::QuotaExceeded => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::Unsupported => StatusCode::NOT_IMPLEMENTED,
+        }
+    }
+}
+
+/// Body of every error response.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    /// Explanation meant for people
+    pub message: String,
+    /// Data about the error, such as the resource and limit of an exceeded quota
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+}
+
+/// An error answered by an endpoint, as a JSON `ErrorBody` with the status
+/// of its code.
+#[derive(Debug)]
+pub struct ApiError(pub ErrorBody);
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl ToString) -> Self {
+        ApiError(ErrorBody {
+            code,
+            message: message.to_string(),
+            details: None,
+        })
+    }
+    pub fn invalid(message: impl ToString) -> Self {
+        ApiError::new(ErrorCode::Invalid, message)
+    }
+    pub fn unauthorized(message: impl ToString) -> Self {
+        ApiError::new(ErrorCode::Unauthorized, message)
+    }
+    pub fn forbidden(message: impl ToString) -> Self {
+        ApiError::new(ErrorCode::Forbidden, message)
+    }
+    pub fn not_found(message: impl ToString) -> Self {
+        ApiError::new(ErrorCode::NotFound, message)
+    }
+    pub fn internal(message: impl ToString) -> Self {
+        ApiError::new(ErrorCode::Internal, message)
+    }
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.0.details = Some(details);
+        self
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.message)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.0.code.status()
+    }
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(&self.0)
+    }
+}
+
+/// Failures of the storage or of the graph database are logged, their
+/// message is not shown to the client.
+impl From<DbError> for ApiError {
+    fn from(e: DbError) -> Self {
+        match e {
+            DbError::NotFound(message) => ApiError::not_found(message),
+            DbError::Invalid(message) => ApiError::invalid(message),
+            DbError::Conflict(message) => ApiError::new(ErrorCode::Conflict, message),
+            DbError::Forbidden(message) => ApiError::forbidden(message),
+            DbError::QuotaExceeded(e) => ApiError::new(ErrorCode::QuotaExceeded, &e)
+                .with_details(json!({ "resource": e.resource, "limit": e.limit })),
+            DbError::Unsupported(message) => ApiError::new(ErrorCode::Unsupported, message),
+            DbError::Backend(message) => {
+                log::error!("Database error: {}", message);
+                ApiError::internal("The database failed to run the request")
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod table;
+pub mod graph;
+pub mod jsonld;
+pub mod archive;
@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use grpg_core::db::error::DbError;
+use grpg_core::db::graph::GraphManager;
+use grpg_core::db::models::{
+    Node,
+    Predicate,
+    QuotaExceeded,
+    TableDefinition
+};
+use grpg_core::db::models::Triple;
+
+/// Version of the archive schema, raised on incompatible changes.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// A setting with everything needed to recreate it on another instance. Ids
+/// are those of the exporting instance. Members, organization, webhooks and
+/// share links belong to the instance and are left out.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SettingArchive {
+    /// Version of the archive schema
+    pub version: u32,
+    /// Seconds since the Unix epoch
+    pub exported_at: u64,
+    pub setting: ArchivedSetting,
+    pub nodes: Vec<Node>,
+    pub predicates: Vec<Predicate>,
+    pub triples: Vec<Triple>,
+    pub tables: Vec<ArchivedTable>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ArchivedSetting {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ArchivedTable {
+    pub id: i32,
+    #[serde(flatten)]
+    pub table: TableDefinition,
+}
+
+/// The setting an archive was imported as, with what was created in it.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ImportedSetting {
+    pub setting_id: i32,
+    pub nodes: usize,
+    pub predicates: usize,
+    pub triples: usize,
+    pub tables: usize,
+}
+
+/// Archive of the setting of `graph`.
+pub fn export(graph: &GraphManager) -> Result<SettingArchive, DbError> {
+    let setting = graph.store.get_setting(graph.setting)?
+        .ok_or_else(|| DbError::not_found("Setting not found"))?;
+    let mut tables: Vec<ArchivedTable> = graph.store.conn(graph.setting).get_tables()?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(id, table)| ArchivedTable { id, table })
+        .collect();
+    tables.sort_by_key(|t| t.id);
+    Ok(SettingArchive {
+        version: ARCHIVE_VERSION,
+        exported_at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        setting: ArchivedSetting { id: graph.setting, name: setting.name },
+        nodes: graph.node_all()?,
+        predicates: graph.predicate_all()?,
+        triples: graph.triple_all()?,
+        tables,
+    })
+}
+
+impl SettingArchive {
+    /// Checks the version of the archive and that its triples and tables only
+    /// refer to its own nodes and predicates.
+    pub fn validate(&self) -> Result<(), DbError> {
+        if self.version > ARCHIVE_VERSION {
+            return Err(DbError::invalid(format!("Archive version {} is newer than the supported {}", self.version, ARCHIVE_VERSION)));
+        }
+        let mut nodes = HashSet::new();
+        if let Some(node) = self.nodes.iter().find(|n| !nodes.insert(n.node_id)) {
+            return Err(DbError::invalid(format!("Node {} appears twice", node.node_id)));
+        }
+        let mut predicates = HashSet::new();
+        if let Some(predicate) = self.predicates.iter().find(|p| !predicates.insert(p.id)) {
+            return Err(DbError::invalid(format!("Predicate {} appears twice", predicate.id)));
+        }
+        for t in &self.triples {
+            if !nodes.contains(&t.subject_id) || !nodes.contains(&t.object_id) || !predicates.contains(&t.predicate_id) {
+                return Err(DbError::invalid(format!("Triple ({}, {}, {}) refers to a missing node or predicate", t.subject_id, t.predicate_id, t.object_id)));
+            }
+        }
+        for archived in &self.tables {
+            let table = &archived.table;
+            if table.filter.node_id.is_some_and(|id| !nodes.contains(&id)) {
+                return Err(DbError::invalid(format!("Table {} filters on a missing node", archived.id)));
+            }
+            let column_predicates = table.columns.iter().filter_map(|c| c.filter.predicate_id);
+            if table.filter.predicate.into_iter().chain(column_predicates).any(|id| !predicates.contains(&id)) {
+                return Err(DbError::invalid(format!("Table {} uses a missing predicate", archived.id)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Recreates the archive in the empty setting of `graph`, giving its
+    /// nodes, predicates and tables new ids and rewriting the triples and
+    /// tables referring to them. The archive must be valid and fit the
+    /// setting's quota.
+    pub fn import(&self, graph: &GraphManager) -> Result<ImportedSetting, DbError> {
+        let quota = graph.store.quota();
+        for (limit, resource, count) in [
+            (quota.max_nodes, "nodes", self.nodes.len()),
+            (quota.max_triples, "triples", self.triples.len()),
+            (quota.max_tables, "tables", self.tables.len()),
+        ] {
+            if let Some(limit) = limit.filter(|limit| count as u64 > *limit) {
+                return Err(QuotaExceeded { resource, limit }.into());
+            }
+        }
+
+        let predicates: HashMap<i32, i32> = self.predicates.iter()
+            .map(|p| Ok((p.id, graph.predicate_create(&p.label)?.id)))
+            .collect::<Result<_, DbError>>()?;
+        let mut nodes = HashMap::new();
+        for node in &self.nodes {
+            nodes.insert(node.node_id, graph.node_create(node.label.clone())?);
+        }
+        for t in &self.triples {
+            graph.triple_create(Triple {
+                subject_id: nodes[&t.subject_id],
+                predicate_id: predicates[&t.predicate_id],
+                object_id: nodes[&t.object_id],
+            })?;
+        }
+        let conn = graph.store.conn(graph.setting);
+        for archived in &self.tables {
+            let mut table = archived.table.clone();
+            table.filter.node_id = table.filter.node_id.map(|id| nodes[&id]);
+            table.filter.predicate = table.filter.predicate.map(|id| predicates[&id]);
+            for column in &mut table.columns {
+                column.filter.predicate_id = column.filter.predicate_id.map(|id| predicates[&id]);
+            }
+            conn.add_table(table)?;
+        }
+        Ok(ImportedSetting {
+            setting_id: graph.setting,
+            nodes: self.nodes.len(),
+            predicates: self.predicates.len(),
+            triples: self.triples.len(),
+            tables: self.tables.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use grpg_core::db::base::Store;
+    use grpg_core::db::events::Events;
+    use grpg_core::db::graph::MemoryBackend;
+    use grpg_core::db::models::{
+        ColumnDefinition,
+        ColumnFilter,
+        Filter,
+        Quota
+    };
+    use super::*;
+
+    #[test]
+    fn imports_archives_under_new_ids() {
+        let backend = MemoryBackend::new();
+        let events = Arc::new(Events::new());
+        let store = Store::in_memory(events.clone(), Quota::default());
+        let ada = store.add_user("ada".to_string(), "secret").unwrap();
+        let source = store.user_settings(ada).add_setting("family".to_string(), None).unwrap();
+        let target = store.user_settings(ada).add_setting("copy".to_string(), None).unwrap();
+        let graph = GraphManager { backend: &backend, setting: source, events: &events, store: &store };
+        let removed = graph.node_create("removed".to_string()).unwrap();
+        graph.node_delete(removed).unwrap();
+        let ada_node = graph.node_create("ada".to_string()).unwrap();
+        let byron = graph.node_create("byron".to_string()).unwrap();
+        let parent = graph.predicate_create("parent of").unwrap().id;
+        graph.triple_create(Triple { subject_id: ada_node, predicate_id: parent, object_id: byron }).unwrap();
+        store.conn(source).add_table(TableDefinition {
+            label: "children".to_string(),
+            filter: Filter { node_id: Some(ada_node), predicate: Some(parent), direction: None },
+            columns: vec![ColumnDefinition { id: 1, filter: ColumnFilter { direction: None, predicate_id: Some(parent) } }],
+        }).unwrap();
+
+        let archive = export(&graph).unwrap();
+        assert_eq!(archive.setting.name, "family");
+        archive.validate().unwrap();
+        let copy = GraphManager { backend: &backend, setting: target, events: &events, store: &store };
+        let imported = archive.import(&copy).unwrap();
+        assert_eq!((imported.nodes, imported.predicates, imported.triples, imported.tables), (2, 1, 1, 1));
+
+        let nodes: HashMap<String, i32> = copy.node_all().unwrap().into_iter().map(|n| (n.label, n.node_id)).collect();
+        assert_ne!(nodes["ada"], ada_node);
+        let triple = &copy.triple_all().unwrap()[0];
+        assert_eq!((triple.subject_id, triple.object_id), (nodes["ada"], nodes["byron"]));
+        let tables = store.conn(target).get_tables().unwrap().unwrap();
+        let table = tables.values().next().unwrap();
+        assert_eq!(table.filter.node_id, Some(nodes["ada"]));
+        assert_eq!(table.filter.predicate, Some(triple.predicate_id));
+    }
+
+    #[test]
+    fn rejects_archives_referring_outside_themselves() {
+        let mut archive = SettingArchive {
+            version: ARCHIVE_VERSION,
+            exported_at: 0,
+            setting: ArchivedSetting { id: 1, name: "family".to_string() },
+            nodes: vec![Node { node_id: 1, label: "ada".to_string() }],
+            predicates: vec![Predicate { id: 1, label: "parent of".to_string() }],
+            triples: vec![Triple { subject_id: 1, predicate_id: 1, object_id: 2 }],
+            tables: Vec::new(),
+        };
+        assert!(matches!(archive.validate(), Err(DbError::Invalid(_))));
+        archive.nodes.push(Node { node_id: 2, label: "byron".to_string() });
+        archive.validate().unwrap();
+        archive.nodes.push(Node { node_id: 2, label: "byron".to_string() });
+        assert!(matches!(archive.validate(), Err(DbError::Invalid(_))));
+        archive.nodes.pop();
+        archive.version = ARCHIVE_VERSION + 1;
+        assert!(matches!(archive.validate(), Err(DbError::Invalid(_))));
+    }
+}
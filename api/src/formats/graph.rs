@@ -0,0 +1,269 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+use grpg_core::db::models::{
+    GraphDirection,
+    Node,
+    Predicate
+};
+use grpg_core::db::models::Triple;
+use crate::formats::jsonld;
+
+#[derive(Deserialize, Serialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphFormat {
+    Graphml,
+    Gexf,
+    Cytoscape,
+    Jsonld,
+}
+impl GraphFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Graphml => "application/graphml+xml",
+            GraphFormat::Gexf => "application/gexf+xml",
+            GraphFormat::Cytoscape => "application/json",
+            GraphFormat::Jsonld => "application/ld+json",
+        }
+    }
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Graphml => "graphml",
+            GraphFormat::Gexf => "gexf",
+            GraphFormat::Cytoscape => "cyjs",
+            GraphFormat::Jsonld => "jsonld",
+        }
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "graphml" => Ok(GraphFormat::Graphml),
+            "gexf" => Ok(GraphFormat::Gexf),
+            "cytoscape" => Ok(GraphFormat::Cytoscape),
+            "jsonld" => Ok(GraphFormat::Jsonld),
+            other => Err(format!("Unknown format {}, expected graphml, gexf, cytoscape or jsonld", other)),
+        }
+    }
+}
+
+/// Nodes, predicates and triples of a setting, or of a part of it.
+pub struct Subgraph {
+    pub setting: i32,
+    pub nodes: Vec<Node>,
+    pub predicates: Vec<Predicate>,
+    pub triples: Vec<Triple>,
+}
+impl Subgraph {
+    fn predicate_labels(&self) -> HashMap<i32, &str> {
+        self.predicates.iter().map(|p| (p.id, p.label.as_str())).collect()
+    }
+    /// Keeps the node `node_id`, the triples linking it to other nodes through
+    /// `predicate` and those nodes. As in table filters, `direction` is the one
+    /// of the link seen from the other nodes: `Out` keeps the triples whose
+    /// object is `node_id`.
+    pub fn around(&mut self, node_id: i32, predicate: Option<i32>, direction: Option<&GraphDirection>) {
+        self.triples.retain(|t| {
+            let linked = match direction {
+                Some(GraphDirection::Out) => t.object_id == node_id,
+                Some(GraphDirection::In) => t.subject_id == node_id,
+                None => t.object_id == node_id || t.subject_id == node_id,
+            };
+            linked && predicate.is_none_or(|p| p == t.predicate_id)
+        });
+        let kept: HashSet<i32> = self.triples.iter()
+            .flat_map(|t| [t.subject_id, t.object_id])
+            .chain([node_id])
+            .collect();
+        self.nodes.retain(|n| kept.contains(&n.node_id));
+    }
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Graphml => graphml(self),
+            GraphFormat::Gexf => gexf(self),
+            GraphFormat::Cytoscape => cytoscape(self).to_string(),
+            GraphFormat::Jsonld => jsonld::document(self, &jsonld::default_base(self.setting)).to_string(),
+        }
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Other control characters cannot appear in XML 1.0, even escaped
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1F}' => escaped.push('\u{FFFD}'),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn graphml(graph: &Subgraph) -> String {
+    let predicates = graph.predicate_labels();
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    out.push_str("  <key id=\"setting\" for=\"graph\" attr.name=\"setting\" attr.type=\"int\"/>\n");
+    out.push_str("  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n");
+    out.push_str("  <key id=\"node_id\" for=\"node\" attr.name=\"node_id\" attr.type=\"int\"/>\n");
+    out.push_str("  <key id=\"predicate\" for=\"edge\" attr.name=\"label\" attr.type=\"string\"/>\n");
+    out.push_str("  <key id=\"predicate_id\" for=\"edge\" attr.name=\"predicate_id\" attr.type=\"int\"/>\n");
+    let _ = writeln!(out, "  <graph id=\"setting-{}\" edgedefault=\"directed\">", graph.setting);
+    let _ = writeln!(out, "    <data key=\"setting\">{}</data>", graph.setting);
+    for node in &graph.nodes {
+        let _ = writeln!(
+            out,
+            "    <node id=\"n{}\"><data key=\"label\">{}</data><data key=\"node_id\">{}</data></node>",
+            node.node_id, xml_escape(&node.label), node.node_id
+        );
+    }
+    for (i, triple) in graph.triples.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\"><data key=\"predicate\">{}</data><data key=\"predicate_id\">{}</data></edge>",
+            i,
+            triple.subject_id,
+            triple.object_id,
+            xml_escape(predicates.get(&triple.predicate_id).copied().unwrap_or_default()),
+            triple.predicate_id
+        );
+    }
+    out.push_str("  </graph>\n");
+    out.push_str("</graphml>\n");
+    out
+}
+
+pub fn gexf(graph: &Subgraph) -> String {
+    let predicates = graph.predicate_labels();
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n");
+    let _ = writeln!(out, "  <meta><description>grpg setting {}</description></meta>", graph.setting);
+    out.push_str("  <graph mode=\"static\" defaultedgetype=\"directed\">\n");
+    out.push_str("    <attributes class=\"node\" mode=\"static\">\n");
+    out.push_str("      <attribute id=\"node_id\" title=\"node_id\" type=\"integer\"/>\n");
+    out.push_str("      <attribute id=\"setting\" title=\"setting\" type=\"integer\"/>\n");
+    out.push_str("    </attributes>\n");
+    out.push_str("    <attributes class=\"edge\" mode=\"static\">\n");
+    out.push_str("      <attribute id=\"predicate_id\" title=\"predicate_id\" type=\"integer\"/>\n");
+    out.push_str("    </attributes>\n");
+    out.push_str("    <nodes>\n");
+    for node in &graph.nodes {
+        let _ = writeln!(
+            out,
+            "      <node id=\"{}\" label=\"{}\"><attvalues><attvalue for=\"node_id\" value=\"{}\"/><attvalue for=\"setting\" value=\"{}\"/></attvalues></node>",
+            node.node_id, xml_escape(&node.label), node.node_id, graph.setting
+        );
+    }
+    out.push_str("    </nodes>\n");
+    out.push_str("    <edges>\n");
+    for (i, triple) in graph.triples.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "      <edge id=\"{}\" source=\"{}\" target=\"{}\" label=\"{}\"><attvalues><attvalue for=\"predicate_id\" value=\"{}\"/></attvalues></edge>",
+            i,
+            triple.subject_id,
+            triple.object_id,
+            xml_escape(predicates.get(&triple.predicate_id).copied().unwrap_or_default()),
+            triple.predicate_id
+        );
+    }
+    out.push_str("    </edges>\n");
+    out.push_str("  </graph>\n");
+    out.push_str("</gexf>\n");
+    out
+}
+
+/// Cytoscape.js elements JSON, as accepted by `cy.json()` and the
+/// Cytoscape desktop importer.
+pub fn cytoscape(graph: &Subgraph) -> serde_json::Value {
+    let predicates = graph.predicate_labels();
+    let nodes: Vec<serde_json::Value> = graph.nodes.iter()
+        .map(|node| json!({
+            "data": {
+                "id": format!("n{}", node.node_id),
+                "label": node.label,
+                "node_id": node.node_id,
+                "setting": graph.setting
+            }
+        }))
+        .collect();
+    let edges: Vec<serde_json::Value> = graph.triples.iter()
+        .enumerate()
+        .map(|(i, triple)| json!({
+            "data": {
+                "id": format!("e{}", i),
+                "source": format!("n{}", triple.subject_id),
+                "target": format!("n{}", triple.object_id),
+                "label": predicates.get(&triple.predicate_id).copied().unwrap_or_default(),
+                "predicate_id": triple.predicate_id
+            }
+        }))
+        .collect();
+    json!({
+        "data": { "setting": graph.setting },
+        "elements": {
+            "nodes": nodes,
+            "edges": edges
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subgraph() -> Subgraph {
+        let node = |node_id| Node { node_id, label: format!("n{node_id}") };
+        let triple = |subject_id, predicate_id, object_id| Triple { subject_id, predicate_id, object_id };
+        Subgraph {
+            setting: 1,
+            nodes: (1..=4).map(node).collect(),
+            predicates: Vec::new(),
+            triples: vec![triple(2, 1, 1), triple(1, 2, 3), triple(3, 1, 4)],
+        }
+    }
+
+    fn ids(subgraph: &Subgraph) -> Vec<i32> {
+        subgraph.nodes.iter().map(|n| n.node_id).collect()
+    }
+
+    #[test]
+    fn around_keeps_anchor_and_its_edges() {
+        let mut graph = subgraph();
+        graph.around(1, None, None);
+        assert_eq!(ids(&graph), vec![1, 2, 3]);
+        assert_eq!(graph.triples.len(), 2);
+    }
+
+    #[test]
+    fn around_follows_predicate_and_direction() {
+        let mut graph = subgraph();
+        graph.around(1, Some(1), Some(&GraphDirection::Out));
+        assert_eq!(ids(&graph), vec![1, 2]);
+        assert_eq!(graph.triples.len(), 1);
+
+        let mut graph = subgraph();
+        graph.around(1, None, Some(&GraphDirection::In));
+        assert_eq!(ids(&graph), vec![1, 3]);
+        assert_eq!(graph.triples[0].predicate_id, 2);
+    }
+
+    #[test]
+    fn escapes_xml_text() {
+        assert_eq!(xml_escape("<a & 'b'>\"c\""), "&lt;a &amp; &apos;b&apos;&gt;&quot;c&quot;");
+        assert_eq!(xml_escape("tab\tline\r\nbell\u{7}nul\u{0}"), "tab\tline\r\nbell\u{FFFD}nul\u{FFFD}");
+    }
+}
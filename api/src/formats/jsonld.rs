@@ -0,0 +1,503 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use utoipa::ToSchema;
+use grpg_core::db::error::DbError;
+use grpg_core::db::graph::GraphManager;
+use grpg_core::db::models::{QuotaExceeded, Triple};
+use crate::formats::graph::Subgraph;
+
+pub const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+pub const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+pub const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+pub fn default_base(setting: i32) -> String {
+    format!("urn:grpg:setting:{}/", setting)
+}
+pub fn node_iri(base: &str, id: i32) -> String {
+    format!("{}node/{}", base, id)
+}
+pub fn predicate_iri(base: &str, id: i32) -> String {
+    format!("{}predicate/{}", base, id)
+}
+
+/// Serializes the subgraph as a compacted JSON-LD document. Every predicate
+/// gets a term in the generated `@context`, named after its label. Labels that
+/// cannot be used as a term are kept as the `rdfs:label` of their definition.
+pub fn document(graph: &Subgraph, base: &str) -> Value {
+    let mut context = Map::new();
+    context.insert("rdfs".to_string(), json!(RDFS));
+    context.insert("label".to_string(), json!(RDFS_LABEL));
+    let mut terms = HashMap::new();
+    for predicate in &graph.predicates {
+        let mut term = predicate.label.clone();
+        let mut definition = json!({
+            "@id": predicate_iri(base, predicate.id),
+            "@type": "@id"
+        });
+        if term.is_empty() || term.starts_with('@') || term.contains(':') || context.contains_key(&term) {
+            term = format!("{}_{}", term, predicate.id);
+            definition["rdfs:label"] = json!(predicate.label);
+        }
+        context.insert(term.clone(), definition);
+        terms.insert(predicate.id, term);
+    }
+
+    let mut objects: HashMap<i32, Map<String, Value>> = HashMap::new();
+    for triple in &graph.triples {
+        let Some(term) = terms.get(&triple.predicate_id) else { continue };
+        let properties = objects.entry(triple.subject_id).or_default();
+        let object = json!(node_iri(base, triple.object_id));
+        match properties.get_mut(term) {
+            Some(Value::Array(values)) => values.push(object),
+            Some(value) => *value = json!([value.take(), object]),
+            None => { properties.insert(term.clone(), object); }
+        }
+    }
+
+    let nodes: Vec<Value> = graph.nodes.iter()
+        .map(|node| {
+            let mut object = Map::new();
+            object.insert("@id".to_string(), json!(node_iri(base, node.node_id)));
+            object.insert("label".to_string(), json!(node.label));
+            object.extend(objects.remove(&node.node_id).unwrap_or_default());
+            Value::Object(object)
+        })
+        .collect();
+
+    json!({
+        "@context": context,
+        "@graph": nodes
+    })
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ImportSummary {
+    pub nodes: usize,
+    pub predicates: usize,
+    pub triples: usize,
+}
+
+struct TermDefinition {
+    iri: String,
+    is_reference: bool,
+    /// Label of the predicate when the term could not be named after it
+    label: Option<String>,
+}
+
+#[derive(Default)]
+struct Context {
+    terms: HashMap<String, TermDefinition>,
+    vocab: Option<String>,
+    base: Option<String>,
+}
+
+impl Context {
+    fn parse(value: Option<&Value>) -> Result<Context, String> {
+        let mut context = Context::default();
+        let definitions = match value {
+            None | Some(Value::Null) => vec![],
+            Some(Value::Array(items)) => items.iter().collect(),
+            Some(value) => vec![value],
+        };
+        for definition in definitions {
+            let Value::Object(entries) = definition else {
+                return Err("Remote contexts are not supported".to_string());
+            };
+            for (term, value) in entries {
+                match (term.as_str(), value) {
+                    ("@vocab", Value::String(iri)) => context.vocab = Some(iri.clone()),
+                    ("@base", Value::String(iri)) => context.base = Some(iri.clone()),
+                    (_, Value::String(iri)) => {
+                        context.terms.insert(term.clone(), TermDefinition {
+                            iri: iri.clone(),
+                            is_reference: false,
+                            label: None,
+                        });
+                    }
+                    (_, Value::Object(definition)) => {
+                        let Some(Value::String(iri)) = definition.get("@id") else { continue };
+                        context.terms.insert(term.clone(), TermDefinition {
+                            iri: iri.clone(),
+                            is_reference: definition.get("@type") == Some(&json!("@id")),
+                            label: definition.get("rdfs:label")
+                                .or_else(|| definition.get(RDFS_LABEL))
+                                .and_then(literal),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+        // Term definitions may use compact IRIs built from other terms
+        let prefixes: HashMap<String, String> = context.terms.iter()
+            .filter(|(_, def)| is_prefix(&def.iri))
+            .map(|(term, def)| (term.clone(), def.iri.clone()))
+            .collect();
+        for definition in context.terms.values_mut() {
+            if let Some((prefix, suffix)) = definition.iri.split_once(':')
+                && let Some(iri) = prefixes.get(prefix)
+            {
+                definition.iri = format!("{}{}", iri, suffix);
+            }
+        }
+        Ok(context)
+    }
+
+    fn expand_property(&self, key: &str) -> String {
+        if let Some(definition) = self.terms.get(key) {
+            return definition.iri.clone();
+        }
+        if let Some((prefix, suffix)) = key.split_once(':') {
+            if let Some(definition) = self.terms.get(prefix).filter(|def| is_prefix(&def.iri)) {
+                return format!("{}{}", definition.iri, suffix);
+            }
+            return key.to_string();
+        }
+        match &self.vocab {
+            Some(vocab) => format!("{}{}", vocab, key),
+            None => key.to_string(),
+        }
+    }
+
+    fn expand_id(&self, id: &str) -> String {
+        if id.starts_with("_:") {
+            return id.to_string();
+        }
+        if let Some((prefix, suffix)) = id.split_once(':') {
+            if let Some(definition) = self.terms.get(prefix).filter(|def| is_prefix(&def.iri)) {
+                return format!("{}{}", definition.iri, suffix);
+            }
+            return id.to_string();
+        }
+        match &self.base {
+            Some(base) => format!("{}{}", base, id),
+            None => id.to_string(),
+        }
+    }
+
+    /// Name used for the predicate of `key`: the label or name of its term
+    /// when the document is compacted, otherwise those of the term mapped to
+    /// the IRI or the IRI's local name.
+    fn predicate_label(&self, key: &str, iri: &str) -> String {
+        let term = match self.terms.get_key_value(key) {
+            Some(term) => Some(term),
+            None => self.terms.iter().find(|(_, def)| def.iri == iri),
+        };
+        match term {
+            Some((term, definition)) => definition.label.clone().unwrap_or_else(|| term.clone()),
+            None => local_name(iri).to_string(),
+        }
+    }
+}
+
+/// Only terms mapped to an IRI ending with a delimiter are used as prefixes of
+/// compact IRIs, so `urn:...` IRIs are kept even if a term is named `urn`.
+fn is_prefix(iri: &str) -> bool {
+    iri.ends_with([':', '/', '?', '#', '[', ']', '@'])
+}
+
+pub fn local_name(iri: &str) -> &str {
+    iri.rsplit(['#', '/', ':']).find(|s| !s.is_empty()).unwrap_or(iri)
+}
+
+fn literal(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Object(o) => o.get("@value").and_then(literal),
+        _ => None,
+    }
+}
+
+enum Object {
+    Reference(String),
+    Literal(String),
+}
+
+#[derive(Default)]
+struct Statements {
+    subjects: Vec<String>,
+    labels: HashMap<String, String>,
+    triples: Vec<(String, String, Object)>,
+    blank_nodes: usize,
+    /// Blank node identifiers used by the document
+    blank_ids: HashSet<String>,
+}
+
+impl Statements {
+    fn subject(&mut self, context: &Context, object: &Map<String, Value>) -> String {
+        let iri = match object.get("@id").and_then(Value::as_str) {
+            Some(id) => context.expand_id(id),
+            None => loop {
+                self.blank_nodes += 1;
+                let id = format!("_:b{}", self.blank_nodes);
+                if !self.blank_ids.contains(&id) {
+                    break id;
+                }
+            },
+        };
+        if !self.subjects.contains(&iri) {
+            self.subjects.push(iri.clone());
+        }
+        iri
+    }
+
+    fn node(&mut self, context: &Context, object: &Map<String, Value>) -> Result<String, String> {
+        let subject = self.subject(context, object);
+        for (key, value) in object {
+            match key.as_str() {
+                "@id" | "@context" => continue,
+                "@type" => {
+                    let types = match value {
+                        Value::Array(items) => items.iter().collect(),
+                        value => vec![value],
+                    };
+                    for t in types.into_iter().filter_map(Value::as_str) {
+                        let iri = context.expand_property(t);
+                        self.triples.push((subject.clone(), "type".to_string(), Object::Reference(iri)));
+                    }
+                    continue;
+                }
+                "@graph" => {
+                    for item in value.as_array().into_iter().flatten() {
+                        if let Value::Object(item) = item {
+                            self.node(context, item)?;
+                        }
+                    }
+                    continue;
+                }
+                _ if key.starts_with('@') => continue,
+                _ => {}
+            }
+            let iri = context.expand_property(key);
+            let is_reference = context.terms.get(key).is_some_and(|def| def.is_reference);
+            let values = match value {
+                Value::Array(items) => items.iter().collect(),
+                value => vec![value],
+            };
+            if iri == RDFS_LABEL {
+                if let Some(label) = values.first().and_then(|v| literal(v)) {
+                    self.labels.insert(subject.clone(), label);
+                }
+                continue;
+            }
+            let predicate = if iri == RDF_TYPE { "type".to_string() } else { context.predicate_label(key, &iri) };
+            for value in values {
+                let object = match value {
+                    Value::Object(nested) if nested.contains_key("@value") => {
+                        literal(value).map(Object::Literal)
+                    }
+                    Value::Object(nested) if nested.len() == 1 && nested.contains_key("@id") => {
+                        nested["@id"].as_str().map(|id| Object::Reference(context.expand_id(id)))
+                    }
+                    Value::Object(nested) => Some(Object::Reference(self.node(context, nested)?)),
+                    Value::String(id) if is_reference => Some(Object::Reference(context.expand_id(id))),
+                    value => literal(value).map(Object::Literal),
+                };
+                if let Some(object) = object {
+                    self.triples.push((subject.clone(), predicate.clone(), object));
+                }
+            }
+        }
+        Ok(subject)
+    }
+}
+
+fn blank_ids(value: &Value, ids: &mut HashSet<String>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(id) = object.get("@id").and_then(Value::as_str).filter(|id| id.starts_with("_:")) {
+                ids.insert(id.to_string());
+            }
+            object.values().for_each(|v| blank_ids(v, ids));
+        }
+        Value::Array(items) => items.iter().for_each(|v| blank_ids(v, ids)),
+        _ => {}
+    }
+}
+
+fn statements(document: &Value) -> Result<Statements, String> {
+    let mut statements = Statements::default();
+    blank_ids(document, &mut statements.blank_ids);
+    let (context, items) = match document {
+        // Expanded documents are a top level array of node objects
+        Value::Array(items) => (Context::default(), items.iter().collect::<Vec<_>>()),
+        Value::Object(object) => {
+            let context = Context::parse(object.get("@context"))?;
+            match object.get("@graph") {
+                Some(Value::Array(items)) if object.keys().all(|k| k == "@context" || k == "@graph") => {
+                    (context, items.iter().collect())
+                }
+                _ => (context, vec![document]),
+            }
+        }
+        _ => return Err("Expected a JSON-LD object or array".to_string()),
+    };
+    for item in items {
+        let Value::Object(object) = item else {
+            return Err("Expected a node object".to_string());
+        };
+        // Objects of an expanded document may carry their own context
+        match object.get("@context") {
+            Some(local) => statements.node(&Context::parse(Some(local))?, object)?,
+            None => statements.node(&context, object)?,
+        };
+    }
+    Ok(statements)
+}
+
+/// Imports a compacted or expanded JSON-LD document into the setting of
+/// `graph`. Subjects and referenced IRIs become nodes, labelled by their
+/// `rdfs:label` or their IRI's local name, literal values become nodes
+/// labelled with the value, and predicates are matched to the setting's
+/// predicates by label, creating the missing ones. The setting's quotas are
+/// checked before anything is written, and the nodes created before a
+/// failure are deleted with their triples.
+pub fn import(graph: &GraphManager, document: &Value) -> Result<ImportSummary, DbError> {
+    let statements = statements(document).map_err(DbError::invalid)?;
+    let mut iris: HashSet<&String> = statements.subjects.iter().collect();
+    let mut literals = 0;
+    for (subject, _, object) in &statements.triples {
+        iris.insert(subject);
+        match object {
+            Object::Reference(iri) => { iris.insert(iri); }
+            Object::Literal(_) => literals += 1,
+        }
+    }
+    let quota = graph.store.quota();
+    for (limit, resource, count) in [
+        (quota.max_nodes, "nodes", graph.node_count()? + (iris.len() + literals) as u64),
+        (quota.max_triples, "triples", graph.triple_count()? + statements.triples.len() as u64),
+    ] {
+        if let Some(limit) = limit.filter(|limit| count > *limit) {
+            return Err(QuotaExceeded { resource, limit }.into());
+        }
+    }
+
+    let mut created = Vec::new();
+    let imported = add_statements(graph, &statements, &mut created);
+    if imported.is_err() {
+        for node_id in created {
+            let _ = graph.node_delete(node_id);
+        }
+    }
+    imported
+}
+
+/// Adds the statements to the graph, keeping the ids of the nodes created.
+fn add_statements(graph: &GraphManager, statements: &Statements, created: &mut Vec<i32>) -> Result<ImportSummary, DbError> {
+    let mut summary = ImportSummary { nodes: 0, predicates: 0, triples: 0 };
+
+    let mut nodes: HashMap<String, i32> = HashMap::new();
+    let mut node = |iri: &str, summary: &mut ImportSummary, created: &mut Vec<i32>| -> Result<i32, DbError> {
+        if let Some(id) = nodes.get(iri) {
+            return Ok(*id);
+        }
+        let label = statements.labels.get(iri).cloned().unwrap_or_else(|| local_name(iri).to_string());
+        let id = graph.node_create(label)?;
+        created.push(id);
+        summary.nodes += 1;
+        nodes.insert(iri.to_string(), id);
+        Ok(id)
+    };
+    for subject in &statements.subjects {
+        node(subject, &mut summary, created)?;
+    }
+
+    let mut predicates: HashMap<String, i32> = graph.predicate_all()?
+        .into_iter()
+        .map(|p| (p.label, p.id))
+        .collect();
+    for (subject, predicate, object) in &statements.triples {
+        let predicate_id = match predicates.get(predicate) {
+            Some(id) => *id,
+            None => {
+                let id = graph.predicate_create(predicate)?.id;
+                summary.predicates += 1;
+                predicates.insert(predicate.clone(), id);
+                id
+            }
+        };
+        let subject_id = node(subject, &mut summary, created)?;
+        let object_id = match object {
+            Object::Reference(iri) => node(iri, &mut summary, created)?,
+            Object::Literal(value) => {
+                let id = graph.node_create(value.clone())?;
+                created.push(id);
+                summary.nodes += 1;
+                id
+            }
+        };
+        graph.triple_create(Triple { subject_id, predicate_id, object_id })?;
+        summary.triples += 1;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grpg_core::db::models::{Node, Predicate};
+
+    #[test]
+    fn predicate_labels_survive_a_round_trip() {
+        let labels = ["knows", "knows", "label", "rdfs", "schema:name", "", "urn"];
+        let graph = Subgraph {
+            setting: 1,
+            nodes: vec![Node { node_id: 1, label: "a".to_string() }, Node { node_id: 2, label: "b".to_string() }],
+            predicates: labels.iter().enumerate()
+                .map(|(id, label)| Predicate { id: id as i32 + 1, label: label.to_string() })
+                .collect(),
+            triples: (1..=labels.len() as i32)
+                .map(|predicate_id| Triple { subject_id: 1, predicate_id, object_id: 2 })
+                .collect(),
+        };
+        let statements = statements(&document(&graph, &default_base(1))).unwrap();
+        let mut imported: Vec<&str> = statements.triples.iter().map(|(_, p, _)| p.as_str()).collect();
+        let mut expected = labels.to_vec();
+        imported.sort();
+        expected.sort();
+        assert_eq!(imported, expected);
+        assert_eq!(statements.labels.values().filter(|l| *l == "a" || *l == "b").count(), 2);
+        assert!(statements.triples.iter().all(|(s, _, o)| {
+            s == &node_iri(&default_base(1), 1) && matches!(o, Object::Reference(iri) if iri == &node_iri(&default_base(1), 2))
+        }));
+    }
+
+    #[test]
+    fn generated_blank_nodes_do_not_reuse_document_ids() {
+        let document = json!({
+            "@context": {"label": RDFS_LABEL, "knows": {"@id": "urn:knows", "@type": "@id"}},
+            "@graph": [
+                {"label": "anonymous", "knows": "_:b1"},
+                {"@id": "_:b1", "label": "named"}
+            ]
+        });
+        let statements = statements(&document).unwrap();
+        assert_eq!(statements.subjects.len(), 2);
+        assert_eq!(statements.labels["_:b1"], "named");
+    }
+
+    #[test]
+    fn rejected_imports_leave_the_graph_unchanged() {
+        let backend = grpg_core::db::graph::MemoryBackend::new();
+        let events = std::sync::Arc::new(grpg_core::db::events::Events::new());
+        let quota = grpg_core::db::models::Quota { max_nodes: None, max_triples: Some(2), max_tables: None };
+        let store = grpg_core::db::base::Store::in_memory(events.clone(), quota);
+        let graph = GraphManager { backend: &backend, setting: 1, events: &events, store: &store };
+        let document = json!({
+            "@context": { "knows": "urn:knows" },
+            "@id": "urn:ada",
+            "knows": [{ "@id": "urn:byron" }, { "@id": "urn:annabella" }, "Charles"]
+        });
+        let failed = import(&graph, &document);
+        assert!(matches!(failed, Err(DbError::QuotaExceeded(QuotaExceeded { resource: "triples", limit: 2 }))));
+        assert_eq!((graph.node_count().unwrap(), graph.predicate_all().unwrap().len()), (0, 0));
+
+        let document = json!({ "@context": { "knows": "urn:knows" }, "@id": "urn:ada", "knows": "Byron" });
+        let summary = import(&graph, &document).unwrap();
+        assert_eq!((summary.nodes, summary.predicates, summary.triples), (2, 1, 1));
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use grpg_core::db::models::{
+    ColumnDefinition,
+    GraphDirection,
+    Node,
+    Predicate,
+    RowResponse,
+    TableDefinition
+};
+
+#[derive(Deserialize, Serialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum TableFormat {
+    Csv,
+    Jsonl,
+}
+impl TableFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TableFormat::Csv => "text/csv; charset=utf-8",
+            TableFormat::Jsonl => "application/x-ndjson",
+        }
+    }
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TableFormat::Csv => "csv",
+            TableFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+pub struct LabeledValue {
+    pub node_id: i32,
+    pub label: String,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+pub struct LabeledCell {
+    pub column: String,
+    pub values: Vec<LabeledValue>,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+pub struct LabeledRow {
+    pub node_id: i32,
+    pub label: String,
+    pub cells: Vec<LabeledCell>,
+}
+
+pub struct LabeledTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<LabeledRow>,
+}
+
+fn column_header(column: &ColumnDefinition, predicates: &HashMap<i32, String>) -> String {
+    let predicate = column.filter.predicate_id
+        .map(|pid| predicates.get(&pid).cloned().unwrap_or_else(|| format!("#{}", pid)))
+        .unwrap_or_else(|| "*".to_string());
+    match column.filter.direction {
+        Some(GraphDirection::Out) => format!("{} (out)", predicate),
+        Some(GraphDirection::In) => format!("{} (in)", predicate),
+        None => predicate,
+    }
+}
+
+/// Joins the ids of the evaluated rows with the labels of their nodes and
+/// names every column after its predicate and direction.
+pub fn label_table(
+    table_def: &TableDefinition,
+    rows: Vec<RowResponse>,
+    nodes: Vec<Node>,
+    predicates: Vec<Predicate>
+) -> LabeledTable {
+    let nodes: HashMap<i32, String> = nodes.into_iter().map(|n| (n.node_id, n.label)).collect();
+    let predicates: HashMap<i32, String> = predicates.into_iter().map(|p| (p.id, p.label)).collect();
+    let headers: HashMap<i32, String> = table_def.columns.iter()
+        .map(|col| (col.id, column_header(col, &predicates)))
+        .collect();
+    let label = |id: i32| LabeledValue {
+        node_id: id,
+        label: nodes.get(&id).cloned().unwrap_or_default(),
+    };
+
+    let rows = rows.into_iter()
+        .map(|row| LabeledRow {
+            node_id: row.node_id,
+            label: label(row.node_id).label,
+            cells: row.columns.into_iter()
+                .map(|cell| LabeledCell {
+                    column: headers.get(&cell.id).cloned().unwrap_or_default(),
+                    values: cell.values.into_iter().map(label).collect(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    LabeledTable {
+        headers: table_def.columns.iter().map(|col| headers[&col.id].clone()).collect(),
+        rows,
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv_line<I: IntoIterator<Item = String>>(fields: I) -> String {
+    let mut line = fields.into_iter()
+        .map(|f| csv_field(&f))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// Renders the table as CSV, one line per item. Cells holding several values
+/// have their labels joined with `separator`.
+pub fn csv_lines(table: LabeledTable, separator: String) -> impl Iterator<Item = String> {
+    let header = csv_line(
+        ["node_id".to_string(), "label".to_string()].into_iter().chain(table.headers)
+    );
+    let rows = table.rows.into_iter().map(move |row| {
+        csv_line(
+            [row.node_id.to_string(), row.label].into_iter().chain(
+                row.cells.into_iter().map(|cell| cell.values.into_iter()
+                    .map(|v| v.label)
+                    .collect::<Vec<_>>()
+                    .join(&separator))
+            )
+        )
+    });
+    std::iter::once(header).chain(rows)
+}
+
+/// Renders the table as JSON Lines, one object per row.
+pub fn jsonl_lines(table: LabeledTable) -> impl Iterator<Item = Result<String, serde_json::Error>> {
+    table.rows.into_iter().map(|row| {
+        let mut line = serde_json::to_string(&row)?;
+        line.push('\n');
+        Ok(line)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use grpg_core::db::models::{CellResponse, ColumnFilter, Filter};
+    use super::*;
+
+    fn table() -> LabeledTable {
+        let table_def = TableDefinition {
+            label: "People".to_string(),
+            filter: Filter { node_id: None, predicate: None, direction: None },
+            columns: vec![
+                ColumnDefinition { id: 1, filter: ColumnFilter { direction: Some(GraphDirection::Out), predicate_id: Some(1) } },
+                ColumnDefinition { id: 2, filter: ColumnFilter { direction: Some(GraphDirection::In), predicate_id: Some(9) } },
+            ],
+        };
+        let rows = vec![RowResponse {
+            node_id: 1,
+            columns: vec![
+                CellResponse { id: 1, values: vec![2, 3] },
+                CellResponse { id: 2, values: vec![] },
+            ],
+        }];
+        let nodes = vec![
+            Node { node_id: 1, label: "Lovelace, \"Ada\"".to_string() },
+            Node { node_id: 2, label: "Byron".to_string() },
+            Node { node_id: 3, label: "Annabella".to_string() },
+        ];
+        let predicates = vec![Predicate { id: 1, label: "child of".to_string() }];
+        label_table(&table_def, rows, nodes, predicates)
+    }
+
+    #[test]
+    fn labels_nodes_and_columns() {
+        let table = table();
+        assert_eq!(table.headers, ["child of (out)", "#9 (in)"]);
+        let row = &table.rows[0];
+        assert_eq!(row.label, "Lovelace, \"Ada\"");
+        assert_eq!(row.cells[0].values.iter().map(|v| v.label.as_str()).collect::<Vec<_>>(), ["Byron", "Annabella"]);
+        assert_eq!(row.cells[1].column, "#9 (in)");
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        let lines: Vec<String> = csv_lines(table(), "|".to_string()).collect();
+        assert_eq!(lines, [
+            "node_id,label,child of (out),#9 (in)\r\n",
+            "1,\"Lovelace, \"\"Ada\"\"\",Byron|Annabella,\r\n",
+        ]);
+        let lines: Vec<String> = csv_lines(table(), ",".to_string()).collect();
+        assert_eq!(lines[1], "1,\"Lovelace, \"\"Ada\"\"\",\"Byron,Annabella\",\r\n");
+    }
+
+    #[test]
+    fn writes_a_json_object_per_line() {
+        let lines: Vec<String> = jsonl_lines(table()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with('\n') && !lines[0].trim_end().contains('\n'));
+        let row: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(row["label"], "Lovelace, \"Ada\"");
+        assert_eq!(row["cells"][0]["values"][1], serde_json::json!({ "node_id": 3, "label": "Annabella" }));
+        assert_eq!(row["cells"][1]["values"], serde_json::json!([]));
+    }
+}
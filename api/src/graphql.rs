@@ -0,0 +1,397 @@
+pub mod loaders;
+use actix_web::web;
+use async_graphql::{
+    dataloader::DataLoader,
+    Context,
+    EmptySubscription,
+    Json,
+    Object,
+    Result,
+    Schema,
+    SimpleObject
+};
+use crate::AppState;
+use crate::auth::CurrentUser;
+use grpg_core::db::models::{
+    self,
+    CellResponse,
+    Role,
+    RowResponse,
+    TableDefinition,
+    TokenScope
+};
+use crate::graphql::loaders::{
+    GraphLoader,
+    IncomingKey,
+    NodeKey,
+    OutgoingKey,
+    PredicateKey
+};
+
+pub type GrpgSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+pub fn schema() -> GrpgSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription).finish()
+}
+
+/// Request scoped data loader, so batches and caches never outlive a request.
+pub fn loader(app_state: web::Data<AppState>) -> DataLoader<GraphLoader> {
+    DataLoader::new(GraphLoader { app_state }, actix_web::rt::spawn)
+}
+
+fn app_state<'a>(ctx: &Context<'a>) -> &'a web::Data<AppState> {
+    ctx.data_unchecked::<web::Data<AppState>>()
+}
+
+fn current_user(ctx: &Context<'_>) -> Result<i32> {
+    ctx.data_unchecked::<Option<CurrentUser>>()
+        .as_ref()
+        .map(|u| u.id())
+        .ok_or_else(|| "Not logged in".into())
+}
+
+/// Checks the caller has a role on the setting allowing the request, and that
+/// their API token, if any, covers it.
+fn authorize(ctx: &Context<'_>, setting_id: i32, scope: TokenScope) -> Result<()> {
+    let Some(user) = ctx.data_unchecked::<Option<CurrentUser>>() else {
+        return Err("Not logged in".into());
+    };
+    if user.scope < scope {
+        return Err("Token scope does not allow this request".into());
+    }
+    if !user.can_access(setting_id) {
+        return Err("Token is not valid for this setting".into());
+    }
+    if app_state(ctx).store.conn(setting_id).get_role(user.id())? < Some(Role::for_scope(scope)) {
+        return Err("Your role on this setting does not allow this request".into());
+    }
+    Ok(())
+}
+
+fn writable(ctx: &Context<'_>, setting_id: i32) -> Result<()> {
+    authorize(ctx, setting_id, TokenScope::ReadWrite)
+}
+
+fn loader_of<'a>(ctx: &Context<'a>) -> &'a DataLoader<GraphLoader> {
+    ctx.data_unchecked::<DataLoader<GraphLoader>>()
+}
+
+#[derive(SimpleObject)]
+pub struct User {
+    pub id: i32,
+    pub name: String,
+}
+
+pub struct Setting {
+    pub id: i32,
+    pub name: String,
+    pub user_id: i32,
+}
+
+#[Object]
+impl Setting {
+    async fn id(&self) -> i32 {
+        self.id
+    }
+    async fn name(&self) -> &str {
+        &self.name
+    }
+    async fn user_id(&self) -> i32 {
+        self.user_id
+    }
+    async fn nodes(&self, ctx: &Context<'_>) -> Result<Vec<Node>> {
+        Ok(app_state(ctx).graph(self.id).node_all()?
+            .into_iter()
+            .map(|n| Node::new(self.id, n))
+            .collect())
+    }
+    async fn node(&self, ctx: &Context<'_>, id: i32) -> Result<Option<Node>> {
+        let node = loader_of(ctx).load_one(NodeKey { setting: self.id, id }).await?;
+        Ok(node.map(|n| Node::new(self.id, n)))
+    }
+    async fn predicates(&self, ctx: &Context<'_>) -> Result<Vec<Predicate>> {
+        Ok(app_state(ctx).graph(self.id).predicate_all()?
+            .into_iter()
+            .map(Predicate::from)
+            .collect())
+    }
+    async fn triples(&self, ctx: &Context<'_>) -> Result<Vec<Triple>> {
+        Ok(app_state(ctx).graph(self.id).triple_all()?
+            .into_iter()
+            .map(|t| Triple { setting: self.id, triple: t })
+            .collect())
+    }
+    async fn tables(&self, ctx: &Context<'_>) -> Result<Vec<Table>> {
+        let tables = app_state(ctx).store.conn(self.id).get_tables()?.unwrap_or_default();
+        let mut tables: Vec<Table> = tables.into_iter()
+            .map(|(id, def)| Table { setting: self.id, id, def })
+            .collect();
+        tables.sort_by_key(|t| t.id);
+        Ok(tables)
+    }
+    async fn table(&self, ctx: &Context<'_>, id: i32) -> Result<Option<Table>> {
+        Ok(app_state(ctx).store.conn(self.id).get_table(id)?
+            .map(|def| Table { setting: self.id, id, def }))
+    }
+}
+
+pub struct Node {
+    setting: i32,
+    node: models::Node,
+}
+impl Node {
+    fn new(setting: i32, node: models::Node) -> Self {
+        Node { setting, node }
+    }
+    fn key(&self) -> NodeKey {
+        NodeKey { setting: self.setting, id: self.node.node_id }
+    }
+}
+
+#[Object]
+impl Node {
+    async fn id(&self) -> i32 {
+        self.node.node_id
+    }
+    async fn label(&self) -> &str {
+        &self.node.label
+    }
+    /// Triples having this node as subject
+    async fn outgoing(&self, ctx: &Context<'_>) -> Result<Vec<Triple>> {
+        let triples = loader_of(ctx).load_one(OutgoingKey(self.key())).await?.unwrap_or_default();
+        Ok(triples.into_iter().map(|t| Triple { setting: self.setting, triple: t }).collect())
+    }
+    /// Triples having this node as object
+    async fn incoming(&self, ctx: &Context<'_>) -> Result<Vec<Triple>> {
+        let triples = loader_of(ctx).load_one(IncomingKey(self.key())).await?.unwrap_or_default();
+        Ok(triples.into_iter().map(|t| Triple { setting: self.setting, triple: t }).collect())
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Predicate {
+    pub id: i32,
+    pub label: String,
+}
+impl From<models::Predicate> for Predicate {
+    fn from(predicate: models::Predicate) -> Self {
+        Predicate { id: predicate.id, label: predicate.label }
+    }
+}
+
+pub struct Triple {
+    setting: i32,
+    triple: models::Triple,
+}
+
+#[Object]
+impl Triple {
+    async fn subject_id(&self) -> i32 {
+        self.triple.subject_id
+    }
+    async fn predicate_id(&self) -> i32 {
+        self.triple.predicate_id
+    }
+    async fn object_id(&self) -> i32 {
+        self.triple.object_id
+    }
+    async fn subject(&self, ctx: &Context<'_>) -> Result<Option<Node>> {
+        let node = loader_of(ctx).load_one(NodeKey { setting: self.setting, id: self.triple.subject_id }).await?;
+        Ok(node.map(|n| Node::new(self.setting, n)))
+    }
+    async fn predicate(&self, ctx: &Context<'_>) -> Result<Option<Predicate>> {
+        let predicate = loader_of(ctx).load_one(PredicateKey { setting: self.setting, id: self.triple.predicate_id }).await?;
+        Ok(predicate.map(Predicate::from))
+    }
+    async fn object(&self, ctx: &Context<'_>) -> Result<Option<Node>> {
+        let node = loader_of(ctx).load_one(NodeKey { setting: self.setting, id: self.triple.object_id }).await?;
+        Ok(node.map(|n| Node::new(self.setting, n)))
+    }
+}
+
+pub struct Table {
+    setting: i32,
+    id: i32,
+    def: TableDefinition,
+}
+
+#[Object]
+impl Table {
+    async fn id(&self) -> i32 {
+        self.id
+    }
+    async fn label(&self) -> &str {
+        &self.def.label
+    }
+    async fn definition(&self) -> Json<TableDefinition> {
+        Json(self.def.clone())
+    }
+    async fn rows(&self, ctx: &Context<'_>) -> Result<Vec<Row>> {
+        Ok(app_state(ctx).graph(self.setting).table_rows(self.def.clone()).await?
+            .into_iter()
+            .map(|row| Row { setting: self.setting, row })
+            .collect())
+    }
+}
+
+pub struct Row {
+    setting: i32,
+    row: RowResponse,
+}
+
+#[Object]
+impl Row {
+    async fn node_id(&self) -> i32 {
+        self.row.node_id
+    }
+    async fn node(&self, ctx: &Context<'_>) -> Result<Option<Node>> {
+        let node = loader_of(ctx).load_one(NodeKey { setting: self.setting, id: self.row.node_id }).await?;
+        Ok(node.map(|n| Node::new(self.setting, n)))
+    }
+    async fn cells(&self) -> Vec<Cell> {
+        self.row.columns.iter()
+            .map(|cell| Cell { setting: self.setting, cell: cell.clone() })
+            .collect()
+    }
+}
+
+pub struct Cell {
+    setting: i32,
+    cell: CellResponse,
+}
+
+#[Object]
+impl Cell {
+    async fn column_id(&self) -> i32 {
+        self.cell.id
+    }
+    async fn value_ids(&self) -> &[i32] {
+        &self.cell.values
+    }
+    async fn values(&self, ctx: &Context<'_>) -> Result<Vec<Node>> {
+        let keys = self.cell.values.iter().map(|&id| NodeKey { setting: self.setting, id });
+        let mut nodes = loader_of(ctx).load_many(keys).await?;
+        Ok(self.cell.values.iter()
+            .filter_map(|id| nodes.remove(&NodeKey { setting: self.setting, id: *id }))
+            .map(|n| Node::new(self.setting, n))
+            .collect())
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Users known to the server, for logged in users
+    async fn users(&self, ctx: &Context<'_>) -> Result<Vec<User>> {
+        current_user(ctx)?;
+        Ok(app_state(ctx).store.get_users()?
+            .into_iter()
+            .map(|u| User { id: u.id, name: u.name })
+            .collect())
+    }
+    async fn user(&self, ctx: &Context<'_>, id: i32) -> Result<Option<User>> {
+        current_user(ctx)?;
+        Ok(app_state(ctx).store.get_user(id)?.map(|u| User { id: u.id, name: u.name }))
+    }
+    async fn me(&self, ctx: &Context<'_>) -> Result<User> {
+        let user_id = current_user(ctx)?;
+        app_state(ctx).store.get_user(user_id)?
+            .map(|u| User { id: u.id, name: u.name })
+            .ok_or_else(|| "User not found".into())
+    }
+    /// Settings of the logged in user
+    async fn settings(&self, ctx: &Context<'_>) -> Result<Vec<Setting>> {
+        let user_id = current_user(ctx)?;
+        Ok(app_state(ctx).store.user_settings(user_id).get_settings()?
+            .into_iter()
+            .filter(|(id, _, _)| authorize(ctx, *id, TokenScope::Read).is_ok())
+            .map(|(id, s, _)| Setting { id, name: s.name, user_id: s.user_id })
+            .collect())
+    }
+    async fn setting(&self, ctx: &Context<'_>, id: i32) -> Result<Option<Setting>> {
+        let Some(setting) = app_state(ctx).store.get_setting(id)? else {
+            return Ok(None);
+        };
+        authorize(ctx, id, TokenScope::Read)?;
+        Ok(Some(Setting { id, name: setting.name, user_id: setting.user_id }))
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_user(&self, ctx: &Context<'_>, username: String, password: String) -> Result<User> {
+        let id = app_state(ctx).store.add_user(username.clone(), &password)?;
+        Ok(User { id, name: username })
+    }
+    async fn create_setting(&self, ctx: &Context<'_>, name: String) -> Result<Setting> {
+        let user_id = current_user(ctx)?;
+        let user = ctx.data_unchecked::<Option<CurrentUser>>().as_ref();
+        if user.is_some_and(|u| u.scope < TokenScope::ReadWrite || u.settings.is_some()) {
+            return Err("Token does not allow creating settings".into());
+        }
+        let id = app_state(ctx).store.user_settings(user_id).add_setting(name.clone(), None)?;
+        Ok(Setting { id, name, user_id })
+    }
+    async fn create_node(&self, ctx: &Context<'_>, setting_id: i32, label: String) -> Result<Node> {
+        writable(ctx, setting_id)?;
+        let node_id = app_state(ctx).graph(setting_id).node_create(label.clone())?;
+        Ok(Node::new(setting_id, models::Node { node_id, label }))
+    }
+    async fn update_node(&self, ctx: &Context<'_>, setting_id: i32, id: i32, label: String) -> Result<Node> {
+        writable(ctx, setting_id)?;
+        Ok(Node::new(setting_id, app_state(ctx).graph(setting_id).node_update(id, label)?))
+    }
+    async fn delete_node(&self, ctx: &Context<'_>, setting_id: i32, id: i32) -> Result<i32> {
+        writable(ctx, setting_id)?;
+        app_state(ctx).graph(setting_id).node_delete(id)?;
+        Ok(id)
+    }
+    async fn create_predicate(&self, ctx: &Context<'_>, setting_id: i32, label: String) -> Result<Predicate> {
+        writable(ctx, setting_id)?;
+        Ok(app_state(ctx).graph(setting_id).predicate_create(&label)?.into())
+    }
+    async fn create_triple(
+        &self,
+        ctx: &Context<'_>,
+        setting_id: i32,
+        subject_id: i32,
+        predicate_id: i32,
+        object_id: i32
+    ) -> Result<Triple> {
+        writable(ctx, setting_id)?;
+        let triple = models::Triple { subject_id, predicate_id, object_id };
+        app_state(ctx).graph(setting_id).triple_create(triple.clone())?;
+        Ok(Triple { setting: setting_id, triple })
+    }
+    async fn delete_triple(
+        &self,
+        ctx: &Context<'_>,
+        setting_id: i32,
+        subject_id: i32,
+        predicate_id: i32,
+        object_id: i32
+    ) -> Result<bool> {
+        writable(ctx, setting_id)?;
+        app_state(ctx).graph(setting_id).triple_delete(models::Triple { subject_id, predicate_id, object_id })?;
+        Ok(true)
+    }
+    async fn create_table(&self, ctx: &Context<'_>, setting_id: i32, definition: Json<TableDefinition>) -> Result<Table> {
+        writable(ctx, setting_id)?;
+        let def = definition.0;
+        let id = app_state(ctx).store.conn(setting_id).add_table(def.clone())?;
+        Ok(Table { setting: setting_id, id, def })
+    }
+    async fn update_table(&self, ctx: &Context<'_>, setting_id: i32, id: i32, definition: Json<TableDefinition>) -> Result<Table> {
+        writable(ctx, setting_id)?;
+        let def = definition.0;
+        app_state(ctx).store.conn(setting_id).set_table(id, def.clone())?;
+        Ok(Table { setting: setting_id, id, def })
+    }
+    async fn delete_table(&self, ctx: &Context<'_>, setting_id: i32, id: i32) -> Result<bool> {
+        writable(ctx, setting_id)?;
+        Ok(app_state(ctx).store.conn(setting_id).remove_table(id)?.is_some())
+    }
+}
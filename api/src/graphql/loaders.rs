@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use actix_web::web;
+use async_graphql::dataloader::Loader;
+use crate::AppState;
+use grpg_core::db::models::{
+    self,
+    GraphDirection
+};
+use grpg_core::db::models::Triple;
+
+/// Node `id` of setting `setting`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeKey {
+    pub setting: i32,
+    pub id: i32,
+}
+
+/// Triples leaving the node.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OutgoingKey(pub NodeKey);
+
+/// Triples reaching the node.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IncomingKey(pub NodeKey);
+
+/// Predicate `id` of setting `setting`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PredicateKey {
+    pub setting: i32,
+    pub id: i32,
+}
+
+/// Batches the lookups made while resolving a GraphQL request so a nested
+/// query issues one Kuzu query per setting and level instead of one per node.
+pub struct GraphLoader {
+    pub app_state: web::Data<AppState>,
+}
+
+fn by_setting<K: Copy>(keys: &[K], key: impl Fn(K) -> NodeKey) -> HashMap<i32, Vec<i32>> {
+    let mut grouped: HashMap<i32, Vec<i32>> = HashMap::new();
+    for k in keys {
+        let k = key(*k);
+        grouped.entry(k.setting).or_default().push(k.id);
+    }
+    grouped
+}
+
+impl GraphLoader {
+    fn triples(&self, keys: &[NodeKey], direction: GraphDirection) -> Result<HashMap<NodeKey, Vec<Triple>>, String> {
+        let mut result: HashMap<NodeKey, Vec<Triple>> = keys.iter().map(|k| (*k, Vec::new())).collect();
+        for (setting, ids) in by_setting(keys, |k| k) {
+            let triples = self.app_state.graph(setting).triple_by_nodes(&ids, direction.clone())
+                .map_err(|e| e.to_string())?;
+            for triple in triples {
+                let id = match direction {
+                    GraphDirection::Out => triple.subject_id,
+                    GraphDirection::In => triple.object_id,
+                };
+                if let Some(triples) = result.get_mut(&NodeKey { setting, id }) {
+                    triples.push(triple);
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Loader<NodeKey> for GraphLoader {
+    type Value = models::Node;
+    type Error = String;
+
+    async fn load(&self, keys: &[NodeKey]) -> Result<HashMap<NodeKey, Self::Value>, Self::Error> {
+        let mut result = HashMap::new();
+        for (setting, ids) in by_setting(keys, |k| k) {
+            for node in self.app_state.graph(setting).node_by_ids(&ids).map_err(|e| e.to_string())? {
+                result.insert(NodeKey { setting, id: node.node_id }, node);
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Loader<OutgoingKey> for GraphLoader {
+    type Value = Vec<Triple>;
+    type Error = String;
+
+    async fn load(&self, keys: &[OutgoingKey]) -> Result<HashMap<OutgoingKey, Self::Value>, Self::Error> {
+        let keys: Vec<NodeKey> = keys.iter().map(|k| k.0).collect();
+        Ok(self.triples(&keys, GraphDirection::Out)?
+            .into_iter()
+            .map(|(k, v)| (OutgoingKey(k), v))
+            .collect())
+    }
+}
+
+impl Loader<IncomingKey> for GraphLoader {
+    type Value = Vec<Triple>;
+    type Error = String;
+
+    async fn load(&self, keys: &[IncomingKey]) -> Result<HashMap<IncomingKey, Self::Value>, Self::Error> {
+        let keys: Vec<NodeKey> = keys.iter().map(|k| k.0).collect();
+        Ok(self.triples(&keys, GraphDirection::In)?
+            .into_iter()
+            .map(|(k, v)| (IncomingKey(k), v))
+            .collect())
+    }
+}
+
+impl Loader<PredicateKey> for GraphLoader {
+    type Value = models::Predicate;
+    type Error = String;
+
+    async fn load(&self, keys: &[PredicateKey]) -> Result<HashMap<PredicateKey, Self::Value>, Self::Error> {
+        let mut result = HashMap::new();
+        let settings = by_setting(keys, |k| NodeKey { setting: k.setting, id: k.id });
+        for setting in settings.keys() {
+            for predicate in self.app_state.graph(*setting).predicate_all().map_err(|e| e.to_string())? {
+                result.insert(PredicateKey { setting: *setting, id: predicate.id }, predicate);
+            }
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,5 @@
+//! Configuration and file formats of grpg, shared by the server and the
+//! `grpg-admin` tool.
+
+pub mod config;
+pub mod formats;
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web,
+    Error,
+    HttpMessage,
+    ResponseError
+};
+use serde_json::json;
+use crate::AppState;
+use crate::auth::CurrentUser;
+use crate::error::{
+    ApiError,
+    ErrorCode
+};
+
+/// Buckets kept before the idle ones are dropped.
+const MAX_BUCKETS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets allowing bursts of a minute's worth of requests, refilled
+/// continuously.
+pub struct RateLimits {
+    /// Requests per minute of each user, whatever token they use, 0 for no limit
+    pub per_user: u32,
+    /// Requests per minute of each client address, 0 for no limit
+    pub per_ip: u32,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimits {
+    pub fn new(per_user: u32, per_ip: u32) -> Self {
+        RateLimits {
+            per_user,
+            per_ip,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a request from the key's bucket, or returns the seconds until one
+    /// is available.
+    fn take(&self, key: String, per_minute: u32) -> Result<(), u64> {
+        if per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = per_minute as f64;
+        let rate = capacity / 60.0;
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_BUCKETS {
+            buckets.retain(|_, b| b.tokens + now.duration_since(b.updated).as_secs_f64() * rate < capacity);
+        }
+        let bucket = buckets.entry(key).or_insert(Bucket { tokens: capacity, updated: now });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / rate).ceil() as u64)
+        }
+    }
+}
+
+/// Answers 429 to the requests of users and addresses over their rate limit.
+pub async fn limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let Some(app_state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    let limits = &app_state.rate_limits;
+    let user = req.extensions().get::<CurrentUser>().map(|u| u.id());
+    let ip = req.peer_addr().map(|a| a.ip().to_string());
+    let exceeded = user
+        .and_then(|id| limits.take(format!("user:{}", id), limits.per_user).err()
+            .map(|retry| (retry, format!("{} requests per minute for this user", limits.per_user))))
+        .or_else(|| ip.and_then(|ip| limits.take(format!("ip:{}", ip), limits.per_ip).err()
+            .map(|retry| (retry, format!("{} requests per minute for this address", limits.per_ip)))));
+    if let Some((retry, limit)) = exceeded {
+        let mut response = ApiError::new(ErrorCode::RateLimited, format!("Rate limit exceeded: {}", limit))
+            .with_details(json!({ "retry_after": retry }))
+            .error_response();
+        response.headers_mut().insert(header::RETRY_AFTER, header::HeaderValue::from(retry));
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_allow_a_minute_of_requests_at_once() {
+        let limits = RateLimits::new(60, 0);
+        for _ in 0..60 {
+            assert_eq!(limits.take("user:1".to_string(), 60), Ok(()));
+        }
+        assert_eq!(limits.take("user:1".to_string(), 60), Err(1));
+        assert_eq!(limits.take("user:2".to_string(), 60), Ok(()));
+    }
+
+    #[test]
+    fn buckets_refill_over_time() {
+        let limits = RateLimits::new(2, 0);
+        limits.take("ip:a".to_string(), 2).unwrap();
+        limits.take("ip:a".to_string(), 2).unwrap();
+        assert_eq!(limits.take("ip:a".to_string(), 2), Err(30));
+        limits.buckets.lock().unwrap().get_mut("ip:a").unwrap().updated -= std::time::Duration::from_secs(30);
+        assert_eq!(limits.take("ip:a".to_string(), 2), Ok(()));
+    }
+
+    #[test]
+    fn zero_means_no_limit() {
+        let limits = RateLimits::new(0, 0);
+        for _ in 0..1000 {
+            assert_eq!(limits.take("user:1".to_string(), 0), Ok(()));
+        }
+    }
+}
@@ -1,97 +1,234 @@
-mod db;
+mod audit;
+mod auth;
 mod endpoints;
-use actix_web::{middleware::Logger, App, HttpServer, web::Data};
+mod error;
+mod cypher;
+mod graphql;
+mod limits;
+mod sparql;
+mod webhooks;
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    middleware::{from_fn, Logger},
+    App,
+    HttpServer,
+    web::{self, Data}
+};
 use actix_cors::Cors;
-use std::{env, error::Error};
+use std::error::Error;
 use utoipa::OpenApi;
 use utoipa_actix_web::{service_config::ServiceConfig, AppExt};
 use utoipa_rapidoc::RapiDoc;
 use utoipa_scalar::{Scalar, Servable as ScalarServable};
-use kuzu::{ Connection, Database, SystemConfig };
 use std::{
     sync::{Arc}
 };
-use crate::db::graph::GraphManager;
-use crate::db::base::Store;
+use grpg_core::db::graph::{
+    GraphBackend,
+    GraphManager
+};
+use grpg_core::db::base::Store;
+use grpg_core::db::events::Events;
+use grpg_core::db::audit::AuditLog;
+use api::{config, formats};
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::limits::RateLimits;
+#[cfg(test)]
+mod tests;
+
+#[derive(OpenApi)]
+struct ApiDoc;
 
 pub struct AppState {
-    db: Arc<Database>,
+    graph: Arc<dyn GraphBackend>,
     store: Arc<Store>,
+    events: Arc<Events>,
+    audit: Arc<AuditLog>,
+    rate_limits: Arc<RateLimits>,
+    /// Names of the users allowed to the `/admin` routes
+    admins: Vec<String>,
 }
 impl AppState {
-    fn establish_connection(&self) -> Connection {
-        Connection::new(&self.db).unwrap()
-    }
-    pub fn graph(&self, setting_id: i32) -> GraphManager {
+    pub fn graph(&self, setting_id: i32) -> GraphManager<'_> {
         GraphManager {
-            conn: self.establish_connection(),
-            setting: setting_id
+            backend: self.graph.as_ref(),
+            setting: setting_id,
+            events: &self.events,
+            store: &self.store
+        }
+    }
+}
+/// Stops the server before it starts, on a configuration it cannot run with.
+fn exit_with(error: String) -> ! {
+    eprintln!("Invalid configuration:\n  {}", error);
+    std::process::exit(1)
+}
+
+/// Masks the token of a share link in a path or URL, so that it is not
+/// written to the logs.
+fn without_share_token(url: &str) -> String {
+    match url.split_once("/shared/") {
+        Some((start, rest)) => {
+            let end = rest.find(['/', '?', '#']).map_or("", |i| &rest[i..]);
+            format!("{}/shared/***{}", start, end)
         }
+        None => url.to_string(),
     }
 }
+
+/// Logs requests in the default format, without the tokens of share links.
+fn logger() -> Logger {
+    Logger::new(r#"%a "%{request}xi" %s %b "%{referer}xi" "%{User-Agent}i" %T"#)
+        .custom_request_replace("request", |req| {
+            let query = if req.query_string().is_empty() { String::new() } else { format!("?{}", req.query_string()) };
+            format!("{} {}{} {:?}", req.method(), without_share_token(req.path()), query, req.version())
+        })
+        .custom_request_replace("referer", |req| {
+            let referer = req.headers().get("Referer").and_then(|r| r.to_str().ok());
+            referer.map_or_else(|| "-".to_string(), without_share_token)
+        })
+}
+
+/// The API with its middlewares, on the given state.
+fn app(
+    app_data: Data<AppState>,
+    schema: Data<graphql::GrpgSchema>,
+    cors: Cors,
+) -> App<impl ServiceFactory<
+    ServiceRequest,
+    Config = (),
+    Response = ServiceResponse<impl MessageBody>,
+    Error = actix_web::Error,
+    InitError = (),
+>> {
+    App::new()
+        .wrap(cors)
+        .app_data(app_data)
+        .app_data(schema)
+        .app_data(web::JsonConfig::default().error_handler(|e, _| ApiError::invalid(e).into()))
+        .app_data(web::QueryConfig::default().error_handler(|e, _| ApiError::invalid(e).into()))
+        .app_data(web::PathConfig::default().error_handler(|e, _| ApiError::invalid(e).into()))
+        .into_utoipa_app()
+        .openapi(ApiDoc::openapi())
+        .map(|app| app.wrap(from_fn(audit::record)))
+        .map(|app| app.wrap(from_fn(limits::limit)))
+        .map(|app| app.wrap(from_fn(auth::resolve_user)))
+        .map(|app| app.wrap(logger()))
+        .configure(|config: &mut ServiceConfig| {
+            config
+                .service(endpoints::auth::login)
+                .service(endpoints::auth::logout)
+                .service(endpoints::auth::get_me)
+                .service(endpoints::tokens::post_token)
+                .service(endpoints::tokens::get_tokens)
+                .service(endpoints::tokens::delete_token)
+                .service(endpoints::users::post_user)
+                .service(endpoints::users::get_users)
+                .service(endpoints::users::get_user_by_id)
+                .service(endpoints::settings::post_setting)
+                .service(endpoints::settings::get_settings)
+                .service(endpoints::settings::get_setting_by_id)
+                .service(endpoints::settings::get_quota)
+                .service(endpoints::members::get_members)
+                .service(endpoints::members::put_member)
+                .service(endpoints::members::delete_member)
+                .service(endpoints::organizations::post_organization)
+                .service(endpoints::organizations::get_organizations)
+                .service(endpoints::organizations::get_organization)
+                .service(endpoints::organizations::post_invitation)
+                .service(endpoints::organizations::put_organization_member)
+                .service(endpoints::organizations::delete_organization_member)
+                .service(endpoints::organizations::get_invitations)
+                .service(endpoints::organizations::accept_invitation)
+                .service(endpoints::organizations::decline_invitation)
+                .service(endpoints::predicates::get_predicates)
+                .service(endpoints::predicates::post_predicate)
+                .service(endpoints::nodes::post_node)
+                .service(endpoints::nodes::get_node)
+                .service(endpoints::nodes::delete_node)
+                .service(endpoints::nodes::put_node)
+                .service(endpoints::triples::post_triple)
+                .service(endpoints::triples::delete_triple)
+                .service(endpoints::triples::get_triples)
+                .service(endpoints::table::put_table)
+                .service(endpoints::table::get_table)
+                .service(endpoints::table::get_table)
+                .service(endpoints::table::post_table)
+                .service(endpoints::table::get_tables)
+                .service(endpoints::table::delete_table)
+                .service(endpoints::table::export_table)
+                .service(endpoints::export::export_graph)
+                .service(endpoints::export::export_archive)
+                .service(endpoints::import::import_jsonld)
+                .service(endpoints::import::import_archive)
+                .service(endpoints::sparql::get_sparql)
+                .service(endpoints::sparql::post_sparql)
+                .service(endpoints::cypher::post_cypher)
+                .service(endpoints::graphql::post_graphql)
+                .service(endpoints::graphql::get_graphiql)
+                .service(endpoints::events::get_events)
+                .service(endpoints::shares::post_share)
+                .service(endpoints::shares::get_shares)
+                .service(endpoints::shares::delete_share)
+                .service(endpoints::shares::get_shared)
+                .service(endpoints::shares::get_shared_tables)
+                .service(endpoints::shares::get_shared_table)
+                .service(endpoints::shares::get_shared_nodes)
+                .service(endpoints::shares::get_shared_predicates)
+                .service(endpoints::shares::get_shared_triples)
+                .service(endpoints::audit::get_audit)
+                .service(endpoints::webhooks::post_webhook)
+                .service(endpoints::webhooks::get_webhooks)
+                .service(endpoints::webhooks::delete_webhook)
+                .service(endpoints::webhooks::get_deliveries)
+                .service(endpoints::hooks::github_webhook)
+                .service(endpoints::admin::get_backup)
+                ;
+        })
+        .openapi_service(|api| {
+            RapiDoc::with_openapi("/api-docs/openapi2.json", api).path("/rapidoc")
+        })
+        .openapi_service(|api| Scalar::with_url("/scalar", api))
+        .into_app()
+}
+
 #[actix_web::main]
 async fn main() -> Result<(), impl Error> {
-    env_logger::init();
-    unsafe {
-        env::set_var("RUST_LOG", "actix_web=debug,actix_server=info");
-    }
-    #[derive(OpenApi)]
-    struct ApiDoc;
-    let db = Database::new("./demo_db", SystemConfig::default()).unwrap();
-    let conn = Connection::new(&db).unwrap();
-    db::create_db(&conn);
-    drop(conn);
+    let config = Config::load().unwrap_or_else(|e| exit_with(e));
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(&config.server.log)).init();
+    let graph = config.graph_backend().unwrap_or_else(|e| exit_with(e));
 
+    let events = Arc::new(Events::new());
+    let store = Arc::new(config.store(events.clone()).unwrap_or_else(|e| exit_with(e)));
+    actix_web::rt::spawn(webhooks::run(store.clone()));
     let app_data = Data::new(AppState {
-        db: Arc::new(db),
-        store: Arc::new(Store::new()),
+        graph,
+        store,
+        events,
+        audit: Arc::new(AuditLog::new(config.audit_log_path())),
+        rate_limits: Arc::new(RateLimits::new(config.limits.rate_limit_user, config.limits.rate_limit_ip)),
+        admins: config.server.admins.clone(),
     });
+    let schema = Data::new(graphql::schema());
+    let cors_origins = config.server.cors_origins.clone();
 
-    HttpServer::new(move || {
-        let cors = Cors::default()
-            .allow_any_origin()
+    let server = HttpServer::new(move || {
+        let mut cors = Cors::default()
             .allow_any_method()
             .allow_any_header();
+        for origin in &cors_origins {
+            cors = if origin == "*" { cors.allow_any_origin() } else { cors.allowed_origin(origin) };
+        }
 
-        App::new()
-            .wrap(cors)
-            .app_data(app_data.clone())
-            .into_utoipa_app()
-            .openapi(ApiDoc::openapi())
-            .map(|app| app.wrap(Logger::default()))
-            .configure(|config: &mut ServiceConfig| {
-                config
-                    .service(endpoints::users::post_user)
-                    .service(endpoints::users::get_users)
-                    .service(endpoints::users::get_user_by_id)
-                    .service(endpoints::settings::post_setting)
-                    .service(endpoints::settings::get_settings)
-                    .service(endpoints::settings::get_setting_by_id)
-                    .service(endpoints::predicates::get_predicates)
-                    .service(endpoints::predicates::post_predicate)
-                    .service(endpoints::nodes::post_node)
-                    .service(endpoints::nodes::get_node)
-                    .service(endpoints::nodes::delete_node)
-                    .service(endpoints::nodes::put_node)
-                    .service(endpoints::triples::post_triple)
-                    .service(endpoints::triples::delete_triple)
-                    .service(endpoints::triples::get_triples)
-                    .service(endpoints::table::put_table)
-                    .service(endpoints::table::get_table)
-                    .service(endpoints::table::get_table)
-                    .service(endpoints::table::post_table)
-                    .service(endpoints::table::get_tables)
-                    .service(endpoints::table::delete_table)
-                    .service(endpoints::hooks::github_webhook)
-                    ;
-            })
-            .openapi_service(|api| {
-                RapiDoc::with_openapi("/api-docs/openapi2.json", api).path("/rapidoc")
-            })
-            .openapi_service(|api| Scalar::with_url("/scalar", api))
-            .into_app()
-    })
-    .bind(("0.0.0.0", 8000))?
-    .run()
-    .await
+        app(app_data.clone(), schema.clone(), cors)
+    });
+    let address = (config.server.host.as_str(), config.server.port);
+    let server = match &config.server.tls {
+        #[cfg(feature = "tls")]
+        Some(tls) => server.bind_rustls_0_23(address, tls.server_config().unwrap_or_else(|e| exit_with(e)))?,
+        _ => server.bind(address)?,
+    };
+    server.run().await
 }
@@ -0,0 +1,57 @@
+mod parser;
+mod translate;
+use std::collections::HashMap;
+use serde_json::{json, Map, Value};
+use grpg_core::db::error::DbError;
+use grpg_core::db::graph::GraphManager;
+use crate::formats::jsonld::{
+    default_base,
+    node_iri,
+    predicate_iri
+};
+use crate::sparql::translate::ColumnKind;
+
+/// Evaluates a SPARQL SELECT query over the setting of `graph` and returns
+/// the bindings in the SPARQL 1.1 JSON results format. Nodes and predicates
+/// are named by the same IRIs as the JSON-LD export.
+pub fn execute(graph: &GraphManager, query: &str) -> Result<serde_json::Value, DbError> {
+    let base = default_base(graph.setting);
+    let query = parser::parse(query, &format!("{}predicate/", base)).map_err(DbError::invalid)?;
+    let predicates: HashMap<String, i32> = graph.predicate_all()?
+        .into_iter()
+        .map(|p| (p.label, p.id))
+        .collect();
+    let translation = translate::translate(&query, &base, &predicates).map_err(DbError::invalid)?;
+
+    let mut params: Vec<(&str, Value)> = translation.params.iter()
+        .map(|(name, value)| (name.as_str(), value.clone()))
+        .collect();
+    params.push(("setting", Value::from(graph.setting)));
+    let rows = graph.query(&translation.query, params)?;
+
+    let bindings: Vec<serde_json::Value> = rows.into_iter()
+        .map(|row| {
+            let mut binding = Map::new();
+            for ((variable, kind), value) in translation.columns.iter().zip(row) {
+                let term = match (kind, value) {
+                    (_, Value::Null) => continue,
+                    (ColumnKind::Node, Value::Number(id)) if id.is_i64() => {
+                        json!({ "type": "uri", "value": node_iri(&base, id.as_i64().unwrap_or_default() as i32) })
+                    }
+                    (ColumnKind::Predicate, Value::Number(id)) if id.is_i64() => {
+                        json!({ "type": "uri", "value": predicate_iri(&base, id.as_i64().unwrap_or_default() as i32) })
+                    }
+                    (_, Value::String(value)) => json!({ "type": "literal", "value": value }),
+                    (_, value) => json!({ "type": "literal", "value": value.to_string() }),
+                };
+                binding.insert(variable.clone(), term);
+            }
+            serde_json::Value::Object(binding)
+        })
+        .collect();
+
+    Ok(json!({
+        "head": { "vars": translation.columns.iter().map(|(v, _)| v).collect::<Vec<_>>() },
+        "results": { "bindings": bindings }
+    }))
+}
@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+use crate::formats::jsonld::{RDF_TYPE, RDFS_LABEL};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Var(String),
+    Iri(String),
+    PrefixedName(String, String),
+    Str(String),
+    Int(i64),
+    Word(String),
+    Punct(&'static str),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let is_name = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '?' || c == '$' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            if i == start {
+                return Err("Expected a variable name".to_string());
+            }
+            tokens.push(Token::Var(chars[start..i].iter().collect()));
+        } else if c == '<' && chars[i + 1..].iter()
+            .take_while(|c| **c != '>')
+            .all(|c| !c.is_whitespace())
+            && chars[i + 1..].contains(&'>')
+        {
+            let start = i + 1;
+            i = start;
+            while chars[i] != '>' {
+                i += 1;
+            }
+            tokens.push(Token::Iri(chars[start..i].iter().collect()));
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("Unterminated string literal".to_string()),
+                    Some(&q) if q == c => break,
+                    Some('\\') => {
+                        i += 1;
+                        match chars.get(i) {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(&e) => value.push(e),
+                            None => return Err("Unterminated string literal".to_string()),
+                        }
+                    }
+                    Some(&ch) => value.push(ch),
+                }
+                i += 1;
+            }
+            i += 1;
+            // Language tags and datatypes are accepted but ignored
+            if chars.get(i) == Some(&'@') {
+                i += 1;
+                while i < chars.len() && is_name(chars[i]) {
+                    i += 1;
+                }
+            } else if chars.get(i) == Some(&'^') && chars.get(i + 1) == Some(&'^') {
+                i += 2;
+                while i < chars.len() && !chars[i].is_whitespace() && !"}).;,".contains(chars[i]) {
+                    i += 1;
+                }
+            }
+            tokens.push(Token::Str(value));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token::Int(text.parse().map_err(|_| "Invalid integer".to_string())?));
+        } else if is_name(c) || c == ':' {
+            let start = i;
+            while i < chars.len() && (is_name(chars[i]) || chars[i] == ':' || chars[i] == '.') {
+                i += 1;
+            }
+            // A trailing dot ends the triple, it is not part of the name
+            while chars[i - 1] == '.' {
+                i -= 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            match word.split_once(':') {
+                Some((prefix, local)) => tokens.push(Token::PrefixedName(prefix.to_string(), local.to_string())),
+                None => tokens.push(Token::Word(word)),
+            }
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let punct = match two.as_str() {
+                "&&" => Some("&&"),
+                "||" => Some("||"),
+                "!=" => Some("!="),
+                "<=" => Some("<="),
+                ">=" => Some(">="),
+                _ => None,
+            };
+            if let Some(p) = punct {
+                tokens.push(Token::Punct(p));
+                i += 2;
+                continue;
+            }
+            let p = match c {
+                '{' => "{",
+                '}' => "}",
+                '(' => "(",
+                ')' => ")",
+                '.' => ".",
+                ';' => ";",
+                ',' => ",",
+                '*' => "*",
+                '=' => "=",
+                '<' => "<",
+                '>' => ">",
+                '!' => "!",
+                _ => return Err(format!("Unexpected character '{}'", c)),
+            };
+            tokens.push(Token::Punct(p));
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Var(String),
+    Iri(String),
+    Literal(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TriplePattern {
+    pub subject: Term,
+    pub predicate: Term,
+    pub object: Term,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+pub enum Expression {
+    Or(Box<Expression>, Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare(Comparison, Box<Expression>, Box<Expression>),
+    Call(String, Vec<Expression>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GroupPattern {
+    pub triples: Vec<TriplePattern>,
+    pub optionals: Vec<GroupPattern>,
+    pub filters: Vec<Expression>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderCondition {
+    pub variable: String,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Query {
+    pub distinct: bool,
+    /// Projected variables, `None` for `SELECT *`
+    pub projection: Option<Vec<String>>,
+    pub pattern: GroupPattern,
+    pub order: Vec<OrderCondition>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    prefixes: HashMap<String, String>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(format!("Expected {}", keyword))
+        }
+    }
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        let found = self.is_punct(punct);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+    fn expect_punct(&mut self, punct: &str) -> Result<(), String> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}'", punct))
+        }
+    }
+    fn integer(&mut self) -> Result<u64, String> {
+        match self.next() {
+            Some(Token::Int(i)) if i >= 0 => Ok(i as u64),
+            _ => Err("Expected a non-negative integer".to_string()),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query, String> {
+        while self.eat_keyword("PREFIX") {
+            let (prefix, local) = match self.next() {
+                Some(Token::PrefixedName(prefix, local)) => (prefix, local),
+                _ => return Err("Expected a prefix name".to_string()),
+            };
+            if !local.is_empty() {
+                return Err("Expected a prefix name".to_string());
+            }
+            let Some(Token::Iri(iri)) = self.next() else {
+                return Err("Expected an IRI after the prefix name".to_string());
+            };
+            self.prefixes.insert(prefix, iri);
+        }
+        self.expect_keyword("SELECT")?;
+        let distinct = self.eat_keyword("DISTINCT");
+        let projection = if self.eat_punct("*") {
+            None
+        } else {
+            let mut vars = Vec::new();
+            while let Some(Token::Var(v)) = self.peek() {
+                vars.push(v.clone());
+                self.position += 1;
+            }
+            if vars.is_empty() {
+                return Err("Expected '*' or variables after SELECT".to_string());
+            }
+            Some(vars)
+        };
+        self.eat_keyword("WHERE");
+        let pattern = self.group()?;
+
+        let mut order = Vec::new();
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let descending = if self.eat_keyword("DESC") {
+                    true
+                } else {
+                    self.eat_keyword("ASC");
+                    false
+                };
+                let parenthesized = self.eat_punct("(");
+                match self.next() {
+                    Some(Token::Var(variable)) => order.push(OrderCondition { variable, descending }),
+                    _ => return Err("Expected a variable in ORDER BY".to_string()),
+                }
+                if parenthesized {
+                    self.expect_punct(")")?;
+                }
+                if !matches!(self.peek(), Some(Token::Var(_))) && !self.is_keyword("ASC") && !self.is_keyword("DESC") {
+                    break;
+                }
+            }
+        }
+        let mut limit = None;
+        let mut offset = None;
+        loop {
+            if self.eat_keyword("LIMIT") {
+                limit = Some(self.integer()?);
+            } else if self.eat_keyword("OFFSET") {
+                offset = Some(self.integer()?);
+            } else {
+                break;
+            }
+        }
+        if let Some(token) = self.peek() {
+            return Err(format!("Unexpected {:?} at the end of the query", token));
+        }
+        Ok(Query { distinct, projection, pattern, order, limit, offset })
+    }
+
+    fn group(&mut self) -> Result<GroupPattern, String> {
+        self.expect_punct("{")?;
+        let mut group = GroupPattern::default();
+        loop {
+            if self.eat_punct("}") {
+                return Ok(group);
+            } else if self.eat_punct(".") {
+                continue;
+            } else if self.eat_keyword("OPTIONAL") {
+                let optional = self.group()?;
+                group.optionals.push(optional);
+            } else if self.eat_keyword("FILTER") {
+                let filter = if self.is_punct("(") {
+                    self.primary()?
+                } else {
+                    self.call()?
+                };
+                group.filters.push(filter);
+            } else if self.peek().is_none() {
+                return Err("Expected '}'".to_string());
+            } else {
+                self.triples(&mut group.triples)?;
+            }
+        }
+    }
+
+    fn triples(&mut self, triples: &mut Vec<TriplePattern>) -> Result<(), String> {
+        let subject = self.term()?;
+        loop {
+            let predicate = if self.eat_keyword("a") {
+                Term::Iri(RDF_TYPE.to_string())
+            } else {
+                self.term()?
+            };
+            loop {
+                let object = self.term()?;
+                triples.push(TriplePattern {
+                    subject: subject.clone(),
+                    predicate: predicate.clone(),
+                    object,
+                });
+                if !self.eat_punct(",") {
+                    break;
+                }
+            }
+            if !self.eat_punct(";") || self.is_punct(".") || self.is_punct("}") {
+                return Ok(());
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        match self.next() {
+            Some(Token::Var(v)) => Ok(Term::Var(v)),
+            Some(Token::Iri(iri)) => Ok(Term::Iri(iri)),
+            Some(Token::PrefixedName(prefix, local)) => match self.prefixes.get(&prefix) {
+                Some(iri) => Ok(Term::Iri(format!("{}{}", iri, local))),
+                None => Err(format!("Unknown prefix '{}:'", prefix)),
+            },
+            Some(Token::Str(s)) => Ok(Term::Literal(s)),
+            Some(Token::Int(i)) => Ok(Term::Literal(i.to_string())),
+            Some(token) => Err(format!("Unexpected {:?}", token)),
+            None => Err("Unexpected end of query".to_string()),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression, String> {
+        let mut left = self.conjunction()?;
+        while self.eat_punct("||") {
+            left = Expression::Or(Box::new(left), Box::new(self.conjunction()?));
+        }
+        Ok(left)
+    }
+
+    fn conjunction(&mut self) -> Result<Expression, String> {
+        let mut left = self.comparison()?;
+        while self.eat_punct("&&") {
+            left = Expression::And(Box::new(left), Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Expression, String> {
+        let left = self.unary()?;
+        let op = match self.peek() {
+            Some(Token::Punct("=")) => Comparison::Eq,
+            Some(Token::Punct("!=")) => Comparison::Ne,
+            Some(Token::Punct("<")) => Comparison::Lt,
+            Some(Token::Punct(">")) => Comparison::Gt,
+            Some(Token::Punct("<=")) => Comparison::Le,
+            Some(Token::Punct(">=")) => Comparison::Ge,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+        Ok(Expression::Compare(op, Box::new(left), Box::new(self.unary()?)))
+    }
+
+    fn unary(&mut self) -> Result<Expression, String> {
+        if self.eat_punct("!") {
+            Ok(Expression::Not(Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression, String> {
+        if self.eat_punct("(") {
+            let expression = self.expression()?;
+            self.expect_punct(")")?;
+            Ok(expression)
+        } else if matches!(self.peek(), Some(Token::Word(_))) {
+            self.call()
+        } else {
+            Ok(Expression::Term(self.term()?))
+        }
+    }
+
+    fn call(&mut self) -> Result<Expression, String> {
+        let Some(Token::Word(name)) = self.next() else {
+            return Err("Expected a function call".to_string());
+        };
+        self.expect_punct("(")?;
+        let mut arguments = Vec::new();
+        if !self.eat_punct(")") {
+            loop {
+                arguments.push(self.expression()?);
+                if self.eat_punct(")") {
+                    break;
+                }
+                self.expect_punct(",")?;
+            }
+        }
+        Ok(Expression::Call(name.to_uppercase(), arguments))
+    }
+}
+
+/// Parses a SPARQL SELECT query. The `rdf:`, `rdfs:` and default `:`
+/// prefixes are predeclared, the latter mapping to `default_prefix`.
+pub fn parse(input: &str, default_prefix: &str) -> Result<Query, String> {
+    let mut prefixes = HashMap::new();
+    prefixes.insert("rdf".to_string(), RDF_TYPE.trim_end_matches("type").to_string());
+    prefixes.insert("rdfs".to_string(), RDFS_LABEL.trim_end_matches("label").to_string());
+    prefixes.insert("".to_string(), default_prefix.to_string());
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        position: 0,
+        prefixes,
+    };
+    parser.query()
+}
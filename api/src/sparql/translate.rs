@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use serde_json::Value;
+use crate::formats::jsonld::{local_name, RDFS_LABEL};
+use crate::sparql::parser::{
+    Comparison,
+    Expression,
+    GroupPattern,
+    Query,
+    Term
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColumnKind {
+    Node,
+    Label,
+    Predicate,
+}
+
+#[derive(Clone)]
+enum Binding {
+    /// Cypher variable of a `Node`
+    Node(String),
+    /// Cypher expression evaluating to a node label
+    Label(String),
+    /// Cypher variable of a `Triple`, whose id is the predicate
+    Predicate(String),
+}
+
+pub struct Translation {
+    pub query: String,
+    pub params: Vec<(String, Value)>,
+    /// Projected SPARQL variables, in the order of the returned columns
+    pub columns: Vec<(String, ColumnKind)>,
+}
+
+struct Translator<'a> {
+    base: &'a str,
+    predicates: &'a HashMap<String, i32>,
+    bindings: HashMap<String, Binding>,
+    variables: Vec<String>,
+    identifiers: Vec<String>,
+    params: Vec<(String, Value)>,
+    anonymous: usize,
+}
+
+impl Translator<'_> {
+    fn param(&mut self, value: Value) -> String {
+        let name = format!("p{}", self.params.len());
+        self.params.push((name.clone(), value));
+        format!("${}", name)
+    }
+
+    fn fresh(&mut self, prefix: &str) -> String {
+        let identifier = format!("{}{}", prefix, self.anonymous);
+        self.anonymous += 1;
+        self.identifiers.push(identifier.clone());
+        identifier
+    }
+
+    fn bind(&mut self, variable: &str, binding: Binding) {
+        self.variables.push(variable.to_string());
+        self.bindings.insert(variable.to_string(), binding);
+    }
+
+    /// Id of the node or predicate named by `iri`. Predicates outside of the
+    /// setting's IRIs are looked up by the IRI's local name. Unknown IRIs
+    /// resolve to an id that matches nothing.
+    fn resolve_iri(&self, iri: &str) -> i32 {
+        let relative = iri.strip_prefix(self.base).unwrap_or(iri);
+        for prefix in ["node/", "predicate/"] {
+            if let Some(id) = relative.strip_prefix(prefix).and_then(|id| id.parse().ok()) {
+                return id;
+            }
+        }
+        self.predicates.get(local_name(iri)).copied().unwrap_or(-1)
+    }
+
+    /// Returns the Cypher variable of the node named by `term` and the node
+    /// pattern to match it with.
+    fn node(&mut self, term: &Term, conditions: &mut Vec<String>) -> Result<(String, String), String> {
+        match term {
+            Term::Var(variable) => match self.bindings.get(variable) {
+                Some(Binding::Node(identifier)) => Ok((identifier.clone(), format!("({})", identifier))),
+                Some(_) => Err(format!("Variable ?{} is used both as a node and as a label or predicate", variable)),
+                None => {
+                    let identifier = format!("v_{}", variable);
+                    self.identifiers.push(identifier.clone());
+                    self.bind(variable, Binding::Node(identifier.clone()));
+                    let pattern = format!("({}:Node {{setting: $setting}})", identifier);
+                    Ok((identifier, pattern))
+                }
+            },
+            Term::Iri(iri) => {
+                let identifier = self.fresh("n");
+                let id = self.param(Value::from(self.resolve_iri(iri)));
+                let pattern = format!("({}:Node {{setting: $setting, id: {}}})", identifier, id);
+                Ok((identifier, pattern))
+            }
+            Term::Literal(label) => {
+                let identifier = self.fresh("n");
+                let label = self.param(Value::String(label.clone()));
+                conditions.push(format!("{}.label = {}", identifier, label));
+                let pattern = format!("({}:Node {{setting: $setting}})", identifier);
+                Ok((identifier, pattern))
+            }
+        }
+    }
+
+    fn group(&mut self, group: &GroupPattern) -> Result<(Vec<String>, Vec<String>), String> {
+        let mut patterns = Vec::new();
+        let mut conditions = Vec::new();
+        for triple in &group.triples {
+            let (subject, subject_pattern) = self.node(&triple.subject, &mut conditions)?;
+            if triple.predicate == Term::Iri(RDFS_LABEL.to_string()) {
+                if subject_pattern.contains(':') {
+                    patterns.push(subject_pattern);
+                }
+                let label = format!("{}.label", subject);
+                match &triple.object {
+                    Term::Var(variable) => match self.bindings.get(variable) {
+                        Some(Binding::Label(other)) => conditions.push(format!("{} = {}", label, other)),
+                        Some(_) => return Err(format!("Variable ?{} is used both as a label and as a node or predicate", variable)),
+                        None => self.bind(variable, Binding::Label(label)),
+                    },
+                    Term::Literal(value) => {
+                        let value = self.param(Value::String(value.clone()));
+                        conditions.push(format!("{} = {}", label, value));
+                    }
+                    Term::Iri(_) => return Err("The object of rdfs:label must be a literal".to_string()),
+                }
+                continue;
+            }
+
+            let relation = self.fresh("r");
+            let relation_pattern = match &triple.predicate {
+                Term::Iri(iri) => {
+                    let id = self.param(Value::from(self.resolve_iri(iri)));
+                    format!("[{}:Triple {{id: {}}}]", relation, id)
+                }
+                Term::Var(variable) => {
+                    match self.bindings.get(variable) {
+                        Some(Binding::Predicate(other)) => conditions.push(format!("{}.id = {}.id", relation, other)),
+                        Some(_) => return Err(format!("Variable ?{} is used both as a predicate and as a node or label", variable)),
+                        None => self.bind(variable, Binding::Predicate(relation.clone())),
+                    }
+                    format!("[{}:Triple]", relation)
+                }
+                Term::Literal(_) => return Err("A predicate cannot be a literal".to_string()),
+            };
+            let (_, object_pattern) = self.node(&triple.object, &mut conditions)?;
+            patterns.push(format!("{}-{}->{}", subject_pattern, relation_pattern, object_pattern));
+        }
+        Ok((patterns, conditions))
+    }
+
+    fn operand(&mut self, term: &Term) -> Result<String, String> {
+        match term {
+            Term::Var(variable) => match self.bindings.get(variable) {
+                Some(Binding::Node(identifier)) => Ok(format!("{}.id", identifier)),
+                Some(Binding::Label(expression)) => Ok(expression.clone()),
+                Some(Binding::Predicate(identifier)) => Ok(format!("{}.id", identifier)),
+                None => Err(format!("Variable ?{} is not bound in the pattern", variable)),
+            },
+            Term::Iri(iri) => Ok(self.param(Value::from(self.resolve_iri(iri)))),
+            Term::Literal(value) => Ok(self.param(Value::String(value.clone()))),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Result<String, String> {
+        Ok(match expression {
+            Expression::Or(l, r) => format!("({} OR {})", self.expression(l)?, self.expression(r)?),
+            Expression::And(l, r) => format!("({} AND {})", self.expression(l)?, self.expression(r)?),
+            Expression::Not(e) => format!("(NOT {})", self.expression(e)?),
+            Expression::Compare(op, l, r) => {
+                let op = match op {
+                    Comparison::Eq => "=",
+                    Comparison::Ne => "<>",
+                    Comparison::Lt => "<",
+                    Comparison::Gt => ">",
+                    Comparison::Le => "<=",
+                    Comparison::Ge => ">=",
+                };
+                format!("({} {} {})", self.expression(l)?, op, self.expression(r)?)
+            }
+            Expression::Term(term) => self.operand(term)?,
+            Expression::Call(name, arguments) => {
+                let arity = match name.as_str() {
+                    "BOUND" | "LCASE" | "UCASE" | "STR" | "STRLEN" => 1..=1,
+                    "CONTAINS" | "STRSTARTS" | "STRENDS" => 2..=2,
+                    "REGEX" => 2..=3,
+                    _ => return Err(format!("Unsupported function {}", name)),
+                };
+                if !arity.contains(&arguments.len()) {
+                    return Err(format!("Wrong number of arguments for {}", name));
+                }
+                if name == "BOUND" {
+                    let Expression::Term(Term::Var(variable)) = &arguments[0] else {
+                        return Err("BOUND expects a variable".to_string());
+                    };
+                    return Ok(match self.bindings.get(variable) {
+                        Some(Binding::Node(identifier)) | Some(Binding::Predicate(identifier)) => format!("({} IS NOT NULL)", identifier),
+                        Some(Binding::Label(expression)) => format!("({} IS NOT NULL)", expression),
+                        None => "false".to_string(),
+                    });
+                }
+                let case_insensitive = name == "REGEX" && matches!(
+                    arguments.get(2),
+                    Some(Expression::Term(Term::Literal(flags))) if flags.contains('i')
+                );
+                let mut args = Vec::new();
+                // REGEX flags are applied by the translation, not passed to Kuzu
+                for argument in arguments.iter().take(2) {
+                    args.push(self.expression(argument)?);
+                }
+                match name.as_str() {
+                    "LCASE" => format!("lower({})", args[0]),
+                    "UCASE" => format!("upper({})", args[0]),
+                    "STR" => args[0].clone(),
+                    "STRLEN" => format!("size({})", args[0]),
+                    "CONTAINS" => format!("({} CONTAINS {})", args[0], args[1]),
+                    "STRSTARTS" => format!("({} STARTS WITH {})", args[0], args[1]),
+                    "STRENDS" => format!("({} ENDS WITH {})", args[0], args[1]),
+                    _ if case_insensitive => format!("regexp_matches(lower({}), lower({}))", args[0], args[1]),
+                    _ => format!("regexp_matches({}, {})", args[0], args[1]),
+                }
+            }
+        })
+    }
+}
+
+fn flatten(group: &GroupPattern, optionals: &mut Vec<GroupPattern>) {
+    for optional in &group.optionals {
+        optionals.push(GroupPattern {
+            triples: optional.triples.clone(),
+            optionals: Vec::new(),
+            filters: optional.filters.clone(),
+        });
+        flatten(optional, optionals);
+    }
+}
+
+/// Translates a parsed query into a parameterized Cypher query over the
+/// `Node`/`Triple` tables of a setting. The caller binds `$setting`.
+/// `predicates` maps predicate labels to ids, for IRIs that are not
+/// the setting's own.
+pub fn translate(query: &Query, base: &str, predicates: &HashMap<String, i32>) -> Result<Translation, String> {
+    let mut translator = Translator {
+        base,
+        predicates,
+        bindings: HashMap::new(),
+        variables: Vec::new(),
+        identifiers: Vec::new(),
+        params: Vec::new(),
+        anonymous: 0,
+    };
+    if query.pattern.triples.is_empty() {
+        return Err("The query pattern needs at least one required triple".to_string());
+    }
+
+    let mut cypher = String::new();
+    let (patterns, conditions) = translator.group(&query.pattern)?;
+    cypher.push_str(&format!("MATCH {}", patterns.join(", ")));
+    if !conditions.is_empty() {
+        cypher.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+
+    let mut optionals = Vec::new();
+    flatten(&query.pattern, &mut optionals);
+    for optional in &optionals {
+        let (patterns, mut conditions) = translator.group(optional)?;
+        if patterns.is_empty() {
+            continue;
+        }
+        for filter in &optional.filters {
+            conditions.push(translator.expression(filter)?);
+        }
+        cypher.push_str(&format!(" OPTIONAL MATCH {}", patterns.join(", ")));
+        if !conditions.is_empty() {
+            cypher.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+    }
+
+    if !query.pattern.filters.is_empty() {
+        let mut filters = Vec::new();
+        for filter in &query.pattern.filters {
+            filters.push(translator.expression(filter)?);
+        }
+        cypher.push_str(&format!(" WITH {} WHERE {}", translator.identifiers.join(", "), filters.join(" AND ")));
+    }
+
+    let projection = match &query.projection {
+        Some(variables) => variables.clone(),
+        None => translator.variables.clone(),
+    };
+    let mut columns = Vec::new();
+    let mut returns = Vec::new();
+    for variable in projection {
+        let (expression, kind) = match translator.bindings.get(&variable) {
+            Some(Binding::Node(identifier)) => (format!("{}.id", identifier), ColumnKind::Node),
+            Some(Binding::Label(expression)) => (expression.clone(), ColumnKind::Label),
+            Some(Binding::Predicate(identifier)) => (format!("{}.id", identifier), ColumnKind::Predicate),
+            None => return Err(format!("Variable ?{} is not bound in the pattern", variable)),
+        };
+        returns.push(format!("{} AS c_{}", expression, variable));
+        columns.push((variable, kind));
+    }
+    cypher.push_str(&format!(
+        " RETURN {}{}",
+        if query.distinct { "DISTINCT " } else { "" },
+        returns.join(", ")
+    ));
+    if !query.order.is_empty() {
+        let mut order = Vec::new();
+        for condition in &query.order {
+            if !columns.iter().any(|(v, _)| *v == condition.variable) {
+                return Err(format!("ORDER BY variable ?{} must be selected", condition.variable));
+            }
+            order.push(format!("c_{}{}", condition.variable, if condition.descending { " DESC" } else { "" }));
+        }
+        cypher.push_str(&format!(" ORDER BY {}", order.join(", ")));
+    }
+    if let Some(offset) = query.offset {
+        cypher.push_str(&format!(" SKIP {}", offset));
+    }
+    if let Some(limit) = query.limit {
+        cypher.push_str(&format!(" LIMIT {}", limit));
+    }
+
+    Ok(Translation {
+        query: cypher,
+        params: translator.params,
+        columns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sparql::parser::parse;
+    use super::*;
+
+    const BASE: &str = "urn:grpg:setting:1/";
+
+    fn cypher(query: &str) -> Translation {
+        let predicates = HashMap::from([("knows".to_string(), 7)]);
+        let query = parse(query, &format!("{}predicate/", BASE)).unwrap();
+        translate(&query, BASE, &predicates).unwrap()
+    }
+
+    #[test]
+    fn translates_basic_graph_patterns() {
+        let translation = cypher("SELECT ?s ?o WHERE { ?s :knows ?o }");
+        assert_eq!(
+            translation.query,
+            "MATCH (v_s:Node {setting: $setting})-[r0:Triple {id: $p0}]->(v_o:Node {setting: $setting}) \
+             RETURN v_s.id AS c_s, v_o.id AS c_o",
+        );
+        assert_eq!(translation.params, vec![("p0".to_string(), Value::from(7))]);
+
+        let translation = cypher("SELECT ?s WHERE { ?s <urn:grpg:setting:1/predicate/3> <urn:grpg:setting:1/node/2> . ?s rdfs:label \"Ada\" }");
+        assert_eq!(
+            translation.query,
+            "MATCH (v_s:Node {setting: $setting})-[r0:Triple {id: $p0}]->(n1:Node {setting: $setting, id: $p1}) \
+             WHERE v_s.label = $p2 RETURN v_s.id AS c_s",
+        );
+        assert_eq!(translation.params, vec![
+            ("p0".to_string(), Value::from(3)),
+            ("p1".to_string(), Value::from(2)),
+            ("p2".to_string(), Value::from("Ada")),
+        ]);
+    }
+
+    #[test]
+    fn translates_filters() {
+        let translation = cypher("SELECT ?s ?name WHERE { ?s :knows ?o . ?s rdfs:label ?name FILTER(CONTAINS(?name, \"A\")) }");
+        assert_eq!(
+            translation.query,
+            "MATCH (v_s:Node {setting: $setting})-[r0:Triple {id: $p0}]->(v_o:Node {setting: $setting}) \
+             WITH v_s, r0, v_o WHERE (v_s.label CONTAINS $p1) RETURN v_s.id AS c_s, v_s.label AS c_name",
+        );
+        assert!(translation.columns.iter().map(|(_, kind)| *kind).eq([ColumnKind::Node, ColumnKind::Label]));
+    }
+
+    #[test]
+    fn translates_optionals() {
+        let translation = cypher("SELECT ?s ?x WHERE { ?s :knows ?o OPTIONAL { ?o :knows ?x . ?x rdfs:label ?l FILTER(?l != \"Byron\") } }");
+        assert_eq!(
+            translation.query,
+            "MATCH (v_s:Node {setting: $setting})-[r0:Triple {id: $p0}]->(v_o:Node {setting: $setting}) \
+             OPTIONAL MATCH (v_o)-[r1:Triple {id: $p1}]->(v_x:Node {setting: $setting}) WHERE (v_x.label <> $p2) \
+             RETURN v_s.id AS c_s, v_x.id AS c_x",
+        );
+    }
+
+    #[test]
+    fn translates_solution_modifiers() {
+        let translation = cypher("SELECT DISTINCT ?s WHERE { ?s :knows ?o } ORDER BY DESC(?s) LIMIT 5 OFFSET 2");
+        assert_eq!(
+            translation.query,
+            "MATCH (v_s:Node {setting: $setting})-[r0:Triple {id: $p0}]->(v_o:Node {setting: $setting}) \
+             RETURN DISTINCT v_s.id AS c_s ORDER BY c_s DESC SKIP 2 LIMIT 5",
+        );
+    }
+
+    #[test]
+    fn scopes_every_pattern() {
+        for query in [
+            "SELECT * WHERE { ?s ?p ?o }",
+            "SELECT * WHERE { <urn:grpg:setting:2/node/1> ?p \"Ada\" OPTIONAL { ?o ?q ?x } }",
+            "SELECT * WHERE { ?s :knows ?o OPTIONAL { ?x :knows ?y OPTIONAL { ?y rdfs:label ?l } } FILTER(BOUND(?x)) }",
+        ] {
+            let translation = cypher(query).query;
+            let nodes = translation.matches(":Node").count();
+            assert!(nodes > 0);
+            assert_eq!(translation.matches(":Node {setting: $setting").count(), nodes, "{}", translation);
+        }
+    }
+}
@@ -0,0 +1,345 @@
+//! The whole API on the in-memory store and graph backend.
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use actix_web::{http::StatusCode, test};
+use serde_json::json;
+use grpg_core::db::graph::MemoryBackend;
+use grpg_core::db::models::{Quota, Triple};
+use super::*;
+
+fn state() -> Data<AppState> {
+    state_with(Quota::default(), RateLimits::new(0, 0))
+}
+
+fn state_with(quota: Quota, rate_limits: RateLimits) -> Data<AppState> {
+    static STATES: AtomicUsize = AtomicUsize::new(0);
+    let events = Arc::new(Events::new());
+    let state = STATES.fetch_add(1, Ordering::Relaxed);
+    let audit = std::env::temp_dir().join(format!("grpg-test-audit-{}-{}.jsonl", std::process::id(), state));
+    let _ = std::fs::remove_file(&audit);
+    Data::new(AppState {
+        graph: Arc::new(MemoryBackend::new()),
+        store: Arc::new(Store::in_memory(events.clone(), quota)),
+        events,
+        audit: Arc::new(AuditLog::new(audit)),
+        rate_limits: Arc::new(rate_limits),
+        admins: Vec::new(),
+    })
+}
+
+/// Sends the request with the token, answering its status and JSON body.
+macro_rules! call {
+    ($service:expr, $request:expr, $token:expr) => {{
+        let request = $request.insert_header(("Authorization", format!("Bearer {}", $token))).to_request();
+        let response = test::call_service(&$service, request).await;
+        let status = response.status();
+        let body = test::read_body(response).await;
+        (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default())
+    }};
+}
+
+/// Labels of the triples of the setting, as (subject, predicate, object).
+fn labelled_triples(state: &AppState, setting_id: i32) -> BTreeSet<(String, String, String)> {
+    let graph = state.graph(setting_id);
+    let nodes: HashMap<i32, String> = graph.node_all().unwrap().into_iter().map(|n| (n.node_id, n.label)).collect();
+    let predicates: HashMap<i32, String> = graph.predicate_all().unwrap().into_iter().map(|p| (p.id, p.label)).collect();
+    graph.triple_all().unwrap().into_iter()
+        .map(|t| (nodes[&t.subject_id].clone(), predicates[&t.predicate_id].clone(), nodes[&t.object_id].clone()))
+        .collect()
+}
+
+/// Session token of a new user with the name.
+macro_rules! session {
+    ($state:expr, $service:ident, $name:expr) => {{
+        $state.store.add_user($name.to_string(), "secret").unwrap();
+        let login = test::TestRequest::post().uri("/auth/login").set_json(json!({ "username": $name, "password": "secret" }));
+        let (status, body) = call!($service, login, "");
+        assert_eq!(status, StatusCode::OK);
+        body["token"].as_str().unwrap().to_string()
+    }};
+}
+
+/// Binds the service on the state and the token of a new user with a
+/// setting of theirs.
+macro_rules! login {
+    ($state:expr, $service:ident, $token:ident, $setting:ident) => {
+        let $service = test::init_service(app($state.clone(), Data::new(graphql::schema()), Cors::default())).await;
+        let $token = session!($state, $service, "ada");
+        let (_, body) = call!($service, test::TestRequest::post().uri("/settings").set_json(json!({ "name": "family" })), &$token);
+        let $setting = body["id"].as_i64().unwrap() as i32;
+    };
+}
+
+/// Binds the state, the service, the token of a logged in user and a setting
+/// of theirs holding a small graph.
+macro_rules! setup {
+    ($state:ident, $service:ident, $token:ident, $setting:ident) => {
+        let $state = state();
+        login!($state, $service, $token, $setting);
+        let graph = $state.graph($setting);
+        // The first node is removed so the ids of the graph do not start at 1
+        let removed = graph.node_create("removed".to_string()).unwrap();
+        let ada = graph.node_create("Ada".to_string()).unwrap();
+        let byron = graph.node_create("Byron".to_string()).unwrap();
+        graph.node_delete(removed).unwrap();
+        assert_eq!((ada, byron), (2, 3));
+        let parent_of = graph.predicate_create("parent of").unwrap().id;
+        let label = graph.predicate_create("label").unwrap().id;
+        graph.triple_create(Triple { subject_id: byron, predicate_id: parent_of, object_id: ada }).unwrap();
+        graph.triple_create(Triple { subject_id: ada, predicate_id: label, object_id: byron }).unwrap();
+    };
+}
+
+#[actix_web::test]
+async fn cypher_queries_are_scoped() {
+    setup!(state, service, token, setting);
+    let uri = format!("/settings/{}/cypher", setting);
+    for query in ["MATCH (u:User) RETURN u", "MATCH (n) DETACH DELETE n", "MATCH (n {setting: 2}) RETURN n"] {
+        let (status, _) = call!(service, test::TestRequest::post().uri(&uri).set_json(json!({ "query": query })), &token);
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+    // Accepted once scoped, then refused by the memory backend which has no Cypher engine
+    let query = "MATCH (n:Node) RETURN n.label AS l UNION ALL MATCH (n) RETURN n.label AS l";
+    let (status, _) = call!(service, test::TestRequest::post().uri(&uri).set_json(json!({ "query": query })), &token);
+    assert_eq!(status, StatusCode::NOT_IMPLEMENTED);
+}
+
+#[actix_web::test]
+async fn sparql_queries_are_parsed() {
+    setup!(state, service, token, setting);
+    let uri = format!("/settings/{}/sparql", setting);
+    let invalid = test::TestRequest::post().uri(&uri)
+        .insert_header(("Content-Type", "application/sparql-query"))
+        .set_payload("SELECT ?s WHERE { ?s ");
+    let (status, _) = call!(service, invalid, &token);
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let valid = test::TestRequest::post().uri(&uri)
+        .insert_header(("Content-Type", "application/sparql-query"))
+        .set_payload("SELECT ?s ?o WHERE { ?s <parent%20of> ?o }");
+    let (status, _) = call!(service, valid, &token);
+    assert_eq!(status, StatusCode::NOT_IMPLEMENTED);
+}
+
+#[actix_web::test]
+async fn archives_are_imported_with_new_ids() {
+    setup!(state, service, token, setting);
+    let (status, archive) = call!(service, test::TestRequest::get().uri(&format!("/settings/{}/archive", setting)), &token);
+    assert_eq!(status, StatusCode::OK);
+    let (status, imported) = call!(service, test::TestRequest::post().uri("/settings/import").set_json(archive), &token);
+    assert_eq!(status, StatusCode::OK);
+    let imported_setting = imported["setting_id"].as_i64().unwrap() as i32;
+    assert_ne!(imported_setting, setting);
+    assert_eq!(imported["triples"], 2);
+    assert_eq!(labelled_triples(&state, imported_setting), labelled_triples(&state, setting));
+}
+
+#[actix_web::test]
+async fn jsonld_exports_import_back() {
+    setup!(state, service, token, setting);
+    let request = test::TestRequest::get().uri(&format!("/settings/{}/export?format=jsonld", setting));
+    let response = test::call_service(&service, request.insert_header(("Authorization", format!("Bearer {}", token))).to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let document = test::read_body(response).await;
+    let (_, body) = call!(service, test::TestRequest::post().uri("/settings").set_json(json!({ "name": "copy" })), &token);
+    let copy = body["id"].as_i64().unwrap() as i32;
+    let import = test::TestRequest::post()
+        .uri(&format!("/settings/{}/import/jsonld", copy))
+        .insert_header(("Content-Type", "application/ld+json"))
+        .set_payload(document);
+    let (status, summary) = call!(service, import, &token);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(summary["triples"], 2);
+    assert_eq!(labelled_triples(&state, copy), labelled_triples(&state, setting));
+}
+
+#[actix_web::test]
+async fn quotas_explain_the_limit_reached() {
+    let state = state_with(Quota { max_nodes: Some(1), max_triples: None, max_tables: None }, RateLimits::new(0, 0));
+    login!(state, service, token, setting);
+    let uri = format!("/settings/{}/node", setting);
+    let (status, _) = call!(service, test::TestRequest::post().uri(&uri).set_json(json!({ "label": "Ada" })), &token);
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = call!(service, test::TestRequest::post().uri(&uri).set_json(json!({ "label": "Byron" })), &token);
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "quota_exceeded");
+    assert_eq!(body["details"], json!({ "resource": "nodes", "limit": 1 }));
+    assert_eq!(body["message"], "Quota exceeded: this setting is limited to 1 nodes");
+}
+
+#[actix_web::test]
+async fn rate_limits_explain_the_limit_reached() {
+    let state = state_with(Quota::default(), RateLimits::new(3, 0));
+    login!(state, service, token, setting);
+    let uri = format!("/settings/{}/node", setting);
+    // Adding the setting took the first of the three, logging in was anonymous
+    for _ in 0..2 {
+        let (status, _) = call!(service, test::TestRequest::get().uri(&uri), &token);
+        assert_eq!(status, StatusCode::OK);
+    }
+    let request = test::TestRequest::get().uri(&uri).insert_header(("Authorization", format!("Bearer {}", token)));
+    let response = test::call_service(&service, request.to_request()).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers().get("Retry-After").unwrap(), "20");
+    let body: serde_json::Value = test::read_body_json(response).await;
+    assert_eq!(body["code"], "rate_limited");
+    assert_eq!(body["message"], "Rate limit exceeded: 3 requests per minute for this user");
+}
+
+#[actix_web::test]
+async fn logins_open_sessions_in_secure_cookies() {
+    let state = state();
+    let service = test::init_service(app(state.clone(), Data::new(graphql::schema()), Cors::default())).await;
+    state.store.add_user("ada".to_string(), "secret").unwrap();
+    let login = |password: &str| test::TestRequest::post().uri("/auth/login").set_json(json!({ "username": "ada", "password": password }));
+    let (status, body) = call!(service, login("wrong"), "");
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "unauthorized");
+
+    let response = test::call_service(&service, login("secret").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let cookie = response.response().cookies().find(|c| c.name() == auth::SESSION_COOKIE).unwrap().into_owned();
+    assert_eq!((cookie.secure(), cookie.http_only(), cookie.path()), (Some(true), Some(true), Some("/")));
+    let body: serde_json::Value = test::read_body_json(response).await;
+    assert_eq!(cookie.value(), body["token"]);
+
+    let me = test::TestRequest::get().uri("/auth/me").cookie(cookie.clone()).to_request();
+    assert_eq!(test::call_service(&service, me).await.status(), StatusCode::OK);
+    let logout = test::TestRequest::post().uri("/auth/logout").cookie(cookie.clone()).to_request();
+    let response = test::call_service(&service, logout).await;
+    assert_eq!(response.response().cookies().next().map(|c| c.value().to_string()), Some(String::new()));
+    let me = test::TestRequest::get().uri("/auth/me").cookie(cookie).to_request();
+    assert_eq!(test::call_service(&service, me).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn users_are_listed_to_logged_in_users_only() {
+    let state = state();
+    login!(state, service, token, _setting);
+    for uri in ["/users", "/users/1"] {
+        let (status, _) = call!(service, test::TestRequest::get().uri(uri), "");
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call!(service, test::TestRequest::get().uri(uri), &token);
+        assert_eq!(status, StatusCode::OK);
+    }
+    let users = test::TestRequest::post().uri("/graphql").set_json(json!({ "query": "{ users { name } }" }));
+    let (_, body) = call!(service, users, "");
+    assert_eq!(body["errors"][0]["message"], "Not logged in");
+    let users = test::TestRequest::post().uri("/graphql").set_json(json!({ "query": "{ users { name } }" }));
+    let (_, body) = call!(service, users, &token);
+    assert_eq!(body["data"]["users"], json!([{ "name": "ada" }]));
+}
+
+#[actix_web::test]
+async fn share_tokens_are_not_logged() {
+    assert_eq!(without_share_token("/shared/abc"), "/shared/***");
+    assert_eq!(without_share_token("/shared/abc/tables/1"), "/shared/***/tables/1");
+    assert_eq!(without_share_token("https://example.org/shared/abc?tab=1"), "https://example.org/shared/***?tab=1");
+    assert_eq!(without_share_token("/settings/1/shares"), "/settings/1/shares");
+}
+
+#[actix_web::test]
+async fn api_tokens_are_held_to_their_scope() {
+    let state = state();
+    login!(state, service, token, setting);
+    let (_, other) = call!(service, test::TestRequest::post().uri("/settings").set_json(json!({ "name": "work" })), &token);
+    let new_token = test::TestRequest::post().uri("/tokens").set_json(json!({ "name": "ci", "scope": "read", "settings": [setting] }));
+    let (status, body) = call!(service, new_token, &token);
+    assert_eq!(status, StatusCode::OK);
+    let read = body["token"].as_str().unwrap().to_string();
+    assert!(read.starts_with("grpg_"));
+
+    let nodes = format!("/settings/{}/node", setting);
+    assert_eq!(call!(service, test::TestRequest::get().uri(&nodes), &read).0, StatusCode::OK);
+    let (status, body) = call!(service, test::TestRequest::post().uri(&nodes).set_json(json!({ "label": "Ada" })), &read);
+    assert_eq!((status, body["message"].as_str()), (StatusCode::FORBIDDEN, Some("Token scope does not allow this request")));
+    let (status, body) = call!(service, test::TestRequest::get().uri(&format!("/settings/{}/node", other["id"])), &read);
+    assert_eq!((status, body["message"].as_str()), (StatusCode::FORBIDDEN, Some("Token is not valid for this setting")));
+    assert_eq!(call!(service, test::TestRequest::get().uri("/tokens"), &read).0, StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn settings_are_guarded_by_roles() {
+    let state = state();
+    login!(state, service, token, setting);
+    let byron = session!(state, service, "byron");
+    let byron_id = state.store.get_users().unwrap().into_iter().find(|u| u.name == "byron").unwrap().id;
+    let nodes = format!("/settings/{}/node", setting);
+    let member = format!("/settings/{}/members/{}", setting, byron_id);
+    let add_node = || test::TestRequest::post().uri(&nodes).set_json(json!({ "label": "Ada" }));
+
+    assert_eq!(call!(service, test::TestRequest::get().uri(&nodes), "").0, StatusCode::UNAUTHORIZED);
+    assert_eq!(call!(service, test::TestRequest::get().uri(&nodes), &byron).0, StatusCode::FORBIDDEN);
+    assert_eq!(call!(service, test::TestRequest::get().uri("/settings/99/node"), &token).0, StatusCode::NOT_FOUND);
+
+    let viewer = test::TestRequest::put().uri(&member).set_json(json!({ "role": "viewer" }));
+    assert_eq!(call!(service, viewer, &token).0, StatusCode::OK);
+    assert_eq!(call!(service, test::TestRequest::get().uri(&nodes), &byron).0, StatusCode::OK);
+    assert_eq!(call!(service, add_node(), &byron).0, StatusCode::FORBIDDEN);
+    let promotion = test::TestRequest::put().uri(&member).set_json(json!({ "role": "owner" }));
+    assert_eq!(call!(service, promotion, &byron).0, StatusCode::FORBIDDEN);
+
+    let editor = test::TestRequest::put().uri(&member).set_json(json!({ "role": "editor" }));
+    assert_eq!(call!(service, editor, &token).0, StatusCode::OK);
+    assert_eq!(call!(service, add_node(), &byron).0, StatusCode::OK);
+    assert_eq!(call!(service, test::TestRequest::get().uri(&format!("/settings/{}/members", setting)), &byron).0, StatusCode::FORBIDDEN);
+    let (_, settings) = call!(service, test::TestRequest::get().uri("/settings"), &byron);
+    assert_eq!(settings.as_array().map(Vec::len), Some(1));
+}
+
+#[actix_web::test]
+async fn organizations_share_their_settings_with_members() {
+    let state = state();
+    login!(state, service, token, _setting);
+    let byron = session!(state, service, "byron");
+    let (_, organization) = call!(service, test::TestRequest::post().uri("/organizations").set_json(json!({ "name": "Analytical" })), &token);
+    let organization = organization["id"].as_i64().unwrap();
+    let (_, setting) = call!(service, test::TestRequest::post().uri("/settings").set_json(json!({ "name": "engine", "organization_id": organization })), &token);
+    let nodes = format!("/settings/{}/node", setting["id"]);
+    assert_eq!(call!(service, test::TestRequest::get().uri(&nodes), &byron).0, StatusCode::FORBIDDEN);
+
+    let invite = || test::TestRequest::post().uri(&format!("/organizations/{}/invitations", organization)).set_json(json!({ "username": "byron", "role": "viewer" }));
+    assert_eq!(call!(service, invite(), &byron).0, StatusCode::NOT_FOUND);
+    assert_eq!(call!(service, invite(), &token).0, StatusCode::OK);
+    let (_, invitations) = call!(service, test::TestRequest::get().uri("/invitations"), &byron);
+    assert_eq!(invitations, json!([{ "id": organization, "name": "Analytical", "role": "viewer" }]));
+    assert_eq!(call!(service, test::TestRequest::get().uri(&nodes), &byron).0, StatusCode::FORBIDDEN);
+
+    let accept = test::TestRequest::post().uri(&format!("/invitations/{}/accept", organization));
+    assert_eq!(call!(service, accept, &byron).0, StatusCode::OK);
+    assert_eq!(call!(service, test::TestRequest::get().uri(&nodes), &byron).0, StatusCode::OK);
+    let add_node = test::TestRequest::post().uri(&nodes).set_json(json!({ "label": "Ada" }));
+    assert_eq!(call!(service, add_node, &byron).0, StatusCode::FORBIDDEN);
+    let (_, invitations) = call!(service, test::TestRequest::get().uri("/invitations"), &byron);
+    assert_eq!(invitations, json!([]));
+}
+
+#[actix_web::test]
+async fn share_links_only_show_what_they_share() {
+    setup!(state, service, token, setting);
+    let table = json!({ "label": "Parents", "filter": {}, "columns": [{ "id": 1, "filter": { "predicate_id": 1, "direction": "in" } }] });
+    call!(service, test::TestRequest::post().uri(&format!("/settings/{}/table", setting)).set_json(table), &token);
+    let (_, tables) = call!(service, test::TestRequest::get().uri(&format!("/settings/{}/tables", setting)), &token);
+    let table_id = tables[0]["id"].as_i64().unwrap();
+    let shares = format!("/settings/{}/shares", setting);
+    let (_, whole) = call!(service, test::TestRequest::post().uri(&shares).set_json(json!({})), &token);
+    let (_, only_table) = call!(service, test::TestRequest::post().uri(&shares).set_json(json!({ "table_id": table_id, "expires_in": 60 })), &token);
+    let shared = |share: &serde_json::Value, rest: &str| format!("/shared/{}{}", share["token"].as_str().unwrap(), rest);
+
+    let (status, body) = call!(service, test::TestRequest::get().uri(&shared(&whole, "")), "");
+    assert_eq!((status, body["name"].as_str()), (StatusCode::OK, Some("family")));
+    let (_, nodes) = call!(service, test::TestRequest::get().uri(&shared(&whole, "/nodes")), "");
+    assert_eq!(nodes.as_array().map(Vec::len), Some(2));
+
+    assert_eq!(call!(service, test::TestRequest::get().uri(&shared(&only_table, "/nodes")), "").0, StatusCode::NOT_FOUND);
+    assert_eq!(call!(service, test::TestRequest::get().uri(&shared(&only_table, "/triples")), "").0, StatusCode::NOT_FOUND);
+    let (status, rows) = call!(service, test::TestRequest::get().uri(&shared(&only_table, &format!("/tables/{}", table_id))), "");
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rows["headers"], json!(["parent of (in)"]));
+    assert_eq!(call!(service, test::TestRequest::get().uri(&shared(&only_table, &format!("/tables/{}", table_id + 1))), "").0, StatusCode::NOT_FOUND);
+
+    let too_long = test::TestRequest::post().uri(&shares).set_json(json!({ "expires_in": u64::MAX }));
+    assert_eq!(call!(service, too_long, &token).0, StatusCode::BAD_REQUEST);
+    let revoke = test::TestRequest::delete().uri(&format!("{}/{}", shares, whole["id"]));
+    assert_eq!(call!(service, revoke, &token).0, StatusCode::OK);
+    assert_eq!(call!(service, test::TestRequest::get().uri(&shared(&whole, "/nodes")), "").0, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::error::RecvError;
+use grpg_core::db::base::Store;
+use grpg_core::db::events::ChangeEvent;
+use grpg_core::db::models::{
+    Delivery,
+    Webhook
+};
+use crate::endpoints::hooks::sign_payload;
+
+/// Attempts made before a delivery is given up.
+const MAX_ATTEMPTS: u32 = 5;
+/// Wait before the first retry, doubled after every failed attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delivers every change published on the store's events to the webhooks
+/// of its setting that accept it. Runs until the events channel closes.
+pub async fn run(store: Arc<Store>) {
+    let mut receiver = store.events().subscribe();
+    let client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .expect("Failed to build webhook client");
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("Webhook dispatcher missed {} events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        let webhooks = match store.conn(event.setting).get_webhooks() {
+            Ok(Some(webhooks)) => webhooks,
+            Ok(None) => continue,
+            Err(e) => {
+                log::error!("Failed to read the webhooks of setting {}: {}", event.setting, e);
+                continue;
+            }
+        };
+        let Ok(payload) = serde_json::to_vec(&event) else {
+            continue;
+        };
+        for (webhook_id, webhook) in webhooks {
+            if webhook.accepts(event.change.name()) {
+                actix_web::rt::spawn(deliver(
+                    store.clone(),
+                    client.clone(),
+                    webhook_id,
+                    webhook,
+                    event.clone(),
+                    payload.clone(),
+                    INITIAL_BACKOFF,
+                ));
+            }
+        }
+    }
+}
+
+async fn deliver(
+    store: Arc<Store>,
+    client: reqwest::Client,
+    webhook_id: i32,
+    webhook: Webhook,
+    event: ChangeEvent,
+    payload: Vec<u8>,
+    mut backoff: Duration,
+) {
+    // Random, as event sequences start over when the server restarts
+    let mut bytes = [0u8; 16];
+    if let Err(e) = getrandom::fill(&mut bytes) {
+        log::warn!("Failed to deliver to webhook {}: {}", webhook_id, e);
+        return;
+    }
+    let delivery_id = hex::encode(bytes);
+    let signature = sign_payload(&payload, webhook.secret.as_bytes());
+    for attempt in 1..=MAX_ATTEMPTS {
+        let response = client
+            .post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .header("X-Grpg-Event", event.change.name())
+            .header("X-Grpg-Delivery", &delivery_id)
+            .header("X-Grpg-Signature-256", &signature)
+            .body(payload.clone())
+            .send()
+            .await;
+        let (status, error) = match response {
+            Ok(r) if r.status().is_success() => (Some(r.status().as_u16()), None),
+            Ok(r) => (Some(r.status().as_u16()), Some(format!("Receiver answered {}", r.status()))),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let success = error.is_none();
+        let recorded = store.conn(event.setting).add_delivery(Delivery {
+            delivery_id: delivery_id.clone(),
+            webhook_id,
+            event: event.change.name().to_string(),
+            attempt,
+            status,
+            error,
+            success,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        });
+        if let Err(e) = recorded {
+            log::error!("Failed to record delivery {} to webhook {}: {}", delivery_id, webhook_id, e);
+        }
+        if success {
+            return;
+        }
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+    log::warn!("Gave up delivering {} to webhook {}", delivery_id, webhook_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use grpg_core::db::events::{Change, Events};
+    use grpg_core::db::models::Quota;
+    use super::*;
+
+    /// Request received by the stand-in receiver: its headers, lowercased, and body.
+    type Received = (Vec<(String, String)>, Vec<u8>);
+
+    /// Local HTTP receiver answering its requests with `statuses` in turn.
+    fn receiver(statuses: Vec<u16>) -> (String, mpsc::Receiver<Received>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (sender, received) = mpsc::channel();
+        std::thread::spawn(move || {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut headers = Vec::new();
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(':') else { break };
+                    headers.push((name.to_lowercase(), value.trim().to_string()));
+                }
+                let length = headers.iter()
+                    .find(|(name, _)| name == "content-length")
+                    .map_or(0, |(_, value)| value.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                sender.send((headers, body)).unwrap();
+                write!(stream, "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    fn header<'a>(request: &'a Received, name: &str) -> &'a str {
+        &request.0.iter().find(|(n, _)| n == name).unwrap().1
+    }
+
+    async fn deliver_to(statuses: Vec<u16>) -> (Vec<Received>, Vec<Delivery>) {
+        let store = Arc::new(Store::in_memory(Arc::new(Events::new()), Quota::default()));
+        let setting = store.user_settings(1).add_setting("hooks".to_string(), None).unwrap();
+        let (url, received) = receiver(statuses);
+        let webhook = Webhook { url, secret: "secret".to_string(), events: Vec::new() };
+        let webhook_id = store.conn(setting).add_webhook(webhook.clone()).unwrap().unwrap();
+        let event = ChangeEvent { sequence: 1, setting, change: Change::NodeDeleted { node_id: 7 } };
+        let payload = serde_json::to_vec(&event).unwrap();
+        deliver(store.clone(), reqwest::Client::new(), webhook_id, webhook, event, payload, Duration::from_millis(1)).await;
+        (received.try_iter().collect(), store.conn(setting).get_deliveries(webhook_id).unwrap().unwrap())
+    }
+
+    #[actix_web::test]
+    async fn signs_and_retries_until_accepted() {
+        let (requests, deliveries) = deliver_to(vec![500, 503, 204]).await;
+        assert_eq!(requests.len(), 3);
+        for request in &requests {
+            assert_eq!(header(request, "x-grpg-signature-256"), sign_payload(&request.1, b"secret"));
+            assert_eq!(header(request, "x-grpg-event"), "node_deleted");
+            assert_eq!(header(request, "x-grpg-delivery"), deliveries[0].delivery_id);
+        }
+        let attempts: Vec<_> = deliveries.iter().map(|d| (d.attempt, d.status, d.success)).collect();
+        assert_eq!(attempts, vec![(1, Some(500), false), (2, Some(503), false), (3, Some(204), true)]);
+    }
+
+    #[actix_web::test]
+    async fn gives_up_after_max_attempts() {
+        let (requests, deliveries) = deliver_to(vec![500; MAX_ATTEMPTS as usize]).await;
+        assert_eq!(requests.len(), MAX_ATTEMPTS as usize);
+        assert_eq!(deliveries.len(), MAX_ATTEMPTS as usize);
+        assert!(deliveries.iter().all(|d| !d.success && d.error.is_some()));
+    }
+
+    #[actix_web::test]
+    async fn delivery_ids_differ_between_events() {
+        let (_, first) = deliver_to(vec![200]).await;
+        let (_, second) = deliver_to(vec![200]).await;
+        assert_ne!(first[0].delivery_id, second[0].delivery_id);
+    }
+
+    #[test]
+    fn webhook_ids_are_not_reused() {
+        let store = Store::in_memory(Arc::new(Events::new()), Quota::default());
+        let setting = store.conn(store.user_settings(1).add_setting("hooks".to_string(), None).unwrap());
+        let webhook = Webhook { url: "http://localhost/".to_string(), secret: String::new(), events: Vec::new() };
+        let first = setting.add_webhook(webhook.clone()).unwrap().unwrap();
+        setting.remove_webhook(first).unwrap().unwrap();
+        assert_eq!(setting.add_webhook(webhook).unwrap().unwrap(), first + 1);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod base;
+pub mod models;
+pub mod graph;
+pub mod events;
+pub mod audit;
+pub mod backup;
+pub mod error;
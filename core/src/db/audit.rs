@@ -0,0 +1,161 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// File the audit entries are appended to by default, one JSON object per line.
+const AUDIT_FILE: &str = "audit.jsonl";
+
+#[derive(Clone, Deserialize, Serialize, ToSchema)]
+pub struct AuditEntry {
+    /// Position of the entry in the log, starting at 1
+    pub id: u64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// User who made the request, absent for anonymous requests
+    pub actor: Option<i32>,
+    pub setting: Option<i32>,
+    /// Method and route of the request, such as `POST /settings/{setting_id}/node`
+    pub operation: String,
+    pub path: String,
+    /// Query string and body of the request
+    pub parameters: serde_json::Value,
+    /// Status code of the response
+    pub status: u16,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditFilter {
+    pub actor: Option<i32>,
+    /// Only entries whose operation contains this text
+    pub operation: Option<String>,
+    /// Only entries made at or after this time, in seconds since the Unix epoch
+    pub since: Option<u64>,
+    /// Only entries made before this time, in seconds since the Unix epoch
+    pub until: Option<u64>,
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.actor.is_none_or(|a| entry.actor == Some(a))
+            && self.operation.as_ref().is_none_or(|o| entry.operation.contains(o.as_str()))
+            && self.since.is_none_or(|t| entry.timestamp >= t)
+            && self.until.is_none_or(|t| entry.timestamp < t)
+    }
+}
+
+/// Append-only log of the mutating requests. Entries are never rewritten,
+/// only added at the end of the file.
+pub struct AuditLog {
+    path: PathBuf,
+    next_id: Mutex<u64>,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        AuditLog::new(AUDIT_FILE)
+    }
+}
+
+impl AuditLog {
+    /// The log kept in the file at `path`, created on the first entry.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let count = std::fs::File::open(&path)
+            .map(|f| BufReader::new(f).lines().count() as u64)
+            .unwrap_or(0);
+        AuditLog {
+            path,
+            next_id: Mutex::new(count + 1),
+        }
+    }
+
+    /// Appends the entry, numbering it after the last one.
+    pub fn append(&self, mut entry: AuditEntry) -> std::io::Result<()> {
+        let mut next_id = self.next_id.lock().unwrap();
+        entry.id = *next_id;
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(&line)?;
+        *next_id += 1;
+        Ok(())
+    }
+
+    /// Entries of the setting matching the filter, newest first, with the
+    /// number of matches.
+    pub fn query(&self, setting: i32, filter: &AuditFilter, offset: usize, limit: usize) -> (Vec<AuditEntry>, usize) {
+        let _lock = self.next_id.lock().unwrap();
+        let Ok(file) = std::fs::File::open(&self.path) else {
+            return (Vec::new(), 0);
+        };
+        let mut entries = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<AuditEntry>(&line).ok())
+            .filter(|entry| entry.setting == Some(setting) && filter.matches(entry))
+            .collect::<Vec<_>>();
+        let total = entries.len();
+        entries.reverse();
+        (entries.into_iter().skip(offset).take(limit).collect(), total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: u64, actor: Option<i32>, setting: i32, operation: &str) -> AuditEntry {
+        AuditEntry {
+            id: 0,
+            timestamp,
+            actor,
+            setting: Some(setting),
+            operation: operation.to_string(),
+            path: String::new(),
+            parameters: serde_json::Value::Null,
+            status: 200,
+        }
+    }
+
+    fn filter() -> AuditFilter {
+        AuditFilter { actor: None, operation: None, since: None, until: None }
+    }
+
+    #[test]
+    fn queries_a_setting_newest_first() {
+        let path = std::env::temp_dir().join(format!("grpg-audit-test-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let log = AuditLog::new(&path);
+        log.append(entry(10, Some(1), 1, "POST /settings/{setting_id}/node")).unwrap();
+        log.append(entry(20, Some(2), 1, "DELETE /settings/{setting_id}/node/{node_id}")).unwrap();
+        log.append(entry(30, None, 2, "POST /settings/{setting_id}/node")).unwrap();
+        log.append(entry(40, Some(1), 1, "POST /settings/{setting_id}/predicate")).unwrap();
+
+        let (entries, total) = log.query(1, &filter(), 0, 10);
+        assert_eq!(total, 3);
+        assert_eq!(entries.iter().map(|e| e.id).collect::<Vec<_>>(), [4, 2, 1]);
+        let (entries, total) = log.query(1, &filter(), 1, 1);
+        assert_eq!(total, 3);
+        assert_eq!(entries.iter().map(|e| e.id).collect::<Vec<_>>(), [2]);
+
+        let by_actor = AuditFilter { actor: Some(1), ..filter() };
+        assert_eq!(log.query(1, &by_actor, 0, 10).0.iter().map(|e| e.id).collect::<Vec<_>>(), [4, 1]);
+        let by_operation = AuditFilter { operation: Some("DELETE".to_string()), ..filter() };
+        assert_eq!(log.query(1, &by_operation, 0, 10).0.iter().map(|e| e.id).collect::<Vec<_>>(), [2]);
+        let by_time = AuditFilter { since: Some(20), until: Some(40), ..filter() };
+        assert_eq!(log.query(1, &by_time, 0, 10).0.iter().map(|e| e.id).collect::<Vec<_>>(), [2]);
+
+        // Numbering carries on when the log is opened again
+        let log = AuditLog::new(&path);
+        log.append(entry(50, None, 2, "DELETE /settings/{setting_id}")).unwrap();
+        assert_eq!(log.query(2, &filter(), 0, 10).0.iter().map(|e| e.id).collect::<Vec<_>>(), [5, 3]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
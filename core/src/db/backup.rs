@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::db::base::{
+    Store,
+    StoredDocument
+};
+use crate::db::error::DbError;
+use crate::db::graph::{
+    GraphBackend,
+    GraphDump
+};
+
+/// Version of the backup format, raised on incompatible changes.
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Number of documents of each collection of the store and the size of the
+/// graphs, recorded in backups and compared after restoring them.
+#[derive(Default, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct BackupCounts {
+    pub documents: BTreeMap<String, usize>,
+    pub graphs: usize,
+    pub nodes: usize,
+    pub predicates: usize,
+    pub triples: usize,
+}
+
+/// A whole instance: every document of the metadata store and the graph of
+/// every setting. It is written as gzipped JSON.
+#[derive(Deserialize, Serialize)]
+pub struct Backup {
+    pub version: u32,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub counts: BackupCounts,
+    pub documents: Vec<StoredDocument>,
+    pub graphs: Vec<GraphDump>,
+}
+
+impl Backup {
+    /// Reads the instance. The graphs are read while the store is held, so
+    /// the backup is consistent as long as the graphs are only changed
+    /// through the server.
+    pub fn take(store: &Store, graph: &dyn GraphBackend) -> Result<Backup, DbError> {
+        let (documents, graphs) = store.documents_with(|| graph.dump())?;
+        let graphs = graphs?;
+        let mut backup = Backup {
+            version: BACKUP_VERSION,
+            created_at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            counts: BackupCounts::default(),
+            documents,
+            graphs,
+        };
+        backup.counts = backup.count();
+        Ok(backup)
+    }
+
+    /// Counts of the documents and graphs the backup holds.
+    pub fn count(&self) -> BackupCounts {
+        let mut counts = BackupCounts::default();
+        for document in &self.documents {
+            *counts.documents.entry(document.collection.clone()).or_default() += 1;
+        }
+        counts.graphs = self.graphs.len();
+        for graph in &self.graphs {
+            counts.nodes += graph.nodes.len();
+            counts.predicates += graph.predicates.len();
+            counts.triples += graph.triples.len();
+        }
+        counts
+    }
+
+    pub fn write(&self, writer: impl Write) -> Result<(), DbError> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        serde_json::to_writer(&mut encoder, self).map_err(DbError::backend)?;
+        encoder.finish().map_err(DbError::backend)?;
+        Ok(())
+    }
+
+    /// Reads a backup, checking its version and that it holds what it counted.
+    pub fn read(reader: impl Read) -> Result<Backup, DbError> {
+        let backup: Backup = serde_json::from_reader(GzDecoder::new(reader))
+            .map_err(|e| DbError::invalid(format!("Invalid backup: {}", e)))?;
+        if backup.version > BACKUP_VERSION {
+            return Err(DbError::invalid(format!("Backup version {} is newer than the supported {}", backup.version, BACKUP_VERSION)));
+        }
+        if backup.count() != backup.counts {
+            return Err(DbError::invalid("Invalid backup: its content does not match its counts"));
+        }
+        Ok(backup)
+    }
+
+    /// Rebuilds the instance into an empty store and graph backend, then
+    /// checks that they hold as much as the backup. Both are left empty when
+    /// either fails to load.
+    pub fn restore(&self, store: &Store, graph: &dyn GraphBackend) -> Result<BackupCounts, DbError> {
+        if !graph.settings()?.is_empty() {
+            return Err(DbError::conflict("The graph backend is not empty"));
+        }
+        if let Err(e) = store.load_documents_with(&self.documents, || graph.load(&self.graphs)) {
+            for setting in graph.settings().unwrap_or_default() {
+                let _ = graph.setting_delete(setting);
+            }
+            return Err(e);
+        }
+        let restored = Backup::take(store, graph)?.counts;
+        if restored != self.counts {
+            let counts = |c: &BackupCounts| serde_json::to_string(c).unwrap_or_default();
+            return Err(DbError::backend(format!("Restored {}, the backup holds {}", counts(&restored), counts(&self.counts))));
+        }
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::db::events::Events;
+    use crate::db::graph::MemoryBackend;
+    use crate::db::models::{Node, Quota, Triple};
+    use super::*;
+
+    fn instance() -> (Store, MemoryBackend) {
+        (Store::in_memory(Arc::new(Events::default()), Quota::default()), MemoryBackend::new())
+    }
+
+    #[test]
+    fn restores_what_was_backed_up() {
+        let (store, graph) = instance();
+        let user = store.add_user("ada".to_string(), "secret").unwrap();
+        let setting = store.user_settings(user).add_setting("family".to_string(), None).unwrap();
+        let ada = graph.node_create(setting, "Ada".to_string()).unwrap();
+        let byron = graph.node_create(setting, "Byron".to_string()).unwrap();
+        let parent_of = graph.predicate_create(setting, "parent of").unwrap().id;
+        graph.triple_create(setting, Triple { subject_id: byron, predicate_id: parent_of, object_id: ada }).unwrap();
+
+        let mut written = Vec::new();
+        Backup::take(&store, &graph).unwrap().write(&mut written).unwrap();
+        let backup = Backup::read(written.as_slice()).unwrap();
+        assert_eq!((backup.counts.graphs, backup.counts.nodes, backup.counts.triples), (1, 2, 1));
+
+        let (restored_store, restored_graph) = instance();
+        assert!(backup.restore(&restored_store, &restored_graph).unwrap() == backup.counts);
+        assert!(restored_store.authenticate("ada", "secret").unwrap().is_some());
+        assert_eq!(restored_store.user_settings(user).get_settings().unwrap()[0].0, setting);
+        assert_eq!(restored_graph.triple_all(setting).unwrap().len(), 1);
+        assert!(matches!(backup.restore(&restored_store, &restored_graph), Err(DbError::Conflict(_))));
+    }
+
+    #[test]
+    fn failed_restores_leave_the_store_empty() {
+        let (store, graph) = instance();
+        let user = store.add_user("ada".to_string(), "secret").unwrap();
+        let setting = store.user_settings(user).add_setting("family".to_string(), None).unwrap();
+        graph.node_create(setting, "Ada".to_string()).unwrap();
+        let mut backup = Backup::take(&store, &graph).unwrap();
+        // The graph of a setting cannot be loaded twice
+        backup.graphs.push(GraphDump { setting, nodes: vec![Node { node_id: 1, label: "Ada".to_string() }], predicates: Vec::new(), triples: Vec::new() });
+
+        let (restored_store, restored_graph) = instance();
+        assert!(backup.restore(&restored_store, &restored_graph).is_err());
+        assert!(restored_store.get_users().unwrap().is_empty());
+        assert!(restored_graph.settings().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,390 @@
+mod setting_manager;
+mod user_settings_manager;
+mod organization_manager;
+mod tables;
+mod settings;
+mod webhooks;
+mod auth;
+mod members;
+mod organizations;
+mod shares;
+mod migrate;
+mod storage;
+mod sqlite;
+mod memory;
+pub use auth::API_TOKEN_PREFIX;
+pub use storage::{
+    Document,
+    Storage,
+    StoredDocument,
+    Transaction
+};
+pub use sqlite::SqliteStorage;
+pub use memory::MemoryStorage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use crate::db::error::DbError;
+use crate::db::events::Events;
+use crate::db::models::{
+    ApiToken,
+    OrganizationData,
+    Quota,
+    Role,
+    Session,
+    ShareLink,
+    TokenScope,
+    UserData,
+    Webhook,
+    Delivery
+};
+use crate::db::base::{
+    setting_manager::SettingManager,
+    user_settings_manager::UserSettingsManager,
+    organization_manager::OrganizationManager
+};
+
+#[derive(Deserialize, Serialize)]
+pub struct SettingData {
+    pub user_id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub webhooks: HashMap<i32, Webhook>,
+    /// Highest webhook id handed out, so the ids of removed webhooks are not reused
+    #[serde(default)]
+    pub last_webhook_id: i32,
+    #[serde(default)]
+    pub deliveries: Vec<Delivery>,
+    /// Users the setting is shared with, besides its owner `user_id`
+    #[serde(default)]
+    pub members: HashMap<i32, Role>,
+    /// Organization owning the setting, whose members all get their team role on it
+    #[serde(default)]
+    pub organization_id: Option<i32>
+}
+
+// Collections of the store
+const USERS: &str = "users";
+/// Password hashes by user id
+const CREDENTIALS: &str = "credentials";
+/// Sessions, found by the hash of their token
+const SESSIONS: &str = "sessions";
+/// API tokens, found by the hash of their token
+const API_TOKENS: &str = "api_tokens";
+const ORGANIZATIONS: &str = "organizations";
+const SETTINGS: &str = "settings";
+/// Table definitions, scoped by setting
+const TABLES: &str = "tables";
+/// Share links, scoped by setting and found by the hash of their token
+const SHARES: &str = "shares";
+/// Every collection, in the order they are backed up.
+const COLLECTIONS: [&str; 8] = [USERS, CREDENTIALS, SESSIONS, API_TOKENS, ORGANIZATIONS, SETTINGS, TABLES, SHARES];
+
+/// A transaction on the store. Every store operation runs in one, so its
+/// reads are consistent and its writes land together or not at all.
+struct Tx<'a>(Box<dyn Transaction + 'a>);
+
+/// Documents that no longer deserialize are reported as backend errors.
+fn parse<T: DeserializeOwned>(document: &Document) -> Result<T, DbError> {
+    serde_json::from_str(&document.data).map_err(DbError::backend)
+}
+
+impl Tx<'_> {
+    fn get<T: DeserializeOwned>(&self, collection: &str, id: i32) -> Result<Option<T>, DbError> {
+        self.get_scoped(collection, 0, id)
+    }
+    fn get_scoped<T: DeserializeOwned>(&self, collection: &str, scope: i32, id: i32) -> Result<Option<T>, DbError> {
+        self.0.get(collection, scope, id)?.map(|d| parse(&d)).transpose()
+    }
+    fn all<T: DeserializeOwned>(&self, collection: &str) -> Result<Vec<(i32, T)>, DbError> {
+        self.all_scoped(collection, 0)
+    }
+    fn all_scoped<T: DeserializeOwned>(&self, collection: &str, scope: i32) -> Result<Vec<(i32, T)>, DbError> {
+        self.0.list(collection, Some(scope))?
+            .into_iter()
+            .map(|(_, id, d)| Ok((id, parse(&d)?)))
+            .collect()
+    }
+    /// Document with the lookup key, with its scope and id.
+    fn find<T: DeserializeOwned>(&self, collection: &str, lookup: &str) -> Result<Option<(i32, i32, T)>, DbError> {
+        self.0.find(collection, lookup)?.map(|(scope, id, d)| Ok((scope, id, parse(&d)?))).transpose()
+    }
+    fn put<T: Serialize>(&mut self, collection: &str, id: i32, value: &T) -> Result<(), DbError> {
+        self.put_scoped(collection, 0, id, None, value)
+    }
+    fn put_scoped<T: Serialize>(&mut self, collection: &str, scope: i32, id: i32, lookup: Option<String>, value: &T) -> Result<(), DbError> {
+        self.0.put(collection, scope, id, Document {
+            lookup,
+            data: serde_json::to_string(value).map_err(DbError::backend)?,
+        })
+    }
+    fn delete<T: DeserializeOwned>(&mut self, collection: &str, id: i32) -> Result<Option<T>, DbError> {
+        self.delete_scoped(collection, 0, id)
+    }
+    fn delete_scoped<T: DeserializeOwned>(&mut self, collection: &str, scope: i32, id: i32) -> Result<Option<T>, DbError> {
+        let removed = self.get_scoped(collection, scope, id)?;
+        if removed.is_some() {
+            self.0.delete(collection, scope, id)?;
+        }
+        Ok(removed)
+    }
+    fn next_id(&self, collection: &str) -> Result<i32, DbError> {
+        self.next_scoped_id(collection, 0)
+    }
+    fn next_scoped_id(&self, collection: &str, scope: i32) -> Result<i32, DbError> {
+        Ok(self.0.list(collection, Some(scope))?.iter().map(|(_, id, _)| *id).max().unwrap_or(0) + 1)
+    }
+
+    /// Highest role the user has on the setting, directly or through its organization.
+    fn role(&self, setting_id: i32, user_id: i32) -> Result<Option<Role>, DbError> {
+        let Some(setting) = self.get::<SettingData>(SETTINGS, setting_id)? else {
+            return Ok(None);
+        };
+        let direct = if setting.user_id == user_id {
+            Some(Role::Owner)
+        } else {
+            setting.members.get(&user_id).copied()
+        };
+        let team = match setting.organization_id {
+            Some(id) => self.get::<OrganizationData>(ORGANIZATIONS, id)?.and_then(|o| o.members.get(&user_id).copied()),
+            None => None,
+        };
+        Ok(direct.max(team))
+    }
+    fn users(&self) -> Result<Vec<UserData>, DbError> {
+        Ok(self.all::<UserData>(USERS)?.into_iter().map(|(_, u)| u).collect())
+    }
+}
+
+/// The storage, the events published on changes, the quota of every setting
+/// and a lock per setting held while checking the quota of a graph write.
+pub struct Store(Mutex<Box<dyn Storage>>, Arc<Events>, Quota, Mutex<HashMap<i32, Arc<Mutex<()>>>>);
+
+impl Store {
+    /// `quota` applies to every setting.
+    pub fn new(storage: Box<dyn Storage>, events: Arc<Events>, quota: Quota) -> Self {
+        Store(Mutex::new(storage), events, quota, Mutex::new(HashMap::new()))
+    }
+    /// Opens the store kept in the SQLite database at `path`, importing the
+    /// YAML file the store was kept in before, at `legacy_path`, when the
+    /// database is new.
+    pub fn open(path: &str, legacy_path: &str, events: Arc<Events>, quota: Quota) -> Result<Self, String> {
+        let store = Store::new(Box::new(SqliteStorage::open(path)?), events, quota);
+        migrate::import_yaml(&store, legacy_path)?;
+        Ok(store)
+    }
+    /// A store kept in memory, for tests.
+    pub fn in_memory(events: Arc<Events>, quota: Quota) -> Self {
+        Store::new(Box::new(MemoryStorage::new()), events, quota)
+    }
+    pub fn events(&self) -> &Events {
+        &self.1
+    }
+    /// Quota of every setting.
+    pub fn quota(&self) -> Quota {
+        self.2
+    }
+    /// Runs `f` holding the setting's quota lock, so that the quota checked
+    /// by `f` still holds when it writes.
+    pub fn with_quota_lock<R>(&self, setting_id: i32, f: impl FnOnce(Quota) -> Result<R, DbError>) -> Result<R, DbError> {
+        let lock = self.3.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(setting_id)
+            .or_default()
+            .clone();
+        let _guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        f(self.2)
+    }
+    pub fn conn(&self, setting: i32) -> SettingManager<'_> {
+        SettingManager {
+            store: self,
+            setting
+        }
+    }
+    pub fn user_settings(&self, user_id: i32) -> UserSettingsManager<'_> {
+        UserSettingsManager {
+            store: self,
+            user_id
+        }
+    }
+
+    pub fn organization(&self, organization_id: i32) -> OrganizationManager<'_> {
+        OrganizationManager {
+            store: self,
+            organization_id
+        }
+    }
+    pub fn add_organization(&self, user_id: i32, name: String) -> Result<i32, DbError> {
+        organizations::add_organization(self, user_id, name)
+    }
+    pub fn get_organizations(&self, user_id: i32) -> Result<Vec<(i32, OrganizationData)>, DbError> {
+        organizations::get_organizations(self, user_id)
+    }
+    pub fn get_invitations(&self, user_id: i32) -> Result<Vec<(i32, OrganizationData)>, DbError> {
+        organizations::get_invitations(self, user_id)
+    }
+
+    /// Share link of the token with its setting, unless it expired.
+    pub fn get_share(&self, token: &str) -> Result<Option<(i32, ShareLink)>, DbError> {
+        shares::get_share(self, token)
+    }
+
+    pub fn get_setting(&self, setting_id: i32) -> Result<Option<SettingData>, DbError> {
+        self.read(|tx| tx.get(SETTINGS, setting_id))
+    }
+    pub fn get_all_settings(&self) -> Result<Vec<(i32, SettingData)>, DbError> {
+        settings::get_all_settings(self)
+    }
+    pub fn rename_setting(&self, setting_id: i32, name: String) -> Result<Option<()>, DbError> {
+        settings::rename_setting(self, setting_id, name)
+    }
+    /// Removes the setting with its tables and share links, not its graph.
+    pub fn remove_setting(&self, setting_id: i32) -> Result<Option<SettingData>, DbError> {
+        settings::remove_setting(self, setting_id)
+    }
+
+    pub fn add_user(&self, name: String, password: &str) -> Result<i32, DbError> {
+        auth::add_user(self, name, password)
+    }
+    /// Sets the password of the user, which also logs them out everywhere.
+    pub fn set_password(&self, user_id: i32, password: &str) -> Result<(), DbError> {
+        auth::set_password(self, user_id, password)
+    }
+    pub fn authenticate(&self, name: &str, password: &str) -> Result<Option<UserData>, DbError> {
+        auth::authenticate(self, name, password)
+    }
+    pub fn add_session(&self, user_id: i32) -> Result<(String, u64), DbError> {
+        auth::add_session(self, user_id)
+    }
+    pub fn get_session_user(&self, token: &str) -> Result<Option<UserData>, DbError> {
+        auth::get_session_user(self, token)
+    }
+    pub fn remove_session(&self, token: &str) -> Result<Option<Session>, DbError> {
+        auth::remove_session(self, token)
+    }
+    pub fn add_api_token(&self, user_id: i32, name: String, scope: TokenScope, settings: Option<Vec<i32>>) -> Result<(i32, String, ApiToken), DbError> {
+        auth::add_api_token(self, user_id, name, scope, settings)
+    }
+    pub fn get_api_tokens(&self, user_id: i32) -> Result<Vec<(i32, ApiToken)>, DbError> {
+        auth::get_api_tokens(self, user_id)
+    }
+    pub fn remove_api_token(&self, user_id: i32, id: i32) -> Result<Option<ApiToken>, DbError> {
+        auth::remove_api_token(self, user_id, id)
+    }
+    pub fn get_api_token_user(&self, token: &str) -> Result<Option<(UserData, ApiToken)>, DbError> {
+        auth::get_api_token_user(self, token)
+    }
+    pub fn get_user(&self, id: i32) -> Result<Option<UserData>, DbError> {
+        self.read(|tx| tx.get(USERS, id))
+    }
+
+    pub fn get_users(&self) -> Result<Vec<UserData>, DbError> {
+        self.read(|tx| tx.users())
+    }
+    /// Every document of the store, read while `f` runs, so that what `f`
+    /// reads elsewhere is consistent with them. Other store operations wait
+    /// meanwhile.
+    pub fn documents_with<R>(&self, f: impl FnOnce() -> R) -> Result<(Vec<StoredDocument>, R), DbError> {
+        self.read(|tx| {
+            let mut documents = Vec::new();
+            for collection in COLLECTIONS {
+                documents.extend(tx.0.list(collection, None)?
+                    .into_iter()
+                    .map(|(scope, id, d)| StoredDocument {
+                        collection: collection.to_string(),
+                        scope,
+                        id,
+                        lookup: d.lookup,
+                        data: d.data,
+                    }));
+            }
+            Ok((documents, f()))
+        })
+    }
+    /// Writes backed up documents into the store, which must be empty, then
+    /// runs `f`. The documents are kept only when `f` succeeds.
+    pub fn load_documents_with<R>(&self, documents: &[StoredDocument], f: impl FnOnce() -> Result<R, DbError>) -> Result<R, DbError> {
+        if let Some(unknown) = documents.iter().find(|d| !COLLECTIONS.contains(&d.collection.as_str())) {
+            return Err(DbError::invalid(format!("Unknown collection {}", unknown.collection)));
+        }
+        self.write(|tx| {
+            for collection in COLLECTIONS {
+                if !tx.0.list(collection, None)?.is_empty() {
+                    return Err(DbError::conflict("The store is not empty"));
+                }
+            }
+            for d in documents {
+                tx.0.put(&d.collection, d.scope, d.id, Document {
+                    lookup: d.lookup.clone(),
+                    data: d.data.clone(),
+                })?;
+            }
+            f()
+        })
+    }
+    /// The storage, even when a thread panicked holding it: its transaction
+    /// was then dropped and rolled back.
+    fn storage(&self) -> MutexGuard<'_, Box<dyn Storage>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    /// Runs `f` in a transaction that is rolled back, for reads.
+    fn read<R>(&self, f: impl FnOnce(&Tx) -> Result<R, DbError>) -> Result<R, DbError> {
+        let mut storage = self.storage();
+        let tx = Tx(storage.transaction()?);
+        f(&tx)
+    }
+    /// Runs `f` in a transaction committed once it returns, or rolled back
+    /// when it fails, so a failed operation writes nothing.
+    fn write<R>(&self, f: impl FnOnce(&mut Tx) -> Result<R, DbError>) -> Result<R, DbError> {
+        let mut storage = self.storage();
+        let mut tx = Tx(storage.transaction()?);
+        let result = f(&mut tx)?;
+        tx.0.commit()?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rolls_back_failed_writes(storage: Box<dyn Storage>) {
+        let store = Store::new(storage, Arc::new(Events::default()), Quota::default());
+        let failed = store.write(|tx| {
+            tx.put(USERS, 1, &UserData { id: 1, name: "ada".to_string() })?;
+            Err::<(), _>(DbError::conflict("Rejected after writing"))
+        });
+        assert!(matches!(failed, Err(DbError::Conflict(_))));
+        assert!(store.get_users().unwrap().is_empty());
+    }
+
+    #[test]
+    fn memory_rolls_back_failed_writes() {
+        rolls_back_failed_writes(Box::new(MemoryStorage::new()));
+    }
+
+    #[test]
+    fn sqlite_rolls_back_failed_writes() {
+        rolls_back_failed_writes(Box::new(SqliteStorage::open(":memory:").unwrap()));
+    }
+
+    #[test]
+    fn loads_documents_only_with_what_follows() {
+        let store = Store::in_memory(Arc::new(Events::default()), Quota::default());
+        let user = Document { lookup: None, data: r#"{"id":1,"name":"ada"}"#.to_string() };
+        let documents = [StoredDocument { collection: USERS.to_string(), scope: 0, id: 1, lookup: user.lookup, data: user.data }];
+        let failed = store.load_documents_with(&documents, || Err::<(), _>(DbError::backend("Graphs not loaded")));
+        assert!(matches!(failed, Err(DbError::Backend(_))));
+        assert!(store.get_users().unwrap().is_empty());
+        store.load_documents_with(&documents, || Ok(())).unwrap();
+        assert_eq!(store.get_users().unwrap().len(), 1);
+        assert!(matches!(store.load_documents_with(&documents, || Ok(())), Err(DbError::Conflict(_))));
+    }
+
+    #[test]
+    fn reports_unreadable_documents() {
+        let store = Store::in_memory(Arc::new(Events::default()), Quota::default());
+        store.write(|tx| tx.0.put(USERS, 0, 1, Document { lookup: None, data: "{".to_string() })).unwrap();
+        assert!(matches!(store.get_user(1), Err(DbError::Backend(_))));
+    }
+}
@@ -0,0 +1,253 @@
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2
+};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::db::error::DbError;
+use crate::db::models::{
+    ApiToken,
+    Session,
+    TokenScope,
+    UserData
+};
+use crate::db::base::{
+    Store,
+    API_TOKENS,
+    CREDENTIALS,
+    SESSIONS,
+    USERS,
+};
+
+/// Prefix telling API tokens apart from session tokens.
+pub const API_TOKEN_PREFIX: &str = "grpg_";
+
+/// Seconds a session stays valid after login.
+const SESSION_TTL: u64 = 30 * 24 * 60 * 60;
+
+pub(super) fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+pub(super) fn random_bytes<const N: usize>() -> Result<[u8; N], DbError> {
+    let mut bytes = [0u8; N];
+    getrandom::fill(&mut bytes).map_err(DbError::backend)?;
+    Ok(bytes)
+}
+
+fn hash_password(password: &str) -> Result<String, DbError> {
+    let salt = SaltString::encode_b64(&random_bytes::<16>()?).map_err(DbError::backend)?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(DbError::backend)
+}
+
+/// Hash checked when logging in as an unknown user, so that the time taken
+/// does not tell which user names exist.
+fn dummy_hash() -> &'static str {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+    DUMMY.get_or_init(|| hash_password("").unwrap_or_default())
+}
+
+/// Sessions are stored by the hash of their token, so the store never holds
+/// a usable token.
+pub(super) fn token_key(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn add_user(store: &Store, name: String, password: &str) -> Result<i32, DbError> {
+    let password_hash = hash_password(password)?;
+    store.write(|tx| {
+        if tx.users()?.iter().any(|u| u.name == name) {
+            return Err(DbError::conflict("Username already taken"));
+        }
+        let next_id = tx.next_id(USERS)?;
+        tx.put(USERS, next_id, &UserData {
+            id: next_id,
+            name
+        })?;
+        tx.put(CREDENTIALS, next_id, &password_hash)?;
+        Ok(next_id)
+    })
+}
+
+/// Sets the user's password, closing their sessions.
+pub fn set_password(store: &Store, user_id: i32, password: &str) -> Result<(), DbError> {
+    let password_hash = hash_password(password)?;
+    store.write(|tx| {
+        if tx.get::<UserData>(USERS, user_id)?.is_none() {
+            return Err(DbError::not_found("User not found"));
+        }
+        tx.put(CREDENTIALS, user_id, &password_hash)?;
+        for (id, session) in tx.all::<Session>(SESSIONS)? {
+            if session.user_id == user_id {
+                tx.delete::<Session>(SESSIONS, id)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+pub fn authenticate(store: &Store, name: &str, password: &str) -> Result<Option<UserData>, DbError> {
+    let found = store.read(|tx| {
+        let Some(user) = tx.users()?.into_iter().find(|u| u.name == name) else {
+            return Ok(None);
+        };
+        Ok(tx.get::<String>(CREDENTIALS, user.id)?.map(|hash| (user, hash)))
+    })?;
+    let (user, hash) = match found {
+        Some((user, hash)) => (Some(user), hash),
+        None => (None, dummy_hash().to_string()),
+    };
+    let verified = PasswordHash::new(&hash).is_ok_and(|hash| {
+        Argon2::default().verify_password(password.as_bytes(), &hash).is_ok()
+    });
+    Ok(user.filter(|_| verified))
+}
+
+/// Opens a session for the user, returning its token and expiry.
+pub fn add_session(store: &Store, user_id: i32) -> Result<(String, u64), DbError> {
+    let token = hex::encode(random_bytes::<32>()?);
+    let now = now();
+    let expires_at = now + SESSION_TTL;
+    store.write(|tx| {
+        for (id, session) in tx.all::<Session>(SESSIONS)? {
+            if session.expires_at <= now {
+                tx.delete::<Session>(SESSIONS, id)?;
+            }
+        }
+        let next_id = tx.next_id(SESSIONS)?;
+        tx.put_scoped(SESSIONS, 0, next_id, Some(token_key(&token)), &Session { user_id, expires_at })
+    })?;
+    Ok((token, expires_at))
+}
+
+pub fn get_session_user(store: &Store, token: &str) -> Result<Option<UserData>, DbError> {
+    store.read(|tx| {
+        let Some((_, _, session)) = tx.find::<Session>(SESSIONS, &token_key(token))? else {
+            return Ok(None);
+        };
+        if session.expires_at <= now() {
+            return Ok(None);
+        }
+        tx.get(USERS, session.user_id)
+    })
+}
+
+pub fn remove_session(store: &Store, token: &str) -> Result<Option<Session>, DbError> {
+    store.write(|tx| {
+        let Some((_, id, _)) = tx.find::<Session>(SESSIONS, &token_key(token))? else {
+            return Ok(None);
+        };
+        tx.delete(SESSIONS, id)
+    })
+}
+
+/// Creates an API token, returning its id and the token itself, which is not
+/// kept and cannot be shown again.
+pub fn add_api_token(
+    store: &Store,
+    user_id: i32,
+    name: String,
+    scope: TokenScope,
+    settings: Option<Vec<i32>>
+) -> Result<(i32, String, ApiToken), DbError> {
+    let token = format!("{}{}", API_TOKEN_PREFIX, hex::encode(random_bytes::<32>()?));
+    let api_token = ApiToken {
+        user_id,
+        name,
+        scope,
+        settings,
+        created_at: now(),
+        token_hash: token_key(&token),
+    };
+    let id = store.write(|tx| {
+        let next_id = tx.next_id(API_TOKENS)?;
+        tx.put_scoped(API_TOKENS, 0, next_id, Some(api_token.token_hash.clone()), &api_token)?;
+        Ok(next_id)
+    })?;
+    Ok((id, token, api_token))
+}
+
+pub fn get_api_tokens(store: &Store, user_id: i32) -> Result<Vec<(i32, ApiToken)>, DbError> {
+    Ok(store.read(|tx| tx.all::<ApiToken>(API_TOKENS))?
+        .into_iter()
+        .filter(|(_, t)| t.user_id == user_id)
+        .collect())
+}
+
+pub fn remove_api_token(store: &Store, user_id: i32, id: i32) -> Result<Option<ApiToken>, DbError> {
+    store.write(|tx| {
+        if tx.get::<ApiToken>(API_TOKENS, id)?.is_none_or(|t| t.user_id != user_id) {
+            return Ok(None);
+        }
+        tx.delete(API_TOKENS, id)
+    })
+}
+
+/// User of the API token, with the token. Its hash is kept as its lookup key
+/// rather than with it.
+pub fn get_api_token_user(store: &Store, token: &str) -> Result<Option<(UserData, ApiToken)>, DbError> {
+    let token_hash = token_key(token);
+    store.read(|tx| {
+        let Some((_, _, api_token)) = tx.find::<ApiToken>(API_TOKENS, &token_hash)? else {
+            return Ok(None);
+        };
+        Ok(tx.get(USERS, api_token.user_id)?.map(|user| (user, ApiToken { token_hash, ..api_token })))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::db::events::Events;
+    use crate::db::models::Quota;
+    use super::*;
+
+    fn store() -> Store {
+        Store::in_memory(Arc::new(Events::default()), Quota::default())
+    }
+
+    #[test]
+    fn authenticates_by_password() {
+        let store = store();
+        let id = add_user(&store, "ada".to_string(), "secret").unwrap();
+        assert_eq!(authenticate(&store, "ada", "secret").unwrap().map(|u| u.id), Some(id));
+        assert!(authenticate(&store, "ada", "wrong").unwrap().is_none());
+        assert!(authenticate(&store, "byron", "secret").unwrap().is_none());
+        assert!(matches!(add_user(&store, "ada".to_string(), "other"), Err(DbError::Conflict(_))));
+    }
+
+    #[test]
+    fn sessions_end_at_logout_or_expiry() {
+        let store = store();
+        let id = add_user(&store, "ada".to_string(), "secret").unwrap();
+        let (token, expires_at) = add_session(&store, id).unwrap();
+        assert!(expires_at > now());
+        assert_eq!(get_session_user(&store, &token).unwrap().map(|u| u.id), Some(id));
+        assert!(remove_session(&store, &token).unwrap().is_some());
+        assert!(get_session_user(&store, &token).unwrap().is_none());
+
+        store.write(|tx| tx.put_scoped(SESSIONS, 0, 9, Some(token_key("old")), &Session { user_id: id, expires_at: now() - 1 })).unwrap();
+        assert!(get_session_user(&store, "old").unwrap().is_none());
+        add_session(&store, id).unwrap();
+        assert!(store.read(|tx| tx.get::<Session>(SESSIONS, 9)).unwrap().is_none());
+    }
+
+    #[test]
+    fn password_resets_close_sessions() {
+        let store = store();
+        let ada = add_user(&store, "ada".to_string(), "secret").unwrap();
+        let byron = add_user(&store, "byron".to_string(), "secret").unwrap();
+        let (ada_token, _) = add_session(&store, ada).unwrap();
+        let (byron_token, _) = add_session(&store, byron).unwrap();
+        set_password(&store, ada, "changed").unwrap();
+        assert!(get_session_user(&store, &ada_token).unwrap().is_none());
+        assert!(get_session_user(&store, &byron_token).unwrap().is_some());
+        assert!(authenticate(&store, "ada", "secret").unwrap().is_none());
+        assert!(authenticate(&store, "ada", "changed").unwrap().is_some());
+        assert!(matches!(set_password(&store, 99, "x"), Err(DbError::NotFound(_))));
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use crate::db::error::DbError;
+use crate::db::models::{
+    Role,
+    UserData
+};
+use crate::db::base::{
+    Store,
+    SettingData,
+    SETTINGS,
+    USERS,
+};
+
+/// Members of the setting, its owner included.
+pub fn get_members(store: &Store, setting_id: i32) -> Result<Option<HashMap<i32, Role>>, DbError> {
+    let setting = store.read(|tx| tx.get::<SettingData>(SETTINGS, setting_id))?;
+    Ok(setting.map(|setting| {
+        let mut members = setting.members;
+        members.insert(setting.user_id, Role::Owner);
+        members
+    }))
+}
+
+pub fn get_role(store: &Store, setting_id: i32, user_id: i32) -> Result<Option<Role>, DbError> {
+    store.read(|tx| tx.role(setting_id, user_id))
+}
+
+pub fn set_member(store: &Store, setting_id: i32, user_id: i32, role: Role) -> Result<(), DbError> {
+    store.write(|tx| {
+        let mut setting: SettingData = tx.get(SETTINGS, setting_id)?.ok_or_else(|| DbError::not_found("Setting not found"))?;
+        if setting.user_id == user_id {
+            return Err(DbError::invalid("The setting's creator is always an owner"));
+        }
+        tx.get::<UserData>(USERS, user_id)?.ok_or_else(|| DbError::not_found("User not found"))?;
+        setting.members.insert(user_id, role);
+        tx.put(SETTINGS, setting_id, &setting)
+    })
+}
+
+pub fn remove_member(store: &Store, setting_id: i32, user_id: i32) -> Result<Option<Role>, DbError> {
+    store.write(|tx| {
+        let Some(mut setting) = tx.get::<SettingData>(SETTINGS, setting_id)? else {
+            return Ok(None);
+        };
+        let Some(removed) = setting.members.remove(&user_id) else {
+            return Ok(None);
+        };
+        tx.put(SETTINGS, setting_id, &setting)?;
+        Ok(Some(removed))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::db::events::Events;
+    use crate::db::models::Quota;
+    use crate::db::base::settings::add_setting;
+    use super::*;
+
+    #[test]
+    fn members_get_the_role_they_are_given() {
+        let store = Store::in_memory(Arc::new(Events::default()), Quota::default());
+        let ada = store.add_user("ada".to_string(), "secret").unwrap();
+        let byron = store.add_user("byron".to_string(), "secret").unwrap();
+        let setting = add_setting(&store, ada, "family".to_string(), None).unwrap();
+        assert_eq!(get_role(&store, setting, ada).unwrap(), Some(Role::Owner));
+        assert_eq!(get_role(&store, setting, byron).unwrap(), None);
+
+        set_member(&store, setting, byron, Role::Viewer).unwrap();
+        assert_eq!(get_role(&store, setting, byron).unwrap(), Some(Role::Viewer));
+        assert_eq!(get_members(&store, setting).unwrap().unwrap().len(), 2);
+        assert!(matches!(set_member(&store, setting, ada, Role::Viewer), Err(DbError::Invalid(_))));
+        assert!(matches!(set_member(&store, setting, 99, Role::Viewer), Err(DbError::NotFound(_))));
+
+        assert_eq!(remove_member(&store, setting, byron).unwrap(), Some(Role::Viewer));
+        assert_eq!(get_role(&store, setting, byron).unwrap(), None);
+        assert_eq!(remove_member(&store, setting, ada).unwrap(), None);
+        assert_eq!(get_role(&store, setting, ada).unwrap(), Some(Role::Owner));
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+use crate::db::error::DbError;
+use crate::db::base::storage::{
+    Document,
+    Storage,
+    Transaction
+};
+
+type Key = (String, i32, i32);
+
+/// Storage kept in memory and lost when dropped, for tests and throwaway
+/// servers.
+#[derive(Default)]
+pub struct MemoryStorage(BTreeMap<Key, Document>);
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        MemoryStorage::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn transaction(&mut self) -> Result<Box<dyn Transaction + '_>, DbError> {
+        Ok(Box::new(MemoryTransaction {
+            documents: &mut self.0,
+            changes: BTreeMap::new(),
+        }))
+    }
+}
+
+/// Changes are kept aside, `None` for deletions, and only applied to the
+/// documents on commit.
+struct MemoryTransaction<'a> {
+    documents: &'a mut BTreeMap<Key, Document>,
+    changes: BTreeMap<Key, Option<Document>>,
+}
+
+impl Transaction for MemoryTransaction<'_> {
+    fn get(&self, collection: &str, scope: i32, id: i32) -> Result<Option<Document>, DbError> {
+        let key = (collection.to_string(), scope, id);
+        Ok(match self.changes.get(&key) {
+            Some(change) => change.clone(),
+            None => self.documents.get(&key).cloned(),
+        })
+    }
+    fn list(&self, collection: &str, scope: Option<i32>) -> Result<Vec<(i32, i32, Document)>, DbError> {
+        let (from, to) = match scope {
+            Some(scope) => ((collection.to_string(), scope, i32::MIN), (collection.to_string(), scope, i32::MAX)),
+            None => ((collection.to_string(), i32::MIN, i32::MIN), (collection.to_string(), i32::MAX, i32::MAX)),
+        };
+        let mut merged = self.documents.range(from.clone()..=to.clone())
+            .map(|(key, document)| (key.clone(), Some(document.clone())))
+            .collect::<BTreeMap<_, _>>();
+        merged.extend(self.changes.range(from..=to).map(|(key, change)| (key.clone(), change.clone())));
+        Ok(merged.into_iter()
+            .filter_map(|((_, scope, id), document)| Some((scope, id, document?)))
+            .collect())
+    }
+    fn find(&self, collection: &str, lookup: &str) -> Result<Option<(i32, i32, Document)>, DbError> {
+        Ok(self.list(collection, None)?
+            .into_iter()
+            .find(|(_, _, document)| document.lookup.as_deref() == Some(lookup)))
+    }
+    fn put(&mut self, collection: &str, scope: i32, id: i32, document: Document) -> Result<(), DbError> {
+        self.changes.insert((collection.to_string(), scope, id), Some(document));
+        Ok(())
+    }
+    fn delete(&mut self, collection: &str, scope: i32, id: i32) -> Result<(), DbError> {
+        self.changes.insert((collection.to_string(), scope, id), None);
+        Ok(())
+    }
+    fn commit(self: Box<Self>) -> Result<(), DbError> {
+        for (key, change) in self.changes {
+            match change {
+                Some(document) => self.documents.insert(key, document),
+                None => self.documents.remove(&key),
+            };
+        }
+        Ok(())
+    }
+}
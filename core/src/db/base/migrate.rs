@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use crate::db::models::{
+    ApiToken,
+    OrganizationData,
+    Session,
+    ShareLink,
+    TableDefinition,
+    UserData
+};
+use crate::db::base::{
+    Store,
+    SettingData,
+    API_TOKENS,
+    CREDENTIALS,
+    ORGANIZATIONS,
+    SESSIONS,
+    SETTINGS,
+    SHARES,
+    TABLES,
+    USERS,
+};
+
+/// A setting as `graph.yaml` kept it, with its table definitions and share
+/// links.
+#[derive(Deserialize)]
+struct LegacySetting {
+    #[serde(flatten)]
+    setting: SettingData,
+    #[serde(default)]
+    tables: HashMap<i32, TableDefinition>,
+    #[serde(default)]
+    shares: HashMap<i32, ShareLink>,
+    /// Token hashes of the share links by their id
+    #[serde(default)]
+    share_hashes: HashMap<i32, String>,
+}
+
+/// Contents of `graph.yaml`. API tokens and share links whose hash is missing
+/// cannot be used and are left out.
+#[derive(Deserialize)]
+struct LegacyStore {
+    #[serde(default)]
+    settings: HashMap<i32, LegacySetting>,
+    #[serde(default)]
+    users: Vec<UserData>,
+    #[serde(default)]
+    credentials: HashMap<i32, String>,
+    #[serde(default)]
+    sessions: HashMap<String, Session>,
+    #[serde(default)]
+    organizations: HashMap<i32, OrganizationData>,
+    #[serde(default)]
+    api_tokens: HashMap<i32, ApiToken>,
+    /// Ids of the API tokens by the hash of their token
+    #[serde(default)]
+    api_token_hashes: HashMap<String, i32>,
+}
+
+/// Imports the store kept in `path` before the move to SQLite, in a single
+/// transaction, then renames the file so it is only imported once. Nothing is
+/// imported into a store that already has users or settings.
+pub fn import_yaml(store: &Store, path: &str) -> Result<(), String> {
+    let Ok(file) = std::fs::File::open(path) else {
+        return Ok(());
+    };
+    let legacy: LegacyStore = serde_yaml::from_reader(file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let imported = store.write(|tx| {
+        if !tx.users()?.is_empty() || !tx.all::<SettingData>(SETTINGS)?.is_empty() {
+            return Ok(false);
+        }
+        for user in &legacy.users {
+            tx.put(USERS, user.id, user)?;
+        }
+        for (user_id, hash) in &legacy.credentials {
+            tx.put(CREDENTIALS, *user_id, hash)?;
+        }
+        for (key, session) in &legacy.sessions {
+            let next_id = tx.next_id(SESSIONS)?;
+            tx.put_scoped(SESSIONS, 0, next_id, Some(key.clone()), session)?;
+        }
+        for (id, organization) in &legacy.organizations {
+            tx.put(ORGANIZATIONS, *id, organization)?;
+        }
+        for (hash, id) in &legacy.api_token_hashes {
+            if let Some(api_token) = legacy.api_tokens.get(id) {
+                tx.put_scoped(API_TOKENS, 0, *id, Some(hash.clone()), api_token)?;
+            }
+        }
+        for (id, legacy_setting) in &legacy.settings {
+            tx.put(SETTINGS, *id, &legacy_setting.setting)?;
+            for (table_id, table) in &legacy_setting.tables {
+                tx.put_scoped(TABLES, *id, *table_id, None, table)?;
+            }
+            for (share_id, hash) in &legacy_setting.share_hashes {
+                if let Some(share) = legacy_setting.shares.get(share_id) {
+                    tx.put_scoped(SHARES, *id, *share_id, Some(hash.clone()), share)?;
+                }
+            }
+        }
+        Ok(true)
+    }).map_err(|e| format!("Failed to import {}: {}", path, e))?;
+    if !imported {
+        log::warn!("{} was not imported, the store already has data", path);
+        return Ok(());
+    }
+    let migrated = format!("{}.migrated", path);
+    std::fs::rename(path, &migrated).map_err(|e| e.to_string())?;
+    log::info!("Imported {} into the store, the file was renamed to {}", path, migrated);
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use crate::db::error::DbError;
+use crate::db::models::{
+    OrganizationData,
+    Role
+};
+use crate::db::base::{
+    Store,
+};
+use crate::db::base::organizations::{
+    get_organization,
+    invite,
+    accept_invitation,
+    decline_invitation,
+    set_member,
+    remove_member
+};
+
+pub struct OrganizationManager<'a> {
+    pub store: &'a Store,
+    pub organization_id: i32,
+}
+
+impl OrganizationManager<'_> {
+    pub fn get(&self) -> Result<Option<OrganizationData>, DbError> {
+        get_organization(self.store, self.organization_id)
+    }
+    pub fn invite(&self, username: &str, role: Role) -> Result<i32, DbError> {
+        invite(self.store, self.organization_id, username, role)
+    }
+    pub fn accept_invitation(&self, user_id: i32) -> Result<Role, DbError> {
+        accept_invitation(self.store, self.organization_id, user_id)
+    }
+    pub fn decline_invitation(&self, user_id: i32) -> Result<Option<Role>, DbError> {
+        decline_invitation(self.store, self.organization_id, user_id)
+    }
+    pub fn set_member(&self, user_id: i32, role: Role) -> Result<(), DbError> {
+        set_member(self.store, self.organization_id, user_id, role)
+    }
+    pub fn remove_member(&self, user_id: i32) -> Result<Role, DbError> {
+        remove_member(self.store, self.organization_id, user_id)
+    }
+}
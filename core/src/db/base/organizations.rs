@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use crate::db::error::DbError;
+use crate::db::models::{
+    OrganizationData,
+    Role
+};
+use crate::db::base::{
+    Store,
+    ORGANIZATIONS,
+};
+
+/// An organization keeps at least one owner, so its members stay manageable.
+fn keeps_owner(organization: &OrganizationData) -> bool {
+    organization.members.values().any(|r| *r == Role::Owner)
+}
+
+pub fn add_organization(store: &Store, user_id: i32, name: String) -> Result<i32, DbError> {
+    store.write(|tx| {
+        let next_id = tx.next_id(ORGANIZATIONS)?;
+        tx.put(ORGANIZATIONS, next_id, &OrganizationData {
+            name,
+            members: HashMap::from([(user_id, Role::Owner)]),
+            invitations: HashMap::new(),
+        })?;
+        Ok(next_id)
+    })
+}
+
+pub fn get_organizations(store: &Store, user_id: i32) -> Result<Vec<(i32, OrganizationData)>, DbError> {
+    Ok(store.read(|tx| tx.all::<OrganizationData>(ORGANIZATIONS))?
+        .into_iter()
+        .filter(|(_, o)| o.members.contains_key(&user_id))
+        .collect())
+}
+
+pub fn get_invitations(store: &Store, user_id: i32) -> Result<Vec<(i32, OrganizationData)>, DbError> {
+    Ok(store.read(|tx| tx.all::<OrganizationData>(ORGANIZATIONS))?
+        .into_iter()
+        .filter(|(_, o)| o.invitations.contains_key(&user_id))
+        .collect())
+}
+
+pub fn get_organization(store: &Store, id: i32) -> Result<Option<OrganizationData>, DbError> {
+    store.read(|tx| tx.get(ORGANIZATIONS, id))
+}
+
+/// Invites a user by name, returning their id.
+pub fn invite(store: &Store, id: i32, username: &str, role: Role) -> Result<i32, DbError> {
+    store.write(|tx| {
+        let user_id = tx.users()?.into_iter()
+            .find(|u| u.name == username)
+            .map(|u| u.id)
+            .ok_or_else(|| DbError::not_found("User not found"))?;
+        let mut organization: OrganizationData = tx.get(ORGANIZATIONS, id)?.ok_or_else(|| DbError::not_found("Organization not found"))?;
+        if organization.members.contains_key(&user_id) {
+            return Err(DbError::conflict("User is already a member"));
+        }
+        organization.invitations.insert(user_id, role);
+        tx.put(ORGANIZATIONS, id, &organization)?;
+        Ok(user_id)
+    })
+}
+
+pub fn accept_invitation(store: &Store, id: i32, user_id: i32) -> Result<Role, DbError> {
+    store.write(|tx| {
+        let mut organization: OrganizationData = tx.get(ORGANIZATIONS, id)?.ok_or_else(|| DbError::not_found("Organization not found"))?;
+        let role = organization.invitations.remove(&user_id).ok_or_else(|| DbError::not_found("Invitation not found"))?;
+        organization.members.insert(user_id, role);
+        tx.put(ORGANIZATIONS, id, &organization)?;
+        Ok(role)
+    })
+}
+
+pub fn decline_invitation(store: &Store, id: i32, user_id: i32) -> Result<Option<Role>, DbError> {
+    store.write(|tx| {
+        let Some(mut organization) = tx.get::<OrganizationData>(ORGANIZATIONS, id)? else {
+            return Ok(None);
+        };
+        let Some(removed) = organization.invitations.remove(&user_id) else {
+            return Ok(None);
+        };
+        tx.put(ORGANIZATIONS, id, &organization)?;
+        Ok(Some(removed))
+    })
+}
+
+pub fn set_member(store: &Store, id: i32, user_id: i32, role: Role) -> Result<(), DbError> {
+    store.write(|tx| {
+        let mut organization: OrganizationData = tx.get(ORGANIZATIONS, id)?.ok_or_else(|| DbError::not_found("Organization not found"))?;
+        let member = organization.members.get_mut(&user_id).ok_or_else(|| DbError::not_found("Member not found"))?;
+        *member = role;
+        if !keeps_owner(&organization) {
+            return Err(DbError::conflict("An organization needs at least one owner"));
+        }
+        tx.put(ORGANIZATIONS, id, &organization)?;
+        Ok(())
+    })
+}
+
+pub fn remove_member(store: &Store, id: i32, user_id: i32) -> Result<Role, DbError> {
+    store.write(|tx| {
+        let mut organization: OrganizationData = tx.get(ORGANIZATIONS, id)?.ok_or_else(|| DbError::not_found("Organization not found"))?;
+        let role = organization.members.remove(&user_id).ok_or_else(|| DbError::not_found("Member not found"))?;
+        if !keeps_owner(&organization) {
+            return Err(DbError::conflict("An organization needs at least one owner"));
+        }
+        tx.put(ORGANIZATIONS, id, &organization)?;
+        Ok(role)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::db::events::Events;
+    use crate::db::models::Quota;
+    use crate::db::base::members::get_role;
+    use crate::db::base::settings::add_setting;
+    use super::*;
+
+    #[test]
+    fn invited_members_get_a_role_on_the_settings() {
+        let store = Store::in_memory(Arc::new(Events::default()), Quota::default());
+        let ada = store.add_user("ada".to_string(), "secret").unwrap();
+        let byron = store.add_user("byron".to_string(), "secret").unwrap();
+        let organization = add_organization(&store, ada, "Analytical".to_string()).unwrap();
+        let setting = add_setting(&store, ada, "engine".to_string(), Some(organization)).unwrap();
+
+        assert_eq!(invite(&store, organization, "byron", Role::Editor).unwrap(), byron);
+        assert_eq!(get_invitations(&store, byron).unwrap().len(), 1);
+        assert_eq!(get_role(&store, setting, byron).unwrap(), None);
+        assert!(matches!(invite(&store, organization, "ada", Role::Viewer), Err(DbError::Conflict(_))));
+        assert!(matches!(invite(&store, organization, "charles", Role::Viewer), Err(DbError::NotFound(_))));
+
+        assert_eq!(accept_invitation(&store, organization, byron).unwrap(), Role::Editor);
+        assert!(get_invitations(&store, byron).unwrap().is_empty());
+        assert_eq!(get_role(&store, setting, byron).unwrap(), Some(Role::Editor));
+        assert!(matches!(accept_invitation(&store, organization, byron), Err(DbError::NotFound(_))));
+
+        assert!(matches!(set_member(&store, organization, ada, Role::Editor), Err(DbError::Conflict(_))));
+        set_member(&store, organization, byron, Role::Owner).unwrap();
+        assert_eq!(remove_member(&store, organization, ada).unwrap(), Role::Owner);
+        // The setting's creator keeps owning it
+        assert_eq!(get_role(&store, setting, ada).unwrap(), Some(Role::Owner));
+    }
+
+    #[test]
+    fn declined_invitations_give_no_role() {
+        let store = Store::in_memory(Arc::new(Events::default()), Quota::default());
+        let ada = store.add_user("ada".to_string(), "secret").unwrap();
+        let byron = store.add_user("byron".to_string(), "secret").unwrap();
+        let organization = add_organization(&store, ada, "Analytical".to_string()).unwrap();
+        invite(&store, organization, "byron", Role::Viewer).unwrap();
+        assert_eq!(decline_invitation(&store, organization, byron).unwrap(), Some(Role::Viewer));
+        assert_eq!(decline_invitation(&store, organization, byron).unwrap(), None);
+        assert!(get_organizations(&store, byron).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use crate::db::error::DbError;
+use crate::db::models::{
+    Delivery,
+    Role,
+    ShareLink,
+    TableDefinition,
+    Webhook
+};
+use crate::db::events::Change;
+use crate::db::base::{
+    Store,
+};
+pub struct SettingManager<'a> {
+    pub store: &'a Store,
+    pub setting: i32,
+}
+use crate::db::base::tables::{
+    set_table,
+    get_table,
+    get_tables,
+    add_table,
+    remove_table
+};
+use crate::db::base::members::{
+    get_role,
+    get_members,
+    set_member,
+    remove_member
+};
+use crate::db::base::shares::{
+    add_share,
+    get_shares,
+    remove_share
+};
+use crate::db::base::webhooks::{
+    add_webhook,
+    get_webhooks,
+    remove_webhook,
+    add_delivery,
+    get_deliveries
+};
+impl SettingManager<'_> {
+    pub fn set_table(&self, id: i32, table: TableDefinition) -> Result<(), DbError> {
+        set_table(self.store, self.setting, id, table.clone())?;
+        self.store.events().publish(self.setting, Change::TableUpdated { table_id: id, table });
+        Ok(())
+    }
+    pub fn get_table(&self, id: i32) -> Result<Option<TableDefinition>, DbError> {
+        get_table(self.store, self.setting, id)
+    }
+    pub fn get_tables(&self) -> Result<Option<HashMap<i32, TableDefinition>>, DbError> {
+        get_tables(self.store, self.setting)
+    }
+    /// Adds the table unless the setting's table quota is reached.
+    pub fn add_table(&self, table: TableDefinition) -> Result<i32, DbError> {
+        let id = add_table(self.store, self.setting, table.clone())?;
+        self.store.events().publish(self.setting, Change::TableCreated { table_id: id, table });
+        Ok(id)
+    }
+    pub fn remove_table(&self, id: i32) -> Result<Option<TableDefinition>, DbError> {
+        let removed = remove_table(self.store, self.setting, id)?;
+        if removed.is_some() {
+            self.store.events().publish(self.setting, Change::TableDeleted { table_id: id });
+        }
+        Ok(removed)
+    }
+    pub fn add_webhook(&self, webhook: Webhook) -> Result<Option<i32>, DbError> {
+        add_webhook(self.store, self.setting, webhook)
+    }
+    pub fn get_webhooks(&self) -> Result<Option<HashMap<i32, Webhook>>, DbError> {
+        get_webhooks(self.store, self.setting)
+    }
+    pub fn remove_webhook(&self, id: i32) -> Result<Option<Webhook>, DbError> {
+        remove_webhook(self.store, self.setting, id)
+    }
+    pub fn add_delivery(&self, delivery: Delivery) -> Result<Option<()>, DbError> {
+        add_delivery(self.store, self.setting, delivery)
+    }
+    pub fn get_deliveries(&self, webhook_id: i32) -> Result<Option<Vec<Delivery>>, DbError> {
+        get_deliveries(self.store, self.setting, webhook_id)
+    }
+    /// Role of the user on the setting, directly or through its organization.
+    pub fn get_role(&self, user_id: i32) -> Result<Option<Role>, DbError> {
+        get_role(self.store, self.setting, user_id)
+    }
+    pub fn get_members(&self) -> Result<Option<HashMap<i32, Role>>, DbError> {
+        get_members(self.store, self.setting)
+    }
+    pub fn set_member(&self, user_id: i32, role: Role) -> Result<(), DbError> {
+        set_member(self.store, self.setting, user_id, role)
+    }
+    pub fn remove_member(&self, user_id: i32) -> Result<Option<Role>, DbError> {
+        remove_member(self.store, self.setting, user_id)
+    }
+    pub fn add_share(&self, table_id: Option<i32>, expires_at: Option<u64>) -> Result<(i32, String, ShareLink), DbError> {
+        add_share(self.store, self.setting, table_id, expires_at)
+    }
+    pub fn get_shares(&self) -> Result<Option<HashMap<i32, ShareLink>>, DbError> {
+        get_shares(self.store, self.setting)
+    }
+    pub fn remove_share(&self, id: i32) -> Result<Option<ShareLink>, DbError> {
+        remove_share(self.store, self.setting, id)
+    }
+}
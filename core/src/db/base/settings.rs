@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use crate::db::error::DbError;
+use crate::db::models::{
+    OrganizationData,
+    Role
+};
+use crate::db::base::{
+    Store,
+    SettingData,
+    ORGANIZATIONS,
+    SETTINGS,
+    SHARES,
+    TABLES,
+};
+
+pub fn add_setting(store: &Store, user_id: i32, name: String, organization_id: Option<i32>) -> Result<i32, DbError> {
+    store.write(|tx| {
+        if let Some(organization_id) = organization_id {
+            let organization: OrganizationData = tx.get(ORGANIZATIONS, organization_id)?.ok_or_else(|| DbError::not_found("Organization not found"))?;
+            if organization.members.get(&user_id) < Some(&Role::Editor) {
+                return Err(DbError::forbidden("Only editors of the organization can add settings to it"));
+            }
+        }
+        let next_id = tx.next_id(SETTINGS)?;
+        tx.put(SETTINGS, next_id, &SettingData {
+            name,
+            user_id,
+            webhooks: HashMap::new(),
+            last_webhook_id: 0,
+            deliveries: Vec::new(),
+            members: HashMap::new(),
+            organization_id,
+        })?;
+        Ok(next_id)
+    })
+}
+pub fn get_settings(store: &Store, user_id: i32) -> Result<Vec<(i32, SettingData, Role)>, DbError> {
+    store.read(|tx| {
+        let mut settings = Vec::new();
+        for (id, setting) in tx.all::<SettingData>(SETTINGS)? {
+            if let Some(role) = tx.role(id, user_id)? {
+                settings.push((id, setting, role));
+            }
+        }
+        Ok(settings)
+    })
+}
+/// Every setting, whoever owns it.
+pub fn get_all_settings(store: &Store) -> Result<Vec<(i32, SettingData)>, DbError> {
+    store.read(|tx| tx.all(SETTINGS))
+}
+pub fn rename_setting(store: &Store, setting_id: i32, name: String) -> Result<Option<()>, DbError> {
+    store.write(|tx| {
+        let Some(mut setting) = tx.get::<SettingData>(SETTINGS, setting_id)? else {
+            return Ok(None);
+        };
+        setting.name = name;
+        tx.put(SETTINGS, setting_id, &setting)?;
+        Ok(Some(()))
+    })
+}
+/// Removes the setting with its tables and share links. Its graph is left to
+/// the caller.
+pub fn remove_setting(store: &Store, setting_id: i32) -> Result<Option<SettingData>, DbError> {
+    store.write(|tx| {
+        let Some(setting) = tx.delete(SETTINGS, setting_id)? else {
+            return Ok(None);
+        };
+        for collection in [TABLES, SHARES] {
+            for (_, id, _) in tx.0.list(collection, Some(setting_id))? {
+                tx.0.delete(collection, setting_id, id)?;
+            }
+        }
+        Ok(Some(setting))
+    })
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use crate::db::error::DbError;
+use crate::db::models::{
+    ShareLink,
+    TableDefinition
+};
+use crate::db::base::{
+    parse,
+    Store,
+    SettingData,
+    SETTINGS,
+    SHARES,
+    TABLES,
+};
+use crate::db::base::auth::{
+    now,
+    random_bytes,
+    token_key
+};
+
+/// Creates a share link, returning its id and token. Only the token's hash
+/// is kept.
+pub fn add_share(
+    store: &Store,
+    setting_id: i32,
+    table_id: Option<i32>,
+    expires_at: Option<u64>
+) -> Result<(i32, String, ShareLink), DbError> {
+    let token = hex::encode(random_bytes::<32>()?);
+    store.write(|tx| {
+        tx.get::<SettingData>(SETTINGS, setting_id)?.ok_or_else(|| DbError::not_found("Setting not found"))?;
+        if let Some(id) = table_id {
+            tx.get_scoped::<TableDefinition>(TABLES, setting_id, id)?.ok_or_else(|| DbError::not_found("Table not found"))?;
+        }
+        let share = ShareLink {
+            table_id,
+            created_at: now(),
+            expires_at,
+            token_hash: token_key(&token),
+        };
+        let next_id = tx.next_scoped_id(SHARES, setting_id)?;
+        tx.put_scoped(SHARES, setting_id, next_id, Some(share.token_hash.clone()), &share)?;
+        Ok((next_id, token, share))
+    })
+}
+
+/// Share links of the setting. Their token hash is kept as their lookup key
+/// rather than with them.
+pub fn get_shares(store: &Store, setting_id: i32) -> Result<Option<HashMap<i32, ShareLink>>, DbError> {
+    store.read(|tx| {
+        if tx.get::<SettingData>(SETTINGS, setting_id)?.is_none() {
+            return Ok(None);
+        }
+        let shares = tx.0.list(SHARES, Some(setting_id))?
+            .into_iter()
+            .map(|(_, id, d)| Ok((id, ShareLink {
+                token_hash: d.lookup.clone().unwrap_or_default(),
+                ..parse(&d)?
+            })))
+            .collect::<Result<_, DbError>>()?;
+        Ok(Some(shares))
+    })
+}
+
+pub fn remove_share(store: &Store, setting_id: i32, id: i32) -> Result<Option<ShareLink>, DbError> {
+    store.write(|tx| tx.delete_scoped(SHARES, setting_id, id))
+}
+
+pub fn get_share(store: &Store, token: &str) -> Result<Option<(i32, ShareLink)>, DbError> {
+    let token_hash = token_key(token);
+    let Some((setting_id, _, share)) = store.read(|tx| tx.find::<ShareLink>(SHARES, &token_hash))? else {
+        return Ok(None);
+    };
+    let share = ShareLink { token_hash, ..share };
+    Ok(share.expires_at.is_none_or(|e| e > now()).then_some((setting_id, share)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::db::events::Events;
+    use crate::db::models::Quota;
+    use crate::db::base::settings::add_setting;
+    use super::*;
+
+    #[test]
+    fn links_end_when_expired_or_revoked() {
+        let store = Store::in_memory(Arc::new(Events::default()), Quota::default());
+        let setting = add_setting(&store, 1, "family".to_string(), None).unwrap();
+        let (_, lasting, _) = add_share(&store, setting, None, Some(now() + 60)).unwrap();
+        let (_, expired, _) = add_share(&store, setting, None, Some(now() - 1)).unwrap();
+        let (revoked_id, revoked, _) = add_share(&store, setting, None, None).unwrap();
+        assert_eq!(get_share(&store, &lasting).unwrap().map(|(s, _)| s), Some(setting));
+        assert!(get_share(&store, &expired).unwrap().is_none());
+        assert!(get_share(&store, &revoked).unwrap().is_some());
+        assert!(remove_share(&store, setting, revoked_id).unwrap().is_some());
+        assert!(get_share(&store, &revoked).unwrap().is_none());
+        assert_eq!(get_shares(&store, setting).unwrap().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn table_links_need_the_table() {
+        let store = Store::in_memory(Arc::new(Events::default()), Quota::default());
+        let setting = add_setting(&store, 1, "family".to_string(), None).unwrap();
+        assert!(matches!(add_share(&store, setting, Some(1), None), Err(DbError::NotFound(_))));
+        assert!(matches!(add_share(&store, setting + 1, None, None), Err(DbError::NotFound(_))));
+    }
+}
@@ -0,0 +1,92 @@
+use rusqlite::{
+    params,
+    Connection,
+    OptionalExtension
+};
+use crate::db::error::DbError;
+use crate::db::base::storage::{
+    Document,
+    Storage,
+    Transaction
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS documents (
+        collection TEXT NOT NULL,
+        scope INTEGER NOT NULL,
+        id INTEGER NOT NULL,
+        lookup TEXT,
+        data TEXT NOT NULL,
+        PRIMARY KEY (collection, scope, id)
+    );
+    CREATE UNIQUE INDEX IF NOT EXISTS documents_lookup ON documents (collection, lookup);
+";
+
+/// Storage in an SQLite database file.
+pub struct SqliteStorage(Connection);
+
+impl SqliteStorage {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        conn.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+        Ok(SqliteStorage(conn))
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn transaction(&mut self) -> Result<Box<dyn Transaction + '_>, DbError> {
+        Ok(Box::new(SqliteTransaction(self.0.transaction()?)))
+    }
+}
+
+struct SqliteTransaction<'a>(rusqlite::Transaction<'a>);
+
+fn document(row: &rusqlite::Row, first: usize) -> rusqlite::Result<Document> {
+    Ok(Document {
+        lookup: row.get(first)?,
+        data: row.get(first + 1)?,
+    })
+}
+
+impl Transaction for SqliteTransaction<'_> {
+    fn get(&self, collection: &str, scope: i32, id: i32) -> Result<Option<Document>, DbError> {
+        Ok(self.0.query_row(
+            "SELECT lookup, data FROM documents WHERE collection = ?1 AND scope = ?2 AND id = ?3",
+            params![collection, scope, id],
+            |row| document(row, 0)
+        ).optional()?)
+    }
+    fn list(&self, collection: &str, scope: Option<i32>) -> Result<Vec<(i32, i32, Document)>, DbError> {
+        let mut statement = self.0.prepare(
+            "SELECT scope, id, lookup, data FROM documents
+             WHERE collection = ?1 AND (?2 IS NULL OR scope = ?2)
+             ORDER BY scope, id"
+        )?;
+        let rows = statement.query_map(params![collection, scope], |row| Ok((row.get(0)?, row.get(1)?, document(row, 2)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+    fn find(&self, collection: &str, lookup: &str) -> Result<Option<(i32, i32, Document)>, DbError> {
+        Ok(self.0.query_row(
+            "SELECT scope, id, lookup, data FROM documents WHERE collection = ?1 AND lookup = ?2",
+            params![collection, lookup],
+            |row| Ok((row.get(0)?, row.get(1)?, document(row, 2)?))
+        ).optional()?)
+    }
+    fn put(&mut self, collection: &str, scope: i32, id: i32, document: Document) -> Result<(), DbError> {
+        self.0.execute(
+            "INSERT OR REPLACE INTO documents (collection, scope, id, lookup, data) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![collection, scope, id, document.lookup, document.data]
+        )?;
+        Ok(())
+    }
+    fn delete(&mut self, collection: &str, scope: i32, id: i32) -> Result<(), DbError> {
+        self.0.execute(
+            "DELETE FROM documents WHERE collection = ?1 AND scope = ?2 AND id = ?3",
+            params![collection, scope, id]
+        )?;
+        Ok(())
+    }
+    fn commit(self: Box<Self>) -> Result<(), DbError> {
+        Ok(self.0.commit()?)
+    }
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use crate::db::error::DbError;
+
+/// A stored document, serialized as JSON.
+#[derive(Clone)]
+pub struct Document {
+    /// Unique key the document can be found by within its collection, such
+    /// as the hash of a token
+    pub lookup: Option<String>,
+    pub data: String,
+}
+
+/// A document with the collection, scope and id it is stored under, as
+/// written to backups.
+#[derive(Deserialize, Serialize)]
+pub struct StoredDocument {
+    pub collection: String,
+    pub scope: i32,
+    pub id: i32,
+    pub lookup: Option<String>,
+    pub data: String,
+}
+
+/// Where the store keeps its documents. Documents are grouped in collections
+/// and identified by an id within a scope, the setting they belong to or 0
+/// for the documents that belong to none.
+pub trait Storage: Send {
+    /// Starts a transaction, rolled back unless it is committed.
+    fn transaction(&mut self) -> Result<Box<dyn Transaction + '_>, DbError>;
+}
+
+/// Operations of a transaction, failing with a backend error when the
+/// storage does.
+pub trait Transaction {
+    fn get(&self, collection: &str, scope: i32, id: i32) -> Result<Option<Document>, DbError>;
+    /// Documents of the collection in the scope, every scope when `None`,
+    /// ordered by scope and id.
+    fn list(&self, collection: &str, scope: Option<i32>) -> Result<Vec<(i32, i32, Document)>, DbError>;
+    /// Document of the collection with the lookup key, with its scope and id.
+    fn find(&self, collection: &str, lookup: &str) -> Result<Option<(i32, i32, Document)>, DbError>;
+    fn put(&mut self, collection: &str, scope: i32, id: i32, document: Document) -> Result<(), DbError>;
+    fn delete(&mut self, collection: &str, scope: i32, id: i32) -> Result<(), DbError>;
+    fn commit(self: Box<Self>) -> Result<(), DbError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::base::{MemoryStorage, SqliteStorage};
+    use super::*;
+
+    fn document(data: &str, lookup: Option<&str>) -> Document {
+        Document { lookup: lookup.map(str::to_string), data: data.to_string() }
+    }
+
+    fn stores_documents(mut storage: Box<dyn Storage>) {
+        let mut tx = storage.transaction().unwrap();
+        tx.put("tables", 2, 1, document("b1", None)).unwrap();
+        tx.put("tables", 1, 2, document("a2", None)).unwrap();
+        tx.put("tables", 1, 1, document("a1", None)).unwrap();
+        tx.put("tokens", 0, 1, document("t1", Some("hash"))).unwrap();
+        tx.commit().unwrap();
+
+        let mut tx = storage.transaction().unwrap();
+        assert_eq!(tx.get("tables", 1, 2).unwrap().map(|d| d.data), Some("a2".to_string()));
+        assert!(tx.get("tables", 3, 2).unwrap().is_none());
+        let ids = |scope| tx.list("tables", scope).unwrap().into_iter().map(|(s, i, d)| (s, i, d.data)).collect::<Vec<_>>();
+        assert_eq!(ids(None), [(1, 1, "a1".to_string()), (1, 2, "a2".to_string()), (2, 1, "b1".to_string())]);
+        assert_eq!(ids(Some(2)), [(2, 1, "b1".to_string())]);
+        assert_eq!(tx.find("tokens", "hash").unwrap().map(|(s, i, _)| (s, i)), Some((0, 1)));
+        assert!(tx.find("tokens", "other").unwrap().is_none());
+        tx.put("tables", 1, 1, document("a1'", None)).unwrap();
+        tx.delete("tables", 2, 1).unwrap();
+        drop(tx);
+
+        let mut tx = storage.transaction().unwrap();
+        assert_eq!(tx.list("tables", None).unwrap().len(), 3, "uncommitted changes are rolled back");
+        tx.put("tables", 1, 1, document("a1'", None)).unwrap();
+        tx.delete("tables", 2, 1).unwrap();
+        tx.commit().unwrap();
+        let tx = storage.transaction().unwrap();
+        assert_eq!(tx.get("tables", 1, 1).unwrap().map(|d| d.data), Some("a1'".to_string()));
+        assert!(tx.get("tables", 2, 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn memory_stores_documents() {
+        stores_documents(Box::new(MemoryStorage::new()));
+    }
+
+    #[test]
+    fn sqlite_stores_documents() {
+        stores_documents(Box::new(SqliteStorage::open(":memory:").unwrap()));
+    }
+
+    #[test]
+    fn sqlite_keeps_documents_across_restarts() {
+        let path = std::env::temp_dir().join(format!("grpg-storage-test-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut storage = SqliteStorage::open(path.to_str().unwrap()).unwrap();
+        let mut tx = storage.transaction().unwrap();
+        tx.put("users", 0, 1, document("ada", Some("ada"))).unwrap();
+        tx.commit().unwrap();
+        drop(storage);
+
+        let mut storage = SqliteStorage::open(path.to_str().unwrap()).unwrap();
+        let tx = storage.transaction().unwrap();
+        assert_eq!(tx.find("users", "ada").unwrap().map(|(_, _, d)| d.data), Some("ada".to_string()));
+        drop(tx);
+        drop(storage);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use crate::db::error::DbError;
+use crate::db::models::{
+    Quota,
+    TableDefinition
+};
+use crate::db::base::{
+    Store,
+    SettingData,
+    SETTINGS,
+    TABLES,
+};
+
+pub fn set_table(store: &Store, setting_id: i32, id: i32, table: TableDefinition) -> Result<(), DbError> {
+    store.write(|tx| {
+        tx.get::<SettingData>(SETTINGS, setting_id)?.ok_or_else(|| DbError::not_found("Setting not found"))?;
+        if tx.get_scoped::<TableDefinition>(TABLES, setting_id, id)?.is_none() {
+            let count = tx.all_scoped::<TableDefinition>(TABLES, setting_id)?.len();
+            Quota::check(store.quota().max_tables, "tables", count as u64)?;
+        }
+        tx.put_scoped(TABLES, setting_id, id, None, &table)
+    })
+}
+pub fn get_table(store: &Store, setting_id: i32, id: i32) -> Result<Option<TableDefinition>, DbError> {
+    store.read(|tx| tx.get_scoped(TABLES, setting_id, id))
+}
+
+pub fn get_tables(store: &Store, setting_id: i32) -> Result<Option<HashMap<i32, TableDefinition>>, DbError> {
+    store.read(|tx| {
+        if tx.get::<SettingData>(SETTINGS, setting_id)?.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(tx.all_scoped(TABLES, setting_id)?.into_iter().collect()))
+    })
+}
+
+pub fn add_table(store: &Store, setting_id: i32, table: TableDefinition) -> Result<i32, DbError> {
+    store.write(|tx| {
+        tx.get::<SettingData>(SETTINGS, setting_id)?.ok_or_else(|| DbError::not_found("Setting not found"))?;
+        let count = tx.all_scoped::<TableDefinition>(TABLES, setting_id)?.len();
+        Quota::check(store.quota().max_tables, "tables", count as u64)?;
+        let next_id = tx.next_scoped_id(TABLES, setting_id)?;
+        tx.put_scoped(TABLES, setting_id, next_id, None, &table)?;
+        Ok(next_id)
+    })
+}
+pub fn remove_table(store: &Store, setting_id: i32, id: i32) -> Result<Option<TableDefinition>, DbError> {
+    store.write(|tx| tx.delete_scoped(TABLES, setting_id, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::db::events::Events;
+    use crate::db::models::{Filter, QuotaExceeded};
+    use super::*;
+
+    fn table() -> TableDefinition {
+        TableDefinition { label: "People".to_string(), filter: Filter { node_id: None, predicate: None, direction: None }, columns: Vec::new() }
+    }
+
+    #[test]
+    fn new_tables_count_against_the_quota() {
+        let quota = Quota { max_nodes: None, max_triples: None, max_tables: Some(1) };
+        let store = Store::in_memory(Arc::new(Events::default()), quota);
+        let setting = crate::db::base::settings::add_setting(&store, 1, "family".to_string(), None).unwrap();
+        let id = add_table(&store, setting, table()).unwrap();
+        set_table(&store, setting, id, table()).unwrap();
+        let exceeded = set_table(&store, setting, id + 1, table());
+        assert!(matches!(exceeded, Err(DbError::QuotaExceeded(QuotaExceeded { resource: "tables", limit: 1 }))));
+        assert!(matches!(set_table(&store, setting + 1, id, table()), Err(DbError::NotFound(_))));
+    }
+}
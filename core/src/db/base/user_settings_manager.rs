@@ -0,0 +1,25 @@
+use crate::db::base::{
+    Store,
+};
+use crate::db::base::SettingData;
+use crate::db::error::DbError;
+use crate::db::models::Role;
+use crate::db::base::settings::{
+    add_setting,
+    get_settings
+};
+
+pub struct UserSettingsManager<'a> {
+    pub store: &'a Store,
+    pub user_id: i32,
+}
+
+impl UserSettingsManager<'_> {
+    pub fn add_setting(&self, name: String, organization_id: Option<i32>) -> Result<i32, DbError> {
+        add_setting(self.store, self.user_id, name, organization_id)
+    }
+    /// Settings the user has a role on, with that role.
+    pub fn get_settings(&self) -> Result<Vec<(i32, SettingData, Role)>, DbError> {
+        get_settings(self.store, self.user_id)
+    }
+}
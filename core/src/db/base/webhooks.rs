@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use crate::db::error::DbError;
+use crate::db::models::{
+    Delivery,
+    Webhook
+};
+use crate::db::base::{
+    Store,
+    SettingData,
+    SETTINGS,
+};
+
+/// Number of delivery attempts kept per setting, the oldest are dropped first.
+const MAX_DELIVERIES: usize = 200;
+
+pub fn add_webhook(store: &Store, setting_id: i32, webhook: Webhook) -> Result<Option<i32>, DbError> {
+    store.write(|tx| {
+        let Some(mut setting) = tx.get::<SettingData>(SETTINGS, setting_id)? else {
+            return Ok(None);
+        };
+        let next_id = setting.webhooks.keys().copied().fold(setting.last_webhook_id, i32::max) + 1;
+        setting.last_webhook_id = next_id;
+        setting.webhooks.insert(next_id, webhook);
+        tx.put(SETTINGS, setting_id, &setting)?;
+        Ok(Some(next_id))
+    })
+}
+
+pub fn get_webhooks(store: &Store, setting_id: i32) -> Result<Option<HashMap<i32, Webhook>>, DbError> {
+    Ok(store.read(|tx| tx.get::<SettingData>(SETTINGS, setting_id))?.map(|x| x.webhooks))
+}
+
+pub fn remove_webhook(store: &Store, setting_id: i32, id: i32) -> Result<Option<Webhook>, DbError> {
+    store.write(|tx| {
+        let Some(mut setting) = tx.get::<SettingData>(SETTINGS, setting_id)? else {
+            return Ok(None);
+        };
+        let Some(removed) = setting.webhooks.remove(&id) else {
+            return Ok(None);
+        };
+        tx.put(SETTINGS, setting_id, &setting)?;
+        Ok(Some(removed))
+    })
+}
+
+pub fn add_delivery(store: &Store, setting_id: i32, delivery: Delivery) -> Result<Option<()>, DbError> {
+    store.write(|tx| {
+        let Some(mut setting) = tx.get::<SettingData>(SETTINGS, setting_id)? else {
+            return Ok(None);
+        };
+        setting.deliveries.push(delivery);
+        let excess = setting.deliveries.len().saturating_sub(MAX_DELIVERIES);
+        setting.deliveries.drain(..excess);
+        tx.put(SETTINGS, setting_id, &setting)?;
+        Ok(Some(()))
+    })
+}
+
+pub fn get_deliveries(store: &Store, setting_id: i32, webhook_id: i32) -> Result<Option<Vec<Delivery>>, DbError> {
+    Ok(store.read(|tx| tx.get::<SettingData>(SETTINGS, setting_id))?.map(|x| x.deliveries
+        .into_iter()
+        .filter(|d| d.webhook_id == webhook_id)
+        .collect()))
+}
@@ -0,0 +1,81 @@
+use std::fmt;
+use crate::db::models::QuotaExceeded;
+
+/// Why an operation of the store or of a graph backend failed.
+#[derive(Debug)]
+pub enum DbError {
+    /// The setting, table, user or other item it names does not exist
+    NotFound(String),
+    /// The input is malformed, such as a query that does not parse
+    Invalid(String),
+    /// The input clashes with what is stored, such as a name already taken
+    Conflict(String),
+    /// The user's role does not allow the operation
+    Forbidden(String),
+    QuotaExceeded(QuotaExceeded),
+    /// The backend cannot run the operation, such as a query on the memory backend
+    Unsupported(String),
+    /// The storage or the graph database failed
+    Backend(String),
+}
+
+impl DbError {
+    pub fn not_found(message: impl ToString) -> Self {
+        DbError::NotFound(message.to_string())
+    }
+    pub fn invalid(message: impl ToString) -> Self {
+        DbError::Invalid(message.to_string())
+    }
+    pub fn conflict(message: impl ToString) -> Self {
+        DbError::Conflict(message.to_string())
+    }
+    pub fn forbidden(message: impl ToString) -> Self {
+        DbError::Forbidden(message.to_string())
+    }
+    pub fn backend(message: impl ToString) -> Self {
+        DbError::Backend(message.to_string())
+    }
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::NotFound(message)
+            | DbError::Invalid(message)
+            | DbError::Conflict(message)
+            | DbError::Forbidden(message)
+            | DbError::Unsupported(message)
+            | DbError::Backend(message) => f.write_str(message),
+            DbError::QuotaExceeded(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl From<QuotaExceeded> for DbError {
+    fn from(e: QuotaExceeded) -> Self {
+        DbError::QuotaExceeded(e)
+    }
+}
+
+impl From<rusqlite::Error> for DbError {
+    fn from(e: rusqlite::Error) -> Self {
+        DbError::Backend(e.to_string())
+    }
+}
+
+/// Kuzu reports every failure as text, the kind of a few is told by it.
+#[cfg(feature = "kuzu")]
+impl From<kuzu::Error> for DbError {
+    fn from(e: kuzu::Error) -> Self {
+        let message = e.to_string();
+        if message.contains("Parser exception") || message.contains("Binder exception") {
+            DbError::Invalid(message)
+        } else if message.contains("duplicated primary key") || message.contains("violates") {
+            DbError::Conflict(message)
+        } else {
+            DbError::Backend(message)
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+use crate::db::models::{
+    Node,
+    Predicate,
+    TableDefinition
+};
+use crate::db::models::Triple;
+
+/// Number of events kept for subscribers that fall behind.
+const CAPACITY: usize = 1024;
+
+#[derive(Clone, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Change {
+    NodeCreated { node: Node },
+    NodeUpdated { node: Node },
+    NodeDeleted { node_id: i32 },
+    PredicateCreated { predicate: Predicate },
+    TripleCreated { triple: Triple },
+    TripleDeleted { triple: Triple },
+    TableCreated { table_id: i32, table: TableDefinition },
+    TableUpdated { table_id: i32, table: TableDefinition },
+    TableDeleted { table_id: i32 },
+}
+impl Change {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Change::NodeCreated { .. } => "node_created",
+            Change::NodeUpdated { .. } => "node_updated",
+            Change::NodeDeleted { .. } => "node_deleted",
+            Change::PredicateCreated { .. } => "predicate_created",
+            Change::TripleCreated { .. } => "triple_created",
+            Change::TripleDeleted { .. } => "triple_deleted",
+            Change::TableCreated { .. } => "table_created",
+            Change::TableUpdated { .. } => "table_updated",
+            Change::TableDeleted { .. } => "table_deleted",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+pub struct ChangeEvent {
+    /// Increasing number identifying the event within this server run
+    pub sequence: u64,
+    pub setting: i32,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+/// Broadcasts the mutations made through `GraphManager` and `SettingManager`
+/// to every subscriber, which filter them by setting.
+pub struct Events {
+    sender: broadcast::Sender<ChangeEvent>,
+    sequence: AtomicU64,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Events {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Events {
+            sender,
+            sequence: AtomicU64::new(0),
+        }
+    }
+    pub fn publish(&self, setting: i32, change: Change) {
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed) + 1;
+        // Sending only fails when nobody is listening
+        let _ = self.sender.send(ChangeEvent { sequence, setting, change });
+    }
+    pub fn subscribe(&self) -> broadcast::Receiver<ChangeEvent> {
+        self.sender.subscribe()
+    }
+}
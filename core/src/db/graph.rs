@@ -0,0 +1,161 @@
+mod backend;
+#[cfg(feature = "kuzu")]
+mod kuzu_backend;
+mod memory;
+pub use backend::{
+    GraphBackend,
+    GraphDump,
+    KuzuOptions,
+    QueryRows
+};
+#[cfg(feature = "kuzu")]
+pub use kuzu_backend::{
+    KuzuBackend,
+    SCHEMA_VERSION
+};
+pub use memory::MemoryBackend;
+use crate::db::error::DbError;
+use crate::db::models::Triple;
+use crate::db::events::{
+    Change,
+    Events
+};
+use crate::db::base::Store;
+use crate::db::models::{
+    Node,
+    Predicate,
+    Quota,
+    TableDefinition,
+    RowResponse,
+    Filter,
+    GraphDirection
+};
+pub struct GraphManager<'a> {
+    pub backend: &'a dyn GraphBackend,
+    pub setting: i32,
+    pub events: &'a Events,
+    pub store: &'a Store
+}
+
+impl<'a> GraphManager<'a> {
+    /// Creates the triple unless the setting's triple quota is reached.
+    pub fn triple_create(&self, triple: Triple) -> Result<(), DbError> {
+        let created = self.store.with_quota_lock(self.setting, |quota| {
+            if quota.max_triples.is_some() {
+                Quota::check(quota.max_triples, "triples", self.triple_count()?)?;
+            }
+            self.backend.triple_create(self.setting, triple.clone())
+        })?;
+        if created {
+            self.events.publish(self.setting, Change::TripleCreated { triple });
+        }
+        Ok(())
+    }
+    pub fn triple_count(&self) -> Result<u64, DbError> {
+        self.backend.triple_count(self.setting)
+    }
+    pub fn triple_delete(&self, triple: Triple) -> Result<(), DbError> {
+        if self.backend.triple_delete(self.setting, triple.clone())? {
+            self.events.publish(self.setting, Change::TripleDeleted { triple });
+        }
+        Ok(())
+    }
+    pub fn triple_all(&self) -> Result<Vec<Triple>, DbError> {
+        self.backend.triple_all(self.setting)
+    }
+    pub fn triple_by_nodes(&self, ids: &[i32], direction: GraphDirection) -> Result<Vec<Triple>, DbError> {
+        self.backend.triple_by_nodes(self.setting, ids, direction)
+    }
+    /// Creates the node unless the setting's node quota is reached.
+    pub fn node_create(&self, label: String) -> Result<i32, DbError> {
+        let node_id = self.store.with_quota_lock(self.setting, |quota| {
+            if quota.max_nodes.is_some() {
+                Quota::check(quota.max_nodes, "nodes", self.node_count()?)?;
+            }
+            self.backend.node_create(self.setting, label.clone())
+        })?;
+        self.events.publish(self.setting, Change::NodeCreated { node: Node { node_id, label } });
+        Ok(node_id)
+    }
+    pub fn node_count(&self) -> Result<u64, DbError> {
+        self.backend.node_count(self.setting)
+    }
+    pub fn node_all(&self,) -> Result<Vec<Node>, DbError> {
+        self.backend.node_all(self.setting)
+    }
+    pub fn node_by_ids(&self, ids: &[i32]) -> Result<Vec<Node>, DbError> {
+        self.backend.node_by_ids(self.setting, ids)
+    }
+    pub fn node_update(&self, node_id: i32, label: String) -> Result<Node, DbError> {
+        let node = self.backend.node_update(self.setting, node_id, label)?;
+        self.events.publish(self.setting, Change::NodeUpdated { node: node.clone() });
+        Ok(node)
+    }
+    pub fn node_delete(&self, node_id: i32) -> Result<(), DbError> {
+        if self.backend.node_delete(self.setting, node_id)? {
+            self.events.publish(self.setting, Change::NodeDeleted { node_id });
+        }
+        Ok(())
+    }
+    pub fn predicate_all(&self) -> Result<Vec<Predicate>, DbError> {
+        self.backend.predicate_all(self.setting)
+    }
+    pub fn predicate_create(&self, label: &str ) -> Result<Predicate, DbError> {
+        let predicate = self.backend.predicate_create(self.setting, label)?;
+        self.events.publish(self.setting, Change::PredicateCreated { predicate: predicate.clone() });
+        Ok(predicate)
+    }
+    /// Runs a read query over the graph. The query is expected to constrain
+    /// itself to the setting through the `$setting` parameter.
+    pub fn query(&self, query: &str, params: Vec<(&str, serde_json::Value)>) -> Result<Vec<Vec<serde_json::Value>>, DbError> {
+        Ok(self.backend.query(query, params, usize::MAX, 0)?.rows)
+    }
+    /// Runs a read query with a timeout, keeping at most `max_rows` rows.
+    pub fn query_limited(
+        &self,
+        query: &str,
+        params: Vec<(&str, serde_json::Value)>,
+        max_rows: usize,
+        timeout_ms: u64
+    ) -> Result<QueryRows, DbError> {
+        self.backend.query(query, params, max_rows, timeout_ms)
+    }
+    pub async fn node_filter(&self, filter: Filter) -> Result<Vec<i32>, DbError> {
+        self.backend.node_filter(self.setting, filter)
+    }
+    pub async fn table_rows(&self, table_def: TableDefinition) -> Result<Vec<RowResponse>, DbError> {
+        self.backend.table_rows(self.setting, table_def)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+
+    #[test]
+    fn managers_hold_their_setting_to_its_quota_and_publish_changes() {
+        let events = Arc::new(Events::new());
+        let store = Store::in_memory(events.clone(), Quota { max_nodes: Some(2), max_triples: None, max_tables: None });
+        let backend = MemoryBackend::new();
+        let ada = store.add_user("ada".to_string(), "secret").unwrap();
+        let setting = store.user_settings(ada).add_setting("family".to_string(), None).unwrap();
+        let graph = GraphManager { backend: &backend, setting, events: &events, store: &store };
+        let mut changes = events.subscribe();
+
+        let parent = graph.node_create("ada".to_string()).unwrap();
+        let child = graph.node_create("byron".to_string()).unwrap();
+        assert!(matches!(graph.node_create("charles".to_string()), Err(DbError::QuotaExceeded(_))));
+        let parent_of = graph.predicate_create("parent of").unwrap();
+        graph.triple_create(Triple { subject_id: parent, predicate_id: parent_of.id, object_id: child }).unwrap();
+        graph.node_delete(child).unwrap();
+        assert_eq!(graph.triple_count().unwrap(), 0);
+
+        let mut names = Vec::new();
+        while let Ok(event) = changes.try_recv() {
+            assert_eq!(event.setting, setting);
+            names.push(event.change.name());
+        }
+        assert_eq!(names, ["node_created", "node_created", "predicate_created", "triple_created", "node_deleted"]);
+    }
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use crate::db::error::DbError;
+use crate::db::models::Triple;
+use crate::db::models::{
+    Filter,
+    GraphDirection,
+    Node,
+    Predicate,
+    RowResponse,
+    TableDefinition
+};
+
+/// Settings of a Kuzu database, each left to Kuzu's default when absent.
+/// They are plain data so they can be configured whether or not the `kuzu`
+/// feature is built.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KuzuOptions {
+    /// Bytes of the buffer pool
+    pub buffer_pool_size: Option<u64>,
+    /// Threads used to run each query
+    pub max_num_threads: Option<u64>,
+    /// Opens the database without writing to it, its schema must be current
+    pub read_only: bool,
+    /// Applies the pending schema migrations when opening the database,
+    /// otherwise its schema must be current
+    pub migrate: bool,
+}
+
+impl Default for KuzuOptions {
+    fn default() -> Self {
+        KuzuOptions {
+            buffer_pool_size: None,
+            max_num_threads: None,
+            read_only: false,
+            migrate: true,
+        }
+    }
+}
+
+/// Nodes, predicates and triples of one setting with their ids, as written
+/// to backups.
+#[derive(Deserialize, Serialize)]
+pub struct GraphDump {
+    pub setting: i32,
+    pub nodes: Vec<Node>,
+    pub predicates: Vec<Predicate>,
+    pub triples: Vec<Triple>,
+}
+
+/// Rows of a read query, with its column names and whether rows past the
+/// limit were dropped.
+pub struct QueryRows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub truncated: bool,
+}
+
+/// Where the graphs of the settings are kept. Every operation is limited to
+/// the nodes, predicates and triples of one setting.
+pub trait GraphBackend: Send + Sync {
+    fn node_create(&self, setting: i32, label: String) -> Result<i32, DbError>;
+    fn node_count(&self, setting: i32) -> Result<u64, DbError>;
+    fn node_all(&self, setting: i32) -> Result<Vec<Node>, DbError>;
+    fn node_by_ids(&self, setting: i32, ids: &[i32]) -> Result<Vec<Node>, DbError>;
+    /// Relabels the node, failing when it does not exist.
+    fn node_update(&self, setting: i32, node_id: i32, label: String) -> Result<Node, DbError>;
+    /// Deletes the node with the triples it is part of, answering whether
+    /// the node existed.
+    fn node_delete(&self, setting: i32, node_id: i32) -> Result<bool, DbError>;
+    /// Ids of the nodes matching the filter, in ascending order.
+    fn node_filter(&self, setting: i32, filter: Filter) -> Result<Vec<i32>, DbError>;
+
+    fn predicate_all(&self, setting: i32) -> Result<Vec<Predicate>, DbError>;
+    fn predicate_create(&self, setting: i32, label: &str) -> Result<Predicate, DbError>;
+
+    /// Creates the triple when both of its nodes exist, answering whether it
+    /// was created.
+    fn triple_create(&self, setting: i32, triple: Triple) -> Result<bool, DbError>;
+    fn triple_count(&self, setting: i32) -> Result<u64, DbError>;
+    /// Deletes the triple, answering whether it existed.
+    fn triple_delete(&self, setting: i32, triple: Triple) -> Result<bool, DbError>;
+    fn triple_all(&self, setting: i32) -> Result<Vec<Triple>, DbError>;
+    /// Triples leaving (`Out`) or reaching (`In`) any of the given nodes.
+    fn triple_by_nodes(&self, setting: i32, ids: &[i32], direction: GraphDirection) -> Result<Vec<Triple>, DbError>;
+
+    /// Settings with any node or predicate, in ascending order.
+    fn settings(&self) -> Result<Vec<i32>, DbError>;
+    /// Deletes every node, predicate and triple of the setting.
+    fn setting_delete(&self, setting: i32) -> Result<(), DbError>;
+    /// Graphs of every setting, read at a single point in time.
+    fn dump(&self) -> Result<Vec<GraphDump>, DbError>;
+    /// Recreates the graphs keeping their ids, failing when the backend holds
+    /// any graph already.
+    fn load(&self, graphs: &[GraphDump]) -> Result<(), DbError>;
+
+    /// Rows of the table, one per node matching its filter in ascending order.
+    fn table_rows(&self, setting: i32, table_def: TableDefinition) -> Result<Vec<RowResponse>, DbError>;
+
+    /// Runs a read Cypher query, keeping at most `max_rows` rows and giving up
+    /// after `timeout_ms`. The query is expected to constrain itself to the
+    /// setting through the `$setting` parameter. Backends without a Cypher
+    /// engine answer an error.
+    fn query(
+        &self,
+        query: &str,
+        params: Vec<(&str, serde_json::Value)>,
+        max_rows: usize,
+        timeout_ms: u64
+    ) -> Result<QueryRows, DbError>;
+}
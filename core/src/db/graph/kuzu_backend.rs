@@ -0,0 +1,301 @@
+mod triples;
+mod nodes;
+mod predicates;
+mod table;
+mod settings;
+mod migrations;
+pub use migrations::SCHEMA_VERSION;
+use kuzu::{
+    Connection,
+    Database,
+    LogicalType,
+    QueryResult,
+    SystemConfig,
+    Value
+};
+use crate::db::error::DbError;
+use crate::db::models::Triple;
+use crate::db::graph::backend::{
+    GraphBackend,
+    GraphDump,
+    KuzuOptions,
+    QueryRows
+};
+use crate::db::models::{
+    Filter,
+    GraphDirection,
+    Node,
+    Predicate,
+    RowResponse,
+    TableDefinition
+};
+
+pub trait TryCast<T> {
+    type Error;
+    fn try_cast(&self) -> Result<T, Self::Error>;
+}
+impl TryCast<i32> for Value {
+    type Error = String;
+
+    fn try_cast(&self) -> Result<i32, Self::Error> {
+        match self {
+            Value::Int64(i) => Ok(*i as i32),
+            _ => Err("Cannot convert to i32".to_string()),
+        }
+    }
+}
+
+impl TryCast<String> for Value {
+    type Error = String;
+
+    fn try_cast(&self) -> Result<String, Self::Error> {
+        match self {
+            Value::String(s) => Ok(s.clone()),
+            _ => Err("Cannot convert to String".to_string()),
+        }
+    }
+}
+
+pub trait TryFromValue: Sized {
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl<T> TryFromValue for T where Value: TryCast<T> {
+    fn from_value(value: &Value) -> Option<T> {
+        value.try_cast().ok()
+    }
+}
+
+pub trait QueryResultUtil {
+    fn single<K: TryFromValue>(self) -> Option<K>;
+
+}
+pub trait ConnectionUtil {
+    fn query_with_params(
+        &self,
+        query: &str,
+        params: Vec<(&str, QueryValue)>
+    ) -> Result<QueryResult<'_>, kuzu::Error>;
+}
+/// Value of a column of the row, failing when Kuzu answered another type.
+pub fn column<T>(row: &[Value], index: usize) -> Result<T, DbError>
+where Value: TryCast<T, Error = String> {
+    row.get(index)
+        .ok_or_else(|| DbError::backend(format!("Missing column {}", index)))?
+        .try_cast()
+        .map_err(DbError::Backend)
+}
+
+impl QueryResultUtil for QueryResult<'_> {
+    fn single<K: TryFromValue>(self) -> Option<K> {
+        let row = self.into_iter().next()?;
+        let value = row.first()?;
+        K::from_value(value)
+    }
+}
+pub struct QueryValue(Value);
+impl From<i32> for QueryValue {
+    fn from(value: i32) -> Self {
+        QueryValue(Value::Int64(value as i64))
+    }
+}
+impl From<String> for QueryValue {
+    fn from(value: String) -> Self {
+        QueryValue(Value::String(value))
+    }
+}
+impl From<&str> for QueryValue {
+    fn from(value: &str) -> Self {
+        QueryValue(Value::String(value.to_string()))
+    }
+}
+
+impl ConnectionUtil for Connection<'_> {
+    fn query_with_params(
+        &self,
+        query: &str,
+        params: Vec<(&str, QueryValue)>
+    ) -> Result<QueryResult<'_>, kuzu::Error> {
+        let params: Vec<(&str, Value)> = params.into_iter()
+            .map(|(name, value)| (name, value.0))
+            .collect();
+        self.execute(&mut self.prepare(query)?, params)
+    }
+}
+/// Converts a value returned by Kuzu to JSON. Nodes and rels become objects
+/// holding their label and properties, temporal and other exotic values use
+/// their textual form.
+fn value_to_json(value: &Value) -> serde_json::Value {
+    use serde_json::{json, Map};
+    let properties = |label: &str, props: &Vec<(String, Value)>| {
+        let mut object = Map::new();
+        object.insert("_label".to_string(), json!(label));
+        for (name, value) in props {
+            object.insert(name.clone(), value_to_json(value));
+        }
+        serde_json::Value::Object(object)
+    };
+    match value {
+        Value::Null(_) => serde_json::Value::Null,
+        Value::Bool(b) => json!(b),
+        Value::Int64(i) => json!(i),
+        Value::Int32(i) => json!(i),
+        Value::Int16(i) => json!(i),
+        Value::Int8(i) => json!(i),
+        Value::UInt64(i) => json!(i),
+        Value::UInt32(i) => json!(i),
+        Value::UInt16(i) => json!(i),
+        Value::UInt8(i) => json!(i),
+        Value::Double(f) => json!(f),
+        Value::Float(f) => json!(f),
+        Value::String(s) => json!(s),
+        Value::List(_, items) | Value::Array(_, items) => {
+            serde_json::Value::Array(items.iter().map(value_to_json).collect())
+        }
+        Value::Struct(fields) => serde_json::Value::Object(
+            fields.iter().map(|(name, value)| (name.clone(), value_to_json(value))).collect()
+        ),
+        Value::Node(node) => properties(node.get_label_name(), node.get_properties()),
+        Value::Rel(rel) => properties(rel.get_label_name(), rel.get_properties()),
+        value => json!(value.to_string()),
+    }
+}
+
+/// Converts a query parameter to the Kuzu value it stands for.
+fn json_to_value(value: serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Bool(b) => Value::Bool(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Int64(i),
+            None => Value::Double(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::String(s),
+        serde_json::Value::Null => Value::Null(LogicalType::Any),
+        value => Value::String(value.to_string()),
+    }
+}
+
+/// Graphs kept in a Kuzu database, the default backend.
+pub struct KuzuBackend(Database);
+
+impl KuzuBackend {
+    /// Opens the database at `path`, applying the pending migrations to its
+    /// schema. When it is opened read-only or `options.migrate` is off, the
+    /// schema must be current instead. A schema newer than this build knows
+    /// is refused either way.
+    pub fn open(path: &str, options: &KuzuOptions) -> Result<Self, String> {
+        let backend = KuzuBackend::open_unchecked(path, options)?;
+        if options.migrate && !options.read_only {
+            migrations::migrate(&backend.conn().map_err(|e| e.to_string())?)?;
+        } else {
+            migrations::check(&backend.conn().map_err(|e| e.to_string())?)?;
+        }
+        Ok(backend)
+    }
+    /// Opens the database without looking at its schema, for the tools
+    /// managing it.
+    pub fn open_unchecked(path: &str, options: &KuzuOptions) -> Result<Self, String> {
+        let mut config = SystemConfig::default().read_only(options.read_only);
+        if let Some(size) = options.buffer_pool_size {
+            config = config.buffer_pool_size(size);
+        }
+        if let Some(threads) = options.max_num_threads {
+            config = config.max_num_threads(threads);
+        }
+        let db = Database::new(path, config).map_err(|e| e.to_string())?;
+        Ok(KuzuBackend(db))
+    }
+    /// Version of the database's schema, 0 before its first migration.
+    pub fn schema_version(&self) -> Result<u32, String> {
+        migrations::schema_version(&self.conn().map_err(|e| e.to_string())?)
+    }
+    /// Applies the pending migrations, returning the version the schema was at.
+    pub fn migrate(&self) -> Result<u32, String> {
+        migrations::migrate(&self.conn().map_err(|e| e.to_string())?)
+    }
+    fn conn(&self) -> Result<Connection<'_>, DbError> {
+        Ok(Connection::new(&self.0)?)
+    }
+}
+
+impl GraphBackend for KuzuBackend {
+    fn node_create(&self, setting: i32, label: String) -> Result<i32, DbError> {
+        nodes::node_create(&self.conn()?, setting, label)
+    }
+    fn node_count(&self, setting: i32) -> Result<u64, DbError> {
+        nodes::node_count(&self.conn()?, setting)
+    }
+    fn node_all(&self, setting: i32) -> Result<Vec<Node>, DbError> {
+        nodes::node_all(&self.conn()?, setting)
+    }
+    fn node_by_ids(&self, setting: i32, ids: &[i32]) -> Result<Vec<Node>, DbError> {
+        nodes::node_by_ids(&self.conn()?, setting, ids)
+    }
+    fn node_update(&self, setting: i32, node_id: i32, label: String) -> Result<Node, DbError> {
+        nodes::node_update(&self.conn()?, setting, node_id, label)
+    }
+    fn node_delete(&self, setting: i32, node_id: i32) -> Result<bool, DbError> {
+        nodes::node_delete(&self.conn()?, setting, node_id)
+    }
+    fn node_filter(&self, setting: i32, filter: Filter) -> Result<Vec<i32>, DbError> {
+        table::filter_values(&self.conn()?, setting, filter)
+    }
+    fn predicate_all(&self, setting: i32) -> Result<Vec<Predicate>, DbError> {
+        predicates::predicate_all(&self.conn()?, setting)
+    }
+    fn predicate_create(&self, setting: i32, label: &str) -> Result<Predicate, DbError> {
+        predicates::predicate_create(&self.conn()?, setting, label)
+    }
+    fn triple_create(&self, setting: i32, triple: Triple) -> Result<bool, DbError> {
+        triples::triple_create(&self.conn()?, setting, triple)
+    }
+    fn triple_count(&self, setting: i32) -> Result<u64, DbError> {
+        triples::triple_count(&self.conn()?, setting)
+    }
+    fn triple_delete(&self, setting: i32, triple: Triple) -> Result<bool, DbError> {
+        triples::triple_delete(&self.conn()?, setting, triple)
+    }
+    fn triple_all(&self, setting: i32) -> Result<Vec<Triple>, DbError> {
+        triples::triple_all(&self.conn()?, setting)
+    }
+    fn triple_by_nodes(&self, setting: i32, ids: &[i32], direction: GraphDirection) -> Result<Vec<Triple>, DbError> {
+        triples::triple_by_nodes(&self.conn()?, setting, ids, direction)
+    }
+    fn settings(&self) -> Result<Vec<i32>, DbError> {
+        settings::settings(&self.conn()?)
+    }
+    fn setting_delete(&self, setting: i32) -> Result<(), DbError> {
+        settings::setting_delete(&self.conn()?, setting)
+    }
+    fn dump(&self) -> Result<Vec<GraphDump>, DbError> {
+        settings::dump(&self.conn()?)
+    }
+    fn load(&self, graphs: &[GraphDump]) -> Result<(), DbError> {
+        settings::load(&self.conn()?, graphs)
+    }
+    fn table_rows(&self, setting: i32, table_def: TableDefinition) -> Result<Vec<RowResponse>, DbError> {
+        table::table_rows(&self.conn()?, setting, table_def)
+    }
+    /// Failures of the query are blamed on it, as it comes from the user.
+    fn query(
+        &self,
+        query: &str,
+        params: Vec<(&str, serde_json::Value)>,
+        max_rows: usize,
+        timeout_ms: u64
+    ) -> Result<QueryRows, DbError> {
+        let conn = self.conn()?;
+        let params = params.into_iter().map(|(name, value)| (name, json_to_value(value))).collect();
+        conn.set_query_timeout(timeout_ms);
+        let mut statement = conn.prepare(query).map_err(DbError::invalid)?;
+        let result = conn.execute(&mut statement, params).map_err(DbError::invalid)?;
+        let columns = result.get_column_names();
+        let mut rows: Vec<Vec<serde_json::Value>> = result
+            .take(max_rows.saturating_add(1))
+            .map(|row| row.iter().map(value_to_json).collect())
+            .collect();
+        let truncated = rows.len() > max_rows;
+        rows.truncate(max_rows);
+        Ok(QueryRows { columns, rows, truncated })
+    }
+}
@@ -0,0 +1,142 @@
+use kuzu::{
+    Connection,
+    Value
+};
+use crate::db::graph::kuzu_backend::{
+    QueryResultUtil,
+    TryCast
+};
+
+/// Schema changes of the database, in order, migration `n` bringing the
+/// schema to version `n + 1`. Released migrations are never edited, changes
+/// go in a new one. A migration is recorded once it completes, so it must be
+/// safe to run again after failing midway.
+const MIGRATIONS: &[&str] = &[
+    // 1: the tables of the nodes, predicates and triples of every setting
+    "CREATE NODE TABLE IF NOT EXISTS Node(id SERIAL, setting INT, label STRING, __id SERIAL, PRIMARY KEY(__id) );
+    CREATE NODE TABLE IF NOT EXISTS Predicate(id SERIAL, setting INT, label STRING, __id SERIAL, PRIMARY KEY (__id));
+    CREATE REL TABLE IF NOT EXISTS Triple(FROM Node TO Node, id INT64);",
+];
+
+/// Version of the schema this build knows.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+impl TryCast<u32> for Value {
+    type Error = String;
+
+    fn try_cast(&self) -> Result<u32, Self::Error> {
+        match self {
+            Value::Int64(i) => u32::try_from(*i).map_err(|e| e.to_string()),
+            _ => Err("Cannot convert to u32".to_string()),
+        }
+    }
+}
+
+/// Version recorded in the database, 0 for a database created before the
+/// versions were recorded or not created yet.
+pub fn schema_version(conn: &Connection<'_>) -> Result<u32, String> {
+    let tables = conn.query("CALL show_tables() RETURN name;").map_err(|e| e.to_string())?;
+    let recorded = tables.into_iter().any(|row| row[0].try_cast().is_ok_and(|name: String| name == "SchemaVersion"));
+    if !recorded {
+        return Ok(0);
+    }
+    let result = conn.query("MATCH (v:SchemaVersion) RETURN v.version;").map_err(|e| e.to_string())?;
+    Ok(result.single().unwrap_or(0))
+}
+
+fn newer_error(version: u32) -> String {
+    format!(
+        "The database schema is at version {}, newer than the version {} this build knows, upgrade grpg",
+        version, SCHEMA_VERSION
+    )
+}
+
+/// Fails unless the schema is at the version this build knows.
+pub fn check(conn: &Connection<'_>) -> Result<(), String> {
+    let version = schema_version(conn)?;
+    if version > SCHEMA_VERSION {
+        return Err(newer_error(version));
+    }
+    if version < SCHEMA_VERSION {
+        return Err(format!(
+            "The database schema is at version {} and needs migrating to version {}, run `grpg-admin migrate`",
+            version, SCHEMA_VERSION
+        ));
+    }
+    Ok(())
+}
+
+/// Applies the pending migrations in order, returning the version the
+/// schema was at.
+pub fn migrate(conn: &Connection<'_>) -> Result<u32, String> {
+    let version = schema_version(conn)?;
+    if version > SCHEMA_VERSION {
+        return Err(newer_error(version));
+    }
+    conn.query("CREATE NODE TABLE IF NOT EXISTS SchemaVersion(id INT64, version INT64, PRIMARY KEY(id));")
+        .map_err(|e| e.to_string())?;
+    for (applied, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let next = applied as u32 + 1;
+        conn.query(migration).map_err(|e| format!("Migration to version {} failed: {}", next, e))?;
+        let mut record = conn.prepare(
+            "MERGE (v:SchemaVersion {id: 0}) ON CREATE SET v.version = $version ON MATCH SET v.version = $version;"
+        ).map_err(|e| e.to_string())?;
+        conn.execute(&mut record, vec![("version", Value::Int64(next as i64))]).map_err(|e| e.to_string())?;
+        log::info!("Migrated the graph database schema to version {}", next);
+    }
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::graph::{KuzuBackend, KuzuOptions};
+    use super::*;
+
+    /// A path for a new database, a directory or a file depending on the
+    /// Kuzu version.
+    fn path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("grpg-migrations-test-{}-{}", std::process::id(), name));
+        let path = path.to_string_lossy().into_owned();
+        remove(&path);
+        path
+    }
+
+    fn remove(path: &str) {
+        let _ = std::fs::remove_dir_all(path);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn migrates_new_databases_to_the_current_version() {
+        let path = path("new");
+        let manual = KuzuOptions { migrate: false, ..KuzuOptions::default() };
+        {
+            let backend = KuzuBackend::open_unchecked(&path, &manual).unwrap();
+            assert_eq!(backend.schema_version().unwrap(), 0);
+        }
+        let refused = KuzuBackend::open(&path, &manual).err().unwrap();
+        assert!(refused.contains("needs migrating"));
+        {
+            let backend = KuzuBackend::open_unchecked(&path, &manual).unwrap();
+            assert_eq!(backend.migrate().unwrap(), 0);
+            assert_eq!(backend.schema_version().unwrap(), SCHEMA_VERSION);
+            // Nothing is left to apply the second time
+            assert_eq!(backend.migrate().unwrap(), SCHEMA_VERSION);
+        }
+        KuzuBackend::open(&path, &manual).unwrap();
+        remove(&path);
+    }
+
+    #[test]
+    fn refuses_newer_schemas() {
+        let path = path("newer");
+        {
+            let backend = KuzuBackend::open(&path, &KuzuOptions::default()).unwrap();
+            let conn = backend.conn().unwrap();
+            conn.query(&format!("MATCH (v:SchemaVersion) SET v.version = {};", SCHEMA_VERSION + 1)).unwrap();
+        }
+        let refused = KuzuBackend::open(&path, &KuzuOptions::default()).err().unwrap();
+        assert!(refused.contains("newer than the version"));
+        remove(&path);
+    }
+}
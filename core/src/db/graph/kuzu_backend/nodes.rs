@@ -0,0 +1,139 @@
+use kuzu::{
+    Value,
+    Connection,
+    LogicalType
+};
+use crate::db::error::DbError;
+use crate::db::graph::kuzu_backend::QueryResultUtil;
+use crate::db::models::Node;
+use crate::db::graph::kuzu_backend::{
+    column,
+    ConnectionUtil
+};
+
+pub fn node_create(
+    conn: &Connection<'_>,
+    setting: i32,
+    label: String
+)  -> Result<i32, DbError> {
+
+    let result = conn.query_with_params(
+        "MATCH (n:Node {setting: $setting}) RETURN MAX(n.id) AS id;",
+        vec![("setting", setting.into())]
+    )?;
+    let last_id : i32 = result.single().unwrap_or(0);
+
+    let query = r#"
+        CREATE (n:Node {setting: $setting, id: $id, label: $label})
+        RETURN n.id;
+    "#;
+
+    let params = vec!(
+        ("setting", Value::Int64(setting as i64)),
+        ("id", Value::Int64((last_id + 1) as i64)),
+        ("label", Value::String(label.clone()))
+    );
+
+    let result = conn.execute(&mut conn.prepare(query)?, params)?;
+    result.single().ok_or_else(|| DbError::backend("Node was not created"))
+}
+
+pub fn node_count(
+    conn: &Connection<'_>,
+    setting: i32
+) -> Result<u64, DbError> {
+    let result = conn.query_with_params(
+        "MATCH (n:Node {setting: $setting}) RETURN COUNT(n);",
+        vec![("setting", setting.into())]
+    )?;
+    Ok(result.single::<i32>().unwrap_or(0) as u64)
+}
+
+pub fn node_all(
+    conn: &Connection<'_>,
+    setting: i32
+) -> Result<Vec<Node>, DbError> {
+    let result = conn.query_with_params(
+        "MATCH (n:Node {setting: $setting}) RETURN n.id AS id, n.label as label;",
+        vec![("setting", setting.into())]
+    )?;
+
+    result
+        .into_iter()
+        .map(|row| Ok(Node {
+            node_id: column(&row, 0)?,
+            label: column(&row, 1).unwrap_or_default(),
+        }))
+        .collect()
+}
+
+pub fn node_update(
+    conn: &Connection<'_>,
+    setting: i32,
+    node_id: i32,
+    label: String
+) -> Result<Node, DbError> {
+    let query = r#"
+        MATCH (n:Node {id: $id, setting: $setting})
+        SET n.label = $label
+        RETURN n.label;
+    "#;
+
+    let params = vec![
+        ("id", Value::Int64(node_id as i64)),
+        ("label", Value::String(label.clone())),
+        ("setting", Value::Int64(setting as i64))
+    ];
+
+    let result = conn.execute(&mut conn.prepare(query)?, params)?;
+    let new_label: String = result.single().ok_or_else(|| DbError::not_found("Node not found"))?;
+
+    Ok(Node {
+        node_id,
+        label: new_label,
+    })
+}
+
+pub fn node_delete(
+    conn: &Connection<'_>,
+    setting: i32,
+    node_id: i32,
+) -> Result<bool, DbError> {
+    let query = r#"
+        MATCH (n:Node {id: $id, setting: $setting}) DETACH DELETE n RETURN n.id;
+    "#;
+
+    let params = vec![
+        ("id", Value::Int64(node_id as i64)),
+        ("setting", Value::Int64(setting as i64))
+    ];
+
+    let result = conn.execute(&mut conn.prepare(query)?, params)?;
+    Ok(result.into_iter().next().is_some())
+}
+
+pub fn node_by_ids(
+    conn: &Connection<'_>,
+    setting: i32,
+    ids: &[i32]
+) -> Result<Vec<Node>, DbError> {
+    let query = r#"
+        MATCH (n:Node {setting: $setting})
+        WHERE n.id IN $ids
+        RETURN n.id AS id, n.label AS label;
+    "#;
+
+    let params = vec![
+        ("setting", Value::Int64(setting as i64)),
+        ("ids", Value::List(LogicalType::Int64, ids.iter().map(|&id| Value::Int64(id as i64)).collect()))
+    ];
+
+    let result = conn.execute(&mut conn.prepare(query)?, params)?;
+    result
+        .into_iter()
+        .map(|row| Ok(Node {
+            node_id: column(&row, 0)?,
+            label: column(&row, 1).unwrap_or_default(),
+        }))
+        .collect()
+}
@@ -2,27 +2,30 @@ pub use kuzu::{
     Connection,
     Value,
 };
-pub use crate::db::{
+pub use crate::db::graph::kuzu_backend::{
     TryCast
 };
 pub use crate::db::models::{
     Predicate
 };
-use crate::db::ConnectionUtil;
+use crate::db::error::DbError;
+use crate::db::graph::kuzu_backend::{
+    column,
+    ConnectionUtil
+};
 
-pub fn predicate_all(conn: &Connection<'_>, setting: i32,) -> Vec<Predicate> {
+pub fn predicate_all(conn: &Connection<'_>, setting: i32,) -> Result<Vec<Predicate>, DbError> {
     let result = conn.query_with_params(
         "MATCH (p:Predicate {setting: $setting}) RETURN p.id, p.label AS label",
         vec!(("setting", setting.into()))
-    ).unwrap();
-    let predicates : Vec<Predicate> = result
+    )?;
+    result
         .into_iter()
-        .map(|row| Predicate {
-            id: row[0].try_cast().unwrap(),
-            label: row[1].try_cast().unwrap()
-        })
-        .collect();
-    predicates
+        .map(|row| Ok(Predicate {
+            id: column(&row, 0)?,
+            label: column(&row, 1)?
+        }))
+        .collect()
 }
 
 
@@ -30,14 +33,13 @@ pub fn predicate_create(
     conn: &Connection<'_>,
     setting: i32,
     label: &str,
-) -> Predicate {
+) -> Result<Predicate, DbError> {
 
     let result = conn
         .query_with_params(
             "MATCH (p:Predicate {setting: $setting}) RETURN MAX(p.id) AS id",
             vec![("setting", setting.into())]
-        )
-        .unwrap();
+        )?;
     let last_id: i32 = result
         .into_iter()
         .next()
@@ -47,14 +49,13 @@ pub fn predicate_create(
     let id = last_id + 1;
     let create_result = conn
         .execute(
-            &mut conn.prepare("CREATE (p:Predicate {label: $label, id: $id, setting: $setting}) RETURN p.id").unwrap(),
+            &mut conn.prepare("CREATE (p:Predicate {label: $label, id: $id, setting: $setting}) RETURN p.id")?,
             vec!(
                 ("label", Value::from(label)),
                 ("id", Value::from(id)),
                 ("setting", Value::Int64(setting as i64))
             ),
-        )
-        .unwrap();
+        )?;
     let new_id: i32 = create_result
         .into_iter()
         .next()
@@ -65,6 +66,6 @@ pub fn predicate_create(
         id: new_id,
         label: label.to_string(),
     };
-    pred
+    Ok(pred)
 
 }
@@ -0,0 +1,99 @@
+use kuzu::{
+    Connection,
+    Value
+};
+use crate::db::error::DbError;
+use crate::db::graph::backend::GraphDump;
+use crate::db::graph::kuzu_backend::{
+    nodes,
+    predicates,
+    triples,
+    ConnectionUtil,
+    TryCast
+};
+
+pub fn settings(conn: &Connection<'_>) -> Result<Vec<i32>, DbError> {
+    let result = conn.query(
+        "MATCH (n:Node) RETURN DISTINCT n.setting AS setting
+        UNION MATCH (p:Predicate) RETURN DISTINCT p.setting AS setting;"
+    )?;
+    let mut settings: Vec<i32> = result
+        .into_iter()
+        .filter_map(|row| row[0].try_cast().ok())
+        .collect();
+    settings.sort();
+    Ok(settings)
+}
+
+pub fn setting_delete(conn: &Connection<'_>, setting: i32) -> Result<(), DbError> {
+    conn.query_with_params(
+        "MATCH (n:Node {setting: $setting}) DETACH DELETE n;",
+        vec![("setting", setting.into())]
+    )?;
+    conn.query_with_params(
+        "MATCH (p:Predicate {setting: $setting}) DELETE p;",
+        vec![("setting", setting.into())]
+    )?;
+    Ok(())
+}
+
+fn dump_graphs(conn: &Connection<'_>) -> Result<Vec<GraphDump>, DbError> {
+    settings(conn)?
+        .into_iter()
+        .map(|setting| Ok(GraphDump {
+            setting,
+            nodes: nodes::node_all(conn, setting)?,
+            predicates: predicates::predicate_all(conn, setting)?,
+            triples: triples::triple_all(conn, setting)?,
+        }))
+        .collect()
+}
+
+/// Reads every graph in a single read-only transaction.
+pub fn dump(conn: &Connection<'_>) -> Result<Vec<GraphDump>, DbError> {
+    conn.query("BEGIN TRANSACTION READ ONLY;")?;
+    let graphs = dump_graphs(conn);
+    let committed = conn.query("COMMIT;");
+    let graphs = graphs?;
+    committed?;
+    Ok(graphs)
+}
+
+fn load_graph(conn: &Connection<'_>, graph: &GraphDump) -> Result<(), DbError> {
+    let setting = Value::Int64(graph.setting as i64);
+    let mut create_node = conn.prepare("CREATE (:Node {setting: $setting, id: $id, label: $label});")?;
+    for node in &graph.nodes {
+        conn.execute(&mut create_node, vec![
+            ("setting", setting.clone()),
+            ("id", Value::Int64(node.node_id as i64)),
+            ("label", Value::String(node.label.clone()))
+        ])?;
+    }
+    let mut create_predicate = conn.prepare("CREATE (:Predicate {setting: $setting, id: $id, label: $label});")?;
+    for predicate in &graph.predicates {
+        conn.execute(&mut create_predicate, vec![
+            ("setting", setting.clone()),
+            ("id", Value::Int64(predicate.id as i64)),
+            ("label", Value::String(predicate.label.clone()))
+        ])?;
+    }
+    for triple in &graph.triples {
+        triples::triple_create(conn, graph.setting, triple.clone())?;
+    }
+    Ok(())
+}
+
+/// Writes the graphs in a single transaction, into a database without any.
+pub fn load(conn: &Connection<'_>, graphs: &[GraphDump]) -> Result<(), DbError> {
+    if !settings(conn)?.is_empty() {
+        return Err(DbError::conflict("The graph database is not empty"));
+    }
+    conn.query("BEGIN TRANSACTION;")?;
+    match graphs.iter().try_for_each(|graph| load_graph(conn, graph)) {
+        Ok(()) => Ok(conn.query("COMMIT;").map(|_| ())?),
+        Err(e) => {
+            let _ = conn.query("ROLLBACK;");
+            Err(e)
+        }
+    }
+}
@@ -11,12 +11,11 @@ pub use kuzu::{
     Value,
     LogicalType
 };
-pub use crate::db::{
-    TryCast
-};
+use crate::db::error::DbError;
+use crate::db::graph::kuzu_backend::column;
 
 use crate::db::models::Filter;
-pub async fn filter_values( conn: &Connection<'_>, setting: i32, filter: Filter) -> Vec<i32> {
+pub fn filter_values( conn: &Connection<'_>, setting: i32, filter: Filter) -> Result<Vec<i32>, DbError> {
    let mut params = vec!(("setting", Value::Int64(setting as i64)));
 
     let triple_str = if let Some(pid) = filter.predicate {
@@ -48,20 +47,20 @@ pub async fn filter_values( conn: &Connection<'_>, setting: i32, filter: Filter)
         "MATCH (n:Node {{setting: $setting}}) {} RETURN DISTINCT n.id AS id, n.label as label;",
         x
     );
-    let result = conn.execute(&mut conn.prepare(&query).unwrap(), params).unwrap();
+    let result = conn.execute(&mut conn.prepare(&query)?, params)?;
 
     let mut row : Vec<i32> = result
         .into_iter()
-        .map(|row| row[0].try_cast().unwrap())
-        .collect();
+        .map(|row| column(&row, 0))
+        .collect::<Result<_, _>>()?;
 
     row.sort();
-    row
+    Ok(row)
 }
-pub async fn table_rows(conn: &Connection<'_>, setting: i32, table_def: TableDefinition) -> Vec<RowResponse> {
-    let nodes_id = filter_values(&conn, setting, table_def.filter).await;
+pub fn table_rows(conn: &Connection<'_>, setting: i32, table_def: TableDefinition) -> Result<Vec<RowResponse>, DbError> {
+    let nodes_id = filter_values(conn, setting, table_def.filter)?;
     if nodes_id.is_empty() {
-        return Vec::<RowResponse>::new();
+        return Ok(Vec::<RowResponse>::new());
     }
 
     let mut out_ids = Vec::new();
@@ -98,15 +97,15 @@ pub async fn table_rows(conn: &Connection<'_>, setting: i32, table_def: TableDef
         ("any_ids", Value::List(LogicalType::Int64, any_ids.iter().map(|&id| Value::Int64(id as i64)).collect()))
     );
 
-    let result = conn.execute(&mut conn.prepare(query).unwrap(), query_params).unwrap();
+    let result = conn.execute(&mut conn.prepare(query)?, query_params)?;
 
     // Group rows by rnid (node id)
     let mut grouped: HashMap<i32, Vec<(i32, i32, i32, String)>> = HashMap::new();
     for row in result {
-        let rnid: i32 = row[0].try_cast().unwrap();
-        let nid: i32 = row[1].try_cast().unwrap();
-        let pid: i32 = row[2].try_cast().unwrap();
-        let direction: String = row[3].try_cast().unwrap();
+        let rnid: i32 = column(&row, 0)?;
+        let nid: i32 = column(&row, 1)?;
+        let pid: i32 = column(&row, 2)?;
+        let direction: String = column(&row, 3)?;
         grouped.entry(rnid).or_default().push((nid, pid, rnid, direction));
     }
 
@@ -145,5 +144,5 @@ pub async fn table_rows(conn: &Connection<'_>, setting: i32, table_def: TableDef
         }
     }
 
-    nodes_id.iter().map( |o| response.iter().filter(|r| r.node_id == *o).next().unwrap().clone()).collect::<Vec<RowResponse>>()
+    Ok(nodes_id.iter().filter_map(|o| response.iter().find(|r| r.node_id == *o).cloned()).collect::<Vec<RowResponse>>())
 }
@@ -0,0 +1,136 @@
+use crate::db::error::DbError;
+use crate::db::graph::kuzu_backend::TryCast;
+use crate::db::models::Triple;
+use crate::db::models::GraphDirection;
+use kuzu::{
+    Value,
+    Connection,
+    LogicalType
+};
+use crate::db::graph::kuzu_backend::ConnectionUtil;
+use crate::db::graph::kuzu_backend::QueryResultUtil;
+
+pub fn triple_create(
+    conn: &Connection<'_>,
+    setting: i32,
+    triple: Triple,
+) -> Result<bool, DbError> {
+    let query =
+        r#"
+        MATCH (n1:Node {setting: $setting}), (n2:Node {setting: $setting})
+        WHERE n1.id = $id1 AND n2.id = $id2
+        CREATE (n1)-[t:Triple { id: $pid }]->(n2)
+        RETURN t.id;
+    "#;
+
+    let params = vec![
+        ("setting", Value::Int64(setting as i64)),
+        ("id1", Value::Int64(triple.subject_id as i64)),
+        ("pid", Value::Int64(triple.predicate_id as i64)),
+        ("id2", Value::Int64(triple.object_id as i64))
+    ];
+
+    let result = conn.execute(&mut conn.prepare(query)?, params)?;
+    Ok(result.into_iter().next().is_some())
+}
+
+
+pub fn triple_count(
+    conn: &Connection<'_>,
+    setting: i32
+) -> Result<u64, DbError> {
+    let result = conn.query_with_params(
+        "MATCH (:Node {setting: $setting})-[t:Triple]->(:Node {setting: $setting}) RETURN COUNT(t);",
+        vec![("setting", setting.into())]
+    )?;
+    Ok(result.single::<i32>().unwrap_or(0) as u64)
+}
+
+pub fn triple_delete(
+    conn: &Connection<'_>,
+    setting: i32,
+    triple: Triple,
+) -> Result<bool, DbError> {
+    let query =
+        r#"
+        MATCH (n1:Node {id: $id1, setting: $setting})-[t:Triple {id: $pid}]->(n2:Node {id: $id2, setting: $setting})
+        DELETE t
+        RETURN t.id;
+    "#;
+
+    let params = vec![
+        ("setting", Value::Int64(setting as i64)),
+        ("id1", Value::Int64(triple.subject_id as i64)),
+        ("pid", Value::Int64(triple.predicate_id as i64)),
+        ("id2", Value::Int64(triple.object_id as i64))
+    ];
+
+    let result = conn.execute(&mut conn.prepare(query)?, params)?;
+    Ok(result.into_iter().next().is_some())
+}
+
+pub fn triple_all(
+    conn: &Connection<'_>,
+    setting: i32
+) -> Result<Vec<Triple>, DbError> {
+    let query =
+        r#"
+        MATCH (n1:Node {setting: $setting})-[t:Triple]->(n2:Node{setting: $setting})
+        RETURN n1.id AS subject_id, t.id AS predicate_id, n2.id AS object_id;
+    "#;
+
+    let result = conn.query_with_params(
+        query,
+        vec![("setting", setting.into())]
+    )?;
+
+    let triples: Vec<Triple> = result
+        .into_iter()
+        .filter_map(|row| {
+            Some(Triple {
+                subject_id: row[0].try_cast().ok()?,
+                predicate_id: row[1].try_cast().ok()?,
+                object_id: row[2].try_cast().ok()?,
+            })
+        })
+        .collect();
+    Ok(triples)
+}
+
+/// Triples leaving (`Out`) or reaching (`In`) any of the given nodes.
+pub fn triple_by_nodes(
+    conn: &Connection<'_>,
+    setting: i32,
+    ids: &[i32],
+    direction: GraphDirection
+) -> Result<Vec<Triple>, DbError> {
+    let query = match direction {
+        GraphDirection::Out => r#"
+            MATCH (n1:Node {setting: $setting})-[t:Triple]->(n2:Node {setting: $setting})
+            WHERE n1.id IN $ids
+            RETURN n1.id AS subject_id, t.id AS predicate_id, n2.id AS object_id;
+        "#,
+        GraphDirection::In => r#"
+            MATCH (n1:Node {setting: $setting})-[t:Triple]->(n2:Node {setting: $setting})
+            WHERE n2.id IN $ids
+            RETURN n1.id AS subject_id, t.id AS predicate_id, n2.id AS object_id;
+        "#,
+    };
+
+    let params = vec![
+        ("setting", Value::Int64(setting as i64)),
+        ("ids", Value::List(LogicalType::Int64, ids.iter().map(|&id| Value::Int64(id as i64)).collect()))
+    ];
+
+    let result = conn.execute(&mut conn.prepare(query)?, params)?;
+    Ok(result
+        .into_iter()
+        .filter_map(|row| {
+            Some(Triple {
+                subject_id: row[0].try_cast().ok()?,
+                predicate_id: row[1].try_cast().ok()?,
+                object_id: row[2].try_cast().ok()?,
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,278 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+use crate::db::error::DbError;
+use crate::db::models::Triple;
+use crate::db::graph::backend::{
+    GraphBackend,
+    GraphDump,
+    QueryRows
+};
+use crate::db::models::{
+    CellResponse,
+    Filter,
+    GraphDirection,
+    Node,
+    Predicate,
+    RowResponse,
+    TableDefinition
+};
+
+#[derive(Default)]
+struct SettingGraph {
+    nodes: BTreeMap<i32, String>,
+    predicates: BTreeMap<i32, String>,
+    triples: Vec<Triple>,
+    /// Highest node and predicate ids given out, so deleted ids are not reused
+    last_node_id: i32,
+    last_predicate_id: i32,
+}
+
+impl SettingGraph {
+    /// Nodes linked to `node_id`, with the predicate and the direction of the
+    /// link as seen from `node_id`.
+    fn links(&self, node_id: i32) -> impl Iterator<Item = (i32, i32, GraphDirection)> + '_ {
+        self.triples.iter().flat_map(move |t| {
+            let out = (t.subject_id == node_id).then_some((t.object_id, t.predicate_id, GraphDirection::Out));
+            let into = (t.object_id == node_id).then_some((t.subject_id, t.predicate_id, GraphDirection::In));
+            out.into_iter().chain(into)
+        })
+    }
+}
+
+/// Graphs kept in memory and lost when the server stops, for tests and
+/// deployments without the Kuzu library. Cypher and SPARQL queries are not
+/// supported.
+#[derive(Default)]
+pub struct MemoryBackend(RwLock<HashMap<i32, SettingGraph>>);
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        MemoryBackend::default()
+    }
+    fn read<R>(&self, setting: i32, f: impl FnOnce(&SettingGraph) -> R) -> R {
+        let graphs = self.0.read().unwrap();
+        match graphs.get(&setting) {
+            Some(graph) => f(graph),
+            None => f(&SettingGraph::default()),
+        }
+    }
+    fn write<R>(&self, setting: i32, f: impl FnOnce(&mut SettingGraph) -> R) -> R {
+        f(self.0.write().unwrap().entry(setting).or_default())
+    }
+}
+
+fn same_direction(a: &GraphDirection, b: &GraphDirection) -> bool {
+    a.to_string() == b.to_string()
+}
+
+impl GraphBackend for MemoryBackend {
+    fn node_create(&self, setting: i32, label: String) -> Result<i32, DbError> {
+        Ok(self.write(setting, |graph| {
+            let id = graph.nodes.keys().copied().fold(graph.last_node_id, i32::max) + 1;
+            graph.last_node_id = id;
+            graph.nodes.insert(id, label);
+            id
+        }))
+    }
+    fn node_count(&self, setting: i32) -> Result<u64, DbError> {
+        Ok(self.read(setting, |graph| graph.nodes.len() as u64))
+    }
+    fn node_all(&self, setting: i32) -> Result<Vec<Node>, DbError> {
+        Ok(self.read(setting, |graph| graph.nodes.iter()
+            .map(|(id, label)| Node { node_id: *id, label: label.clone() })
+            .collect()))
+    }
+    fn node_by_ids(&self, setting: i32, ids: &[i32]) -> Result<Vec<Node>, DbError> {
+        Ok(self.read(setting, |graph| ids.iter()
+            .filter_map(|id| Some(Node { node_id: *id, label: graph.nodes.get(id)?.clone() }))
+            .collect()))
+    }
+    fn node_update(&self, setting: i32, node_id: i32, label: String) -> Result<Node, DbError> {
+        self.write(setting, |graph| {
+            let current = graph.nodes.get_mut(&node_id).ok_or_else(|| DbError::not_found("Node not found"))?;
+            *current = label.clone();
+            Ok(Node { node_id, label })
+        })
+    }
+    fn node_delete(&self, setting: i32, node_id: i32) -> Result<bool, DbError> {
+        Ok(self.write(setting, |graph| {
+            graph.triples.retain(|t| t.subject_id != node_id && t.object_id != node_id);
+            graph.nodes.remove(&node_id).is_some()
+        }))
+    }
+    fn node_filter(&self, setting: i32, filter: Filter) -> Result<Vec<i32>, DbError> {
+        Ok(self.read(setting, |graph| {
+            if filter.direction.is_none() && filter.predicate.is_none() {
+                return graph.nodes.keys().copied().collect();
+            }
+            graph.nodes.keys()
+                .copied()
+                .filter(|id| graph.links(*id).any(|(other, predicate, direction)| {
+                    filter.direction.as_ref().is_none_or(|d| same_direction(d, &direction))
+                        && filter.predicate.is_none_or(|p| p == predicate)
+                        && filter.node_id.is_none_or(|n| n == other)
+                }))
+                .collect()
+        }))
+    }
+    fn predicate_all(&self, setting: i32) -> Result<Vec<Predicate>, DbError> {
+        Ok(self.read(setting, |graph| graph.predicates.iter()
+            .map(|(id, label)| Predicate { id: *id, label: label.clone() })
+            .collect()))
+    }
+    fn predicate_create(&self, setting: i32, label: &str) -> Result<Predicate, DbError> {
+        Ok(self.write(setting, |graph| {
+            let id = graph.predicates.keys().copied().fold(graph.last_predicate_id, i32::max) + 1;
+            graph.last_predicate_id = id;
+            graph.predicates.insert(id, label.to_string());
+            Predicate { id, label: label.to_string() }
+        }))
+    }
+    fn triple_create(&self, setting: i32, triple: Triple) -> Result<bool, DbError> {
+        Ok(self.write(setting, |graph| {
+            let created = graph.nodes.contains_key(&triple.subject_id) && graph.nodes.contains_key(&triple.object_id);
+            if created {
+                graph.triples.push(triple);
+            }
+            created
+        }))
+    }
+    fn triple_count(&self, setting: i32) -> Result<u64, DbError> {
+        Ok(self.read(setting, |graph| graph.triples.len() as u64))
+    }
+    fn triple_delete(&self, setting: i32, triple: Triple) -> Result<bool, DbError> {
+        Ok(self.write(setting, |graph| {
+            let count = graph.triples.len();
+            graph.triples.retain(|t| {
+                (t.subject_id, t.predicate_id, t.object_id) != (triple.subject_id, triple.predicate_id, triple.object_id)
+            });
+            graph.triples.len() < count
+        }))
+    }
+    fn triple_all(&self, setting: i32) -> Result<Vec<Triple>, DbError> {
+        Ok(self.read(setting, |graph| graph.triples.clone()))
+    }
+    fn triple_by_nodes(&self, setting: i32, ids: &[i32], direction: GraphDirection) -> Result<Vec<Triple>, DbError> {
+        Ok(self.read(setting, |graph| graph.triples.iter()
+            .filter(|t| match direction {
+                GraphDirection::Out => ids.contains(&t.subject_id),
+                GraphDirection::In => ids.contains(&t.object_id),
+            })
+            .cloned()
+            .collect()))
+    }
+    fn settings(&self) -> Result<Vec<i32>, DbError> {
+        let graphs = self.0.read().unwrap();
+        let mut settings: Vec<i32> = graphs.iter()
+            .filter(|(_, graph)| !graph.nodes.is_empty() || !graph.predicates.is_empty())
+            .map(|(setting, _)| *setting)
+            .collect();
+        settings.sort();
+        Ok(settings)
+    }
+    fn setting_delete(&self, setting: i32) -> Result<(), DbError> {
+        self.0.write().unwrap().remove(&setting);
+        Ok(())
+    }
+    fn dump(&self) -> Result<Vec<GraphDump>, DbError> {
+        let graphs = self.0.read().unwrap();
+        let mut dump: Vec<GraphDump> = graphs.iter()
+            .filter(|(_, graph)| !graph.nodes.is_empty() || !graph.predicates.is_empty())
+            .map(|(setting, graph)| GraphDump {
+                setting: *setting,
+                nodes: graph.nodes.iter().map(|(id, label)| Node { node_id: *id, label: label.clone() }).collect(),
+                predicates: graph.predicates.iter().map(|(id, label)| Predicate { id: *id, label: label.clone() }).collect(),
+                triples: graph.triples.clone(),
+            })
+            .collect();
+        dump.sort_by_key(|g| g.setting);
+        Ok(dump)
+    }
+    fn load(&self, dump: &[GraphDump]) -> Result<(), DbError> {
+        let mut graphs = self.0.write().unwrap();
+        if graphs.values().any(|graph| !graph.nodes.is_empty() || !graph.predicates.is_empty()) {
+            return Err(DbError::conflict("The graph backend is not empty"));
+        }
+        for graph in dump {
+            if graphs.get(&graph.setting).is_some_and(|graph| !graph.nodes.is_empty() || !graph.predicates.is_empty()) {
+                return Err(DbError::invalid(format!("The graph of setting {} is loaded twice", graph.setting)));
+            }
+            graphs.insert(graph.setting, SettingGraph {
+                nodes: graph.nodes.iter().map(|n| (n.node_id, n.label.clone())).collect(),
+                predicates: graph.predicates.iter().map(|p| (p.id, p.label.clone())).collect(),
+                triples: graph.triples.clone(),
+                ..SettingGraph::default()
+            });
+        }
+        Ok(())
+    }
+    /// Links are looked up for the predicates of the columns, in the columns'
+    /// directions, and each column keeps those matching its own filter, like
+    /// the Kuzu backend's query.
+    fn table_rows(&self, setting: i32, table_def: TableDefinition) -> Result<Vec<RowResponse>, DbError> {
+        let nodes_id = self.node_filter(setting, table_def.filter.clone())?;
+        let mut out_ids = Vec::new();
+        let mut in_ids = Vec::new();
+        let mut any_ids = Vec::new();
+        for col in &table_def.columns {
+            match col.filter.direction {
+                Some(GraphDirection::Out) => col.filter.predicate_id.inspect(|pid| out_ids.push(*pid)),
+                Some(GraphDirection::In) => col.filter.predicate_id.inspect(|pid| in_ids.push(*pid)),
+                None => col.filter.predicate_id.inspect(|pid| any_ids.push(*pid)),
+            };
+        }
+        Ok(self.read(setting, |graph| nodes_id.into_iter()
+            .map(|node_id| {
+                // Linked node, predicate and direction, none for links found either way
+                let links: Vec<(i32, i32, Option<GraphDirection>)> = graph.links(node_id)
+                    .flat_map(|(other, pid, direction)| {
+                        let directed = match direction {
+                            GraphDirection::Out => out_ids.contains(&pid),
+                            GraphDirection::In => in_ids.contains(&pid),
+                        };
+                        let directed = directed.then_some((other, pid, Some(direction)));
+                        let any = any_ids.contains(&pid).then_some((other, pid, None));
+                        directed.into_iter().chain(any)
+                    })
+                    .collect();
+                let columns = table_def.columns.iter().map(|col| {
+                    let mut values: Vec<i32> = links.iter()
+                        .filter(|(_, pid, direction)| {
+                            col.filter.direction.as_ref()
+                                .is_none_or(|d| direction.as_ref().is_some_and(|l| same_direction(d, l)))
+                            && col.filter.predicate_id.is_none_or(|cpid| *pid == cpid)
+                        })
+                        .map(|(other, _, _)| *other)
+                        .collect();
+                    values.sort();
+                    CellResponse { id: col.id, values }
+                }).collect();
+                RowResponse { node_id, columns }
+            })
+            .collect()))
+    }
+    fn query(
+        &self,
+        _query: &str,
+        _params: Vec<(&str, serde_json::Value)>,
+        _max_rows: usize,
+        _timeout_ms: u64
+    ) -> Result<QueryRows, DbError> {
+        Err(DbError::Unsupported("Queries need the Kuzu graph backend".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_not_reused() {
+        let backend = MemoryBackend::new();
+        backend.node_create(1, "Ada".to_string()).unwrap();
+        let byron = backend.node_create(1, "Byron".to_string()).unwrap();
+        backend.node_delete(1, byron).unwrap();
+        assert_eq!(backend.node_create(1, "Allegra".to_string()).unwrap(), byron + 1);
+        assert_eq!(backend.node_create(2, "Ada".to_string()).unwrap(), 1);
+    }
+}
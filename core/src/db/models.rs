@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use utoipa::{ToSchema};
+
+
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct UserData {
+    pub id: i32,
+    pub name: String,
+}
+
+/// A team of users sharing the settings it owns.
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct OrganizationData {
+    pub name: String,
+    /// Roles by user id, granted on every setting of the organization
+    #[serde(default)]
+    pub members: HashMap<i32, Role>,
+    /// Roles offered to users who have not accepted yet
+    #[serde(default)]
+    pub invitations: HashMap<i32, Role>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct Node {
+    pub node_id: i32,
+    pub label: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct Triple {
+    pub subject_id: i32,
+    pub predicate_id: i32,
+    pub object_id: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct Predicate {
+    pub id: i32,
+    pub label: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphDirection {
+    In,
+    Out,
+}
+impl std::fmt::Display for GraphDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphDirection::In => write!(f, "in"),
+            GraphDirection::Out => write!(f, "out"),
+        }
+    }
+}
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct ColumnFilter {
+    pub direction: Option<GraphDirection>,
+    pub predicate_id: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct ColumnDefinition {
+    pub id: i32,
+    pub filter: ColumnFilter,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct TableDefinition {
+    pub label: String,
+    pub filter: Filter,
+    pub columns: Vec<ColumnDefinition>,
+}
+
+#[derive(Clone, Deserialize, Serialize, ToSchema)]
+pub struct Filter {
+    pub node_id: Option<i32>,
+    pub predicate: Option<i32>,
+    pub direction: Option<GraphDirection>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct CellResponse {
+    pub id: i32,
+    pub values: Vec<i32>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct RowResponse {
+    pub node_id: i32,
+    pub columns: Vec<CellResponse>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct Webhook {
+    pub url: String,
+    pub secret: String,
+    /// Event names to deliver, such as `node_created`. A trailing `*` matches
+    /// by prefix (`table_*`) and an empty list matches every event.
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+impl Webhook {
+    pub fn accepts(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| match e.strip_suffix('*') {
+            Some(prefix) => event.starts_with(prefix),
+            None => e == event,
+        })
+    }
+}
+
+/// One attempt at delivering an event to a webhook.
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct Delivery {
+    /// Identifies the delivery across its retries, also sent as `X-Grpg-Delivery`
+    pub delivery_id: String,
+    pub webhook_id: i32,
+    pub event: String,
+    pub attempt: u32,
+    /// Status code answered by the receiver, when it answered at all
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub success: bool,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Session {
+    pub user_id: i32,
+    /// Seconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+/// Access a user has to a setting, each role includes the ones before it.
+#[derive(Debug, Deserialize, Serialize, ToSchema, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    Editor,
+    /// Also manages members and webhooks
+    Owner,
+}
+impl Role {
+    /// Role needed for a request that needs the given token scope.
+    pub fn for_scope(scope: TokenScope) -> Role {
+        match scope {
+            TokenScope::Read => Role::Viewer,
+            TokenScope::ReadWrite => Role::Editor,
+            TokenScope::Admin => Role::Owner,
+        }
+    }
+}
+
+/// What an API token may do, each scope includes the ones before it.
+#[derive(Deserialize, Serialize, ToSchema, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    Read,
+    ReadWrite,
+    /// Also manages API tokens and webhooks
+    Admin,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct ApiToken {
+    pub user_id: i32,
+    pub name: String,
+    pub scope: TokenScope,
+    /// Settings the token is limited to, every setting of the user when absent
+    pub settings: Option<Vec<i32>>,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    /// Kept in its own column of the store, never serialized
+    #[serde(default, skip_serializing)]
+    #[schema(ignore)]
+    pub token_hash: String,
+}
+
+/// Link giving anonymous read-only access to a setting or one of its tables.
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
+pub struct ShareLink {
+    /// Table the link is limited to, the whole setting when absent
+    pub table_id: Option<i32>,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    /// Seconds since the Unix epoch, the link never expires when absent
+    pub expires_at: Option<u64>,
+    /// Kept in its own column of the store, never serialized
+    #[serde(default, skip_serializing)]
+    #[schema(ignore)]
+    pub token_hash: String,
+}
+
+/// Limits on the size of a setting, none when a field is absent.
+#[derive(Deserialize, Serialize, ToSchema, Clone, Copy, Default)]
+pub struct Quota {
+    pub max_nodes: Option<u64>,
+    pub max_triples: Option<u64>,
+    pub max_tables: Option<u64>,
+}
+impl Quota {
+    /// Fails when `count` items already reach the limit, so none can be added.
+    pub fn check(limit: Option<u64>, resource: &'static str, count: u64) -> Result<(), QuotaExceeded> {
+        match limit {
+            Some(limit) if count >= limit => Err(QuotaExceeded { resource, limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct QuotaExceeded {
+    pub resource: &'static str,
+    pub limit: u64,
+}
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Quota exceeded: this setting is limited to {} {}", self.limit, self.resource)
+    }
+}
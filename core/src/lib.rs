@@ -0,0 +1,48 @@
+//! Core of grpg: the models, the metadata store and the graph of each
+//! setting, with the evaluation of its tables. The `api` crate serves them
+//! over HTTP, other services can embed them in-process.
+//!
+//! - [`db::base::Store`] keeps users, settings, table definitions and the
+//!   other metadata, in SQLite ([`db::base::Store::open`]) or in memory
+//!   ([`db::base::Store::in_memory`]).
+//! - [`db::graph::GraphBackend`] keeps the nodes, predicates and triples of
+//!   every setting, in Kuzu ([`db::graph::KuzuBackend`], behind the default
+//!   `kuzu` feature) or in memory ([`db::graph::MemoryBackend`]).
+//! - [`db::graph::GraphManager`] edits and reads the graph of one setting,
+//!   enforcing its quota and publishing its changes to [`db::events::Events`].
+//! - [`db::error::DbError`] tells why an operation of either failed.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use grpg_core::db::base::Store;
+//! use grpg_core::db::error::DbError;
+//! use grpg_core::db::events::Events;
+//! use grpg_core::db::graph::{GraphManager, MemoryBackend};
+//! use grpg_core::db::models::{Filter, Quota, TableDefinition, Triple};
+//!
+//! # async fn example() -> Result<(), DbError> {
+//! let events = Arc::new(Events::new());
+//! let store = Store::in_memory(events.clone(), Quota::default());
+//! let backend = MemoryBackend::new();
+//!
+//! let user_id = store.add_user("ana".to_string(), "secret")?;
+//! let setting = store.user_settings(user_id).add_setting("Family".to_string(), None)?;
+//! let graph = GraphManager { backend: &backend, setting, events: &events, store: &store };
+//!
+//! let parent = graph.node_create("Maria".to_string())?;
+//! let child = graph.node_create("Ana".to_string())?;
+//! let parent_of = graph.predicate_create("parent of")?;
+//! graph.triple_create(Triple { subject_id: parent, predicate_id: parent_of.id, object_id: child })?;
+//!
+//! let table = TableDefinition {
+//!     label: "People".to_string(),
+//!     filter: Filter { node_id: None, predicate: None, direction: None },
+//!     columns: Vec::new(),
+//! };
+//! let rows = graph.table_rows(table).await?;
+//! assert_eq!(rows.len(), 2);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod db;